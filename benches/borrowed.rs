@@ -0,0 +1,38 @@
+//! Compare borrowed parsing with `parse_ort` on a large document where a few
+//! cells need unescaping
+//!
+//! Run with `cargo bench --bench borrowed`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+
+const ROWS: usize = 100_000;
+
+fn document() -> String {
+    let mut content = String::from("users:id,name,email,note,tags:\n");
+    for row in 0..ROWS {
+        let note = if row % 10 == 0 { "escaped\\, note" } else { "plain note" };
+        content.push_str(&format!("{},user {},user{}@example.com,{},[a,b,c]\n", row, row, row, note));
+    }
+    content
+}
+
+fn parse(c: &mut Criterion) {
+    let content = document();
+
+    let mut group = c.benchmark_group("parse 100k rows");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(content.len() as u64));
+    group.bench_function("parse_ort", |b| b.iter(|| ort_rs::parse_ort(black_box(&content)).unwrap()));
+    group.bench_function("parse_ort_borrowed", |b| b.iter(|| ort_rs::parse_ort_borrowed(black_box(&content)).unwrap()));
+    group.bench_function("parse_ort_borrowed, one field read", |b| {
+        b.iter(|| {
+            let value = ort_rs::parse_ort_borrowed(black_box(&content)).unwrap();
+            value["users"][ROWS / 2]["email"].as_str().map(str::len)
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,31 @@
+//! Compare decoding a 100k-row table from MessagePack with parsing it from ORT
+//!
+//! Run with `cargo bench --bench msgpack --features msgpack`.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+
+const ROWS: usize = 100_000;
+
+fn table() -> String {
+    let mut content = String::from("users:id,name,active,tags,pos(lat,lng):\n");
+    for row in 0..ROWS {
+        content.push_str(&format!("{},user {},{},[a,b,c],({}.5,{}.25)\n", row, row, row % 2 == 0, row, row));
+    }
+    content
+}
+
+fn decode(c: &mut Criterion) {
+    let content = table();
+    let bytes = ort_rs::parse_ort(&content).unwrap().to_msgpack();
+
+    let mut group = c.benchmark_group("decode 100k rows");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(ROWS as u64));
+    group.bench_function("parse_ort", |b| b.iter(|| ort_rs::parse_ort(black_box(&content)).unwrap()));
+    group.bench_function("from_msgpack", |b| b.iter(|| ort_rs::from_msgpack(black_box(&bytes)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
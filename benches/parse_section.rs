@@ -0,0 +1,46 @@
+//! Compare `parse_section` with `parse_ort` for one small section of a large document
+//!
+//! Run with `cargo bench --bench parse_section`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SECTIONS: usize = 12;
+const ROWS: usize = 50_000;
+
+fn main() {
+    let mut content = String::new();
+    for section in 0..SECTIONS {
+        content.push_str(&format!("section{}:id,name,tags,pos(lat,lng):\n", section));
+        for row in 0..ROWS {
+            content.push_str(&format!("{},user {},[a,b,c],({}.5,{}.25)\n", row, row, row, row));
+        }
+        content.push('\n');
+    }
+    content.push_str("meta:key,value:\n");
+    for row in 0..10 {
+        content.push_str(&format!("key{},{}\n", row, row));
+    }
+    println!("document: {} MB, {} sections", content.len() / 1_000_000, SECTIONS + 1);
+
+    let full = time(|| black_box(ort_rs::parse_ort(&content).unwrap()));
+    println!("{:<36}{:>10.1?}", "parse_ort", full);
+
+    let cases = [("first, 50000 rows", "section0"), ("last, 10 rows", "meta"), ("absent", "missing")];
+    for (case, key) in cases {
+        let lazy = time(|| black_box(ort_rs::parse_section(&content, key).unwrap()));
+        println!("{:<36}{:>10.1?}", format!("parse_section ({})", case), lazy);
+    }
+}
+
+/// Best time of a few runs
+fn time<T>(mut f: impl FnMut() -> T) -> Duration {
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
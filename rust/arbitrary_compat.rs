@@ -0,0 +1,86 @@
+use crate::ort_value::{Map, OrtValue};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Nesting depth of the values `OrtValue::arbitrary` builds
+pub const DEFAULT_MAX_DEPTH: u8 = 4;
+
+// Characters with a meaning in ORT, picked often so escaping gets exercised
+const SPECIAL_CHARS: &[char] = &[',', '(', ')', '[', ']', '\\', ':', '"', '#', ' ', '\t', '\n', '\r'];
+
+/// OrtValue built by `Arbitrary` with arrays and objects nested at most
+/// `MAX_DEPTH` levels deep
+///
+/// Every variant is generated. Strings and object keys mix arbitrary
+/// characters with ones that are special in ORT, such as delimiters,
+/// parentheses, brackets and backslashes.
+///
+/// # Example
+/// ```
+/// use arbitrary::{Arbitrary, Unstructured};
+/// use ort_rs::{BoundedOrtValue, OrtValue};
+///
+/// fn depth(value: &OrtValue) -> usize {
+///     match value {
+///         OrtValue::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+///         OrtValue::Object(obj) => 1 + obj.values().map(depth).max().unwrap_or(0),
+///         _ => 0,
+///     }
+/// }
+///
+/// let bytes: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+/// let mut u = Unstructured::new(&bytes);
+/// while !u.is_empty() {
+///     let BoundedOrtValue(value) = BoundedOrtValue::<2>::arbitrary(&mut u).unwrap();
+///     assert!(depth(&value) <= 2);
+/// }
+///
+/// let value = OrtValue::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+/// assert!(depth(&value) <= ort_rs::arbitrary_compat::DEFAULT_MAX_DEPTH as usize);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoundedOrtValue<const MAX_DEPTH: u8>(pub OrtValue);
+
+impl<'a, const MAX_DEPTH: u8> Arbitrary<'a> for BoundedOrtValue<MAX_DEPTH> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(BoundedOrtValue(arbitrary_value(u, MAX_DEPTH)?))
+    }
+}
+
+impl<'a> Arbitrary<'a> for OrtValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_value(u, DEFAULT_MAX_DEPTH)
+    }
+}
+
+fn arbitrary_value(u: &mut Unstructured, depth: u8) -> Result<OrtValue> {
+    // Arrays and objects only while there is depth left
+    let variants = if depth == 0 { 4 } else { 6 };
+    Ok(match u.choose_index(variants)? {
+        0 => OrtValue::Null,
+        1 => OrtValue::Bool(u.arbitrary()?),
+        2 => OrtValue::Number(u.arbitrary()?),
+        3 => OrtValue::String(arbitrary_string(u)?),
+        4 => {
+            let len = u.int_in_range(0..=4)?;
+            OrtValue::Array((0..len).map(|_| arbitrary_value(u, depth - 1)).collect::<Result<_>>()?)
+        }
+        _ => {
+            let len = u.int_in_range(0..=4)?;
+            let mut obj = Map::new();
+            for _ in 0..len {
+                obj.insert(arbitrary_string(u)?, arbitrary_value(u, depth - 1)?);
+            }
+            OrtValue::Object(obj)
+        }
+    })
+}
+
+fn arbitrary_string(u: &mut Unstructured) -> Result<String> {
+    let len = u.int_in_range(0..=12)?;
+    (0..len)
+        .map(|_| match u.ratio(1, 2)? {
+            true => u.choose(SPECIAL_CHARS).copied(),
+            false => u.arbitrary::<char>(),
+        })
+        .collect()
+}
@@ -0,0 +1,502 @@
+// File handling shared by the converters. Paths ending in `.gz` are read and
+// written gzip-compressed when built with the `compression` feature. An input
+// path of `-` reads standard input, and its output goes to standard output
+// unless an output directory is given.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+/// Exit code for invalid arguments
+pub const EXIT_USAGE: i32 = 1;
+/// Exit code for input that fails to parse or convert
+pub const EXIT_INVALID: i32 = 2;
+/// Exit code for a file that can't be read or written
+pub const EXIT_IO: i32 = 3;
+
+/// Path that reads standard input, or as an output writes standard output
+const STDIN: &str = "-";
+
+/// Check if an input path means standard input
+pub fn is_stdin(path: &Path) -> bool {
+    path == Path::new(STDIN)
+}
+
+/// Parse the command line, exiting with `EXIT_USAGE` if it is invalid
+///
+/// `--help` and `--version` print to standard output and exit with 0.
+#[cfg(feature = "cli")]
+pub fn parse_args<T: clap::Parser>() -> T {
+    T::try_parse().unwrap_or_else(|e| {
+        let code = if e.use_stderr() { EXIT_USAGE } else { 0 };
+        let _ = e.print();
+        process::exit(code);
+    })
+}
+
+/// Read an input file, decompressing `.gz` files, or standard input for `-`
+///
+/// Trailing `\r` and whitespace are trimmed from every line.
+pub fn read_input(path: &Path) -> io::Result<String> {
+    read_raw_input(path).map(|content| trim_lines(&content))
+}
+
+/// Read an input like `read_input`, but exactly as it is, for comparing with
+/// what would be written back
+pub fn read_raw_input(path: &Path) -> io::Result<String> {
+    if is_stdin(path) {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        Ok(content)
+    } else if is_gzip(path) {
+        read_gzip(path)
+    } else {
+        fs::read_to_string(path)
+    }
+}
+
+/// Trim the end of each line, keeping whitespace escaped by a backslash
+fn trim_lines(content: &str) -> String {
+    let lines: Vec<&str> = content
+        .split('\n')
+        .map(|line| {
+            let trimmed = line.trim_end();
+            let backslashes = trimmed.len() - trimmed.trim_end_matches('\\').len();
+            match line[trimmed.len()..].chars().next() {
+                Some(ch) if backslashes % 2 == 1 => &line[..trimmed.len() + ch.len_utf8()],
+                _ => trimmed,
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Write an output file, compressing `.gz` files
+fn write_output(path: &Path, content: &str) -> io::Result<()> {
+    if is_gzip(path) {
+        return write_gzip(path, content);
+    }
+    fs::write(path, content)
+}
+
+/// Write converted output to a file, or to standard output for `None`
+pub fn write_target(target: Option<&Path>, content: &str) -> io::Result<()> {
+    let Some(path) = target else {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(content.as_bytes())?;
+        if !content.ends_with('\n') {
+            stdout.write_all(b"\n")?;
+        }
+        return stdout.flush();
+    };
+    write_output(path, content)
+}
+
+/// Write converted output as `write_target` does, first creating the
+/// directories of a file in an output directory
+pub fn write_converted(target: Option<&Path>, content: &str, output_dir: bool) -> Result<(), Failure> {
+    let created = match target.and_then(Path::parent) {
+        Some(parent) if output_dir && !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    };
+    created.and_then(|()| write_target(target, content)).map_err(|e| {
+        let target = target.unwrap_or(Path::new("stdout"));
+        Failure::io(format!("Failed to write file '{}': {}", target.display(), e))
+    })
+}
+
+/// Output file for a converted input, or `None` for standard output
+///
+/// Without `output`, a file converts next to itself and standard input to
+/// standard output. An `output` of `-` is standard output. Otherwise it is the
+/// directory to convert into when `dir` is set or it is an existing directory,
+/// where standard input converts to `stdin.<extension>`, and else the file to
+/// write.
+pub fn output_target(input: &Path, output: Option<&Path>, dir: bool, extension: &str) -> Option<PathBuf> {
+    match output {
+        Some(path) if is_stdin(path) => None,
+        Some(path) if dir || path.is_dir() => Some(output_path(input, Some(path), extension)),
+        Some(path) => Some(path.to_path_buf()),
+        None if is_stdin(input) => None,
+        None => Some(output_path(input, None, extension)),
+    }
+}
+
+/// File name without its extension, ignoring a trailing `.gz`
+pub fn file_stem(path: &Path) -> String {
+    if is_stdin(path) {
+        return "stdin".to_string();
+    }
+    let path = if is_gzip(path) { path.file_stem().map(Path::new).unwrap_or(path) } else { path };
+    path.file_stem().unwrap_or_default().to_string_lossy().to_string()
+}
+
+/// Output path for a converted file (`data.ort.gz` converts to `data.json.gz`)
+fn output_path(input: &Path, output_dir: Option<&Path>, extension: &str) -> PathBuf {
+    let extension = if is_gzip(input) {
+        format!("{}.gz", extension)
+    } else {
+        extension.to_string()
+    };
+
+    match output_dir {
+        Some(dir) => dir.join(format!("{}.{}", file_stem(input), extension)),
+        None if is_gzip(input) => input.with_extension("").with_extension(extension),
+        None => input.with_extension(extension),
+    }
+}
+
+/// Expand an input argument into paths, matching `*` and `?` in its file name
+/// for shells that leave patterns to the program
+///
+/// Arguments naming an existing file, and patterns that match nothing, are
+/// kept as they are, so reading them reports the missing file.
+pub fn expand_input(arg: &str) -> Vec<PathBuf> {
+    let path = Path::new(arg);
+    let pattern = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    if path.exists() || !pattern.contains(['*', '?']) {
+        return vec![path.to_path_buf()];
+    }
+
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let mut matches: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(|name| matches_pattern(pattern, name)))
+        .map(|entry| path.with_file_name(entry.file_name()))
+        .collect();
+    matches.sort();
+
+    if matches.is_empty() {
+        return vec![path.to_path_buf()];
+    }
+    matches
+}
+
+/// A file to convert
+pub struct Input {
+    pub path: PathBuf,
+    /// Directory of the file within the directory it was found in, which its
+    /// output goes into under an output directory
+    pub subdir: PathBuf,
+}
+
+impl Input {
+    /// Output argument for this file, within `output` as its directory is
+    /// within the one it was found in
+    pub fn output(&self, output: Option<&Path>) -> Option<PathBuf> {
+        match output {
+            Some(path) if !is_stdin(path) && !self.subdir.as_os_str().is_empty() => Some(path.join(&self.subdir)),
+            _ => output.map(Path::to_path_buf),
+        }
+    }
+}
+
+/// Expand input arguments as `expand_input` does, or standard input without
+/// any
+///
+/// With `recursive`, directories are searched for files ending in
+/// `.<extension>` or `.<extension>.gz`, skipping hidden ones, and each is
+/// given its directory within the one searched.
+pub fn collect_inputs(args: &[String], recursive: bool, extension: &str) -> Vec<Input> {
+    if args.is_empty() {
+        return vec![Input { path: PathBuf::from(STDIN), subdir: PathBuf::new() }];
+    }
+
+    let mut inputs = vec![];
+    for path in args.iter().flat_map(|arg| expand_input(arg)) {
+        if recursive && path.is_dir() {
+            find_files(&path, &path, extension, &mut inputs);
+        } else {
+            inputs.push(Input { path, subdir: PathBuf::new() });
+        }
+    }
+    inputs
+}
+
+fn find_files(root: &Path, dir: &Path, extension: &str, inputs: &mut Vec<Input>) {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir).into_iter().flatten().flatten().map(|entry| entry.path()).collect();
+    entries.sort();
+
+    let suffixes = [format!(".{}", extension), format!(".{}.gz", extension)];
+    for path in entries {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            find_files(root, &path, extension, inputs);
+        } else if suffixes.iter().any(|suffix| name.ends_with(suffix.as_str())) {
+            let subdir = dir.strip_prefix(root).unwrap_or(Path::new("")).to_path_buf();
+            inputs.push(Input { path, subdir });
+        }
+    }
+}
+
+/// Check that no two inputs convert to the same output file, which would
+/// leave only one of them
+pub fn check_outputs<F>(inputs: &[Input], target: F) -> Result<(), Failure>
+where
+    F: Fn(&Input) -> Option<PathBuf>,
+{
+    let mut sources: HashMap<PathBuf, &Path> = HashMap::new();
+    for input in inputs {
+        let Some(output) = target(input) else {
+            continue;
+        };
+        if let Some(other) = sources.insert(output.clone(), &input.path) {
+            return Err(Failure::usage(format!(
+                "Inputs '{}' and '{}' both convert to '{}'",
+                other.display(),
+                input.path.display(),
+                output.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Convert every input on `jobs` threads, recording the results in input
+/// order
+///
+/// After a failure with `fail_fast`, inputs that haven't started are skipped.
+/// When standard error is a terminal, the number of files done is shown
+/// there as they finish.
+pub fn convert_all<F>(inputs: &[Input], jobs: usize, fail_fast: bool, convert: F) -> Batch
+where
+    F: Fn(&Input) -> Result<(), Failure> + Sync,
+{
+    let results: Mutex<Vec<Option<Result<(), Failure>>>> = Mutex::new(inputs.iter().map(|_| None).collect());
+    let next = AtomicUsize::new(0);
+    let done = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let progress = inputs.len() > 1 && io::stderr().is_terminal();
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, inputs.len().max(1)) {
+            scope.spawn(|| {
+                while !stop.load(Ordering::Relaxed) {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(input) = inputs.get(index) else {
+                        break;
+                    };
+                    let result = convert(input);
+                    if result.is_err() && fail_fast {
+                        stop.store(true, Ordering::Relaxed);
+                    }
+                    results.lock().unwrap()[index] = Some(result);
+
+                    let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                    if progress {
+                        eprint!("\r{}/{} files", done, inputs.len());
+                    }
+                }
+            });
+        }
+    });
+    if progress {
+        eprint!("\r\x1b[K");
+    }
+
+    let mut batch = Batch::default();
+    for result in results.into_inner().unwrap().into_iter().flatten() {
+        batch.record(result);
+    }
+    batch
+}
+
+/// Match a file name against a pattern where `*` is any run of characters and
+/// `?` is one character; hidden files only match patterns starting with `.`
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some('*'), _) => matches(&pattern[1..], name) || (!name.is_empty() && matches(pattern, &name[1..])),
+            (Some('?'), Some(_)) => matches(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p == n => matches(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches(&pattern, &name)
+}
+
+/// Why an input failed, and the exit code that gives
+pub struct Failure {
+    pub code: i32,
+    pub message: String,
+}
+
+impl Failure {
+    /// Arguments that can't be carried out
+    pub fn usage(message: String) -> Self {
+        Failure { code: EXIT_USAGE, message }
+    }
+
+    /// Input that failed to parse or convert
+    pub fn invalid(message: String) -> Self {
+        Failure { code: EXIT_INVALID, message }
+    }
+
+    /// A file that couldn't be read or written
+    pub fn io(message: String) -> Self {
+        Failure { code: EXIT_IO, message }
+    }
+}
+
+/// Tally of a run over several inputs, reported once all have been tried
+#[derive(Default)]
+pub struct Batch {
+    converted: usize,
+    failures: Vec<Failure>,
+}
+
+impl Batch {
+    /// Record how one input went, giving `false` if it failed
+    pub fn record(&mut self, result: Result<(), Failure>) -> bool {
+        match result {
+            Ok(()) => {
+                self.converted += 1;
+                true
+            }
+            Err(failure) => {
+                self.failures.push(failure);
+                false
+            }
+        }
+    }
+
+    /// Print the failures, and a summary when there was more than one input,
+    /// giving the exit code
+    ///
+    /// That is the highest code of the failures, so a file that couldn't be
+    /// read outweighs one that didn't parse.
+    pub fn finish(self, print_failures: bool) -> i32 {
+        if print_failures {
+            for failure in &self.failures {
+                eprintln!("{}", failure.message);
+            }
+        }
+        if self.converted + self.failures.len() > 1 {
+            eprintln!("{} converted, {} failed", self.converted, self.failures.len());
+        }
+        self.failures.iter().map(|failure| failure.code).max().unwrap_or(0)
+    }
+}
+
+/// The table a converter writes: the named section, or else a top-level
+/// table or the only section
+pub fn section_table<'a>(value: &'a ort_rs::OrtValue, section: Option<&str>) -> Result<&'a ort_rs::OrtValue, String> {
+    match (value, section) {
+        (_, Some(section)) => value.get(section).ok_or_else(|| format!("Section '{}' not found", section)),
+        (ort_rs::OrtValue::Object(obj), None) if obj.len() == 1 => Ok(obj.values().next().unwrap()),
+        (ort_rs::OrtValue::Object(obj), None) => {
+            let mut keys: Vec<&str> = obj.keys().map(String::as_str).collect();
+            keys.sort();
+            Err(format!("The file has the sections {}; choose one with --section", keys.join(", ")))
+        }
+        (table, None) => Ok(table),
+    }
+}
+
+/// Parse a `--delimiter` argument, exiting with the usage if it is invalid
+pub fn delimiter_arg(arg: &str, usage: &str) -> char {
+    parse_delimiter(arg).unwrap_or_else(|| {
+        eprintln!("Invalid delimiter '{}'\n{}", arg, usage);
+        process::exit(1);
+    })
+}
+
+/// Parse a `--delimiter` argument for clap
+#[cfg(feature = "cli")]
+pub fn delimiter(arg: &str) -> Result<char, String> {
+    parse_delimiter(arg).ok_or_else(|| "expected tab or a single punctuation character other than :\\()[]\".+-_#".to_string())
+}
+
+/// Parse a `--csv-delimiter` argument for clap: a single ASCII character, or
+/// `tab` / `\t`
+#[cfg(feature = "cli")]
+pub fn csv_delimiter(arg: &str) -> Result<u8, String> {
+    match arg {
+        "tab" | "\\t" => Ok(b'\t'),
+        _ if arg.len() == 1 && arg.is_ascii() && arg != "\"" && arg != "\n" => Ok(arg.as_bytes()[0]),
+        _ => Err("expected a single ASCII character other than \", or tab".to_string()),
+    }
+}
+
+/// What `--nested` makes of nested objects and arrays in CSV
+#[cfg(all(feature = "cli", feature = "csv"))]
+#[derive(Clone, Copy, clap::ValueEnum)]
+pub enum NestedArg {
+    /// Reject them when writing, and keep column names as they are when reading
+    Error,
+    /// Dot-separated column names such as `address.city`
+    Dotted,
+}
+
+#[cfg(all(feature = "cli", feature = "csv"))]
+impl From<NestedArg> for ort_rs::CsvNested {
+    fn from(arg: NestedArg) -> Self {
+        match arg {
+            NestedArg::Error => ort_rs::CsvNested::Error,
+            NestedArg::Dotted => ort_rs::CsvNested::Dotted,
+        }
+    }
+}
+
+/// Parse a delimiter: a single character that can separate values, or `tab` / `\t`
+fn parse_delimiter(arg: &str) -> Option<char> {
+    if arg == "tab" || arg == "\\t" {
+        return Some('\t');
+    }
+
+    let mut chars = arg.chars();
+    match (chars.next(), chars.next()) {
+        (Some(ch), None) if ort_rs::escape::is_valid_delimiter(ch) => Some(ch),
+        _ => None,
+    }
+}
+
+fn is_gzip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
+
+#[cfg(feature = "compression")]
+fn read_gzip(path: &Path) -> io::Result<String> {
+    use std::io::Read;
+
+    let mut content = String::new();
+    flate2::read::GzDecoder::new(fs::File::open(path)?).read_to_string(&mut content)?;
+    Ok(content)
+}
+
+#[cfg(feature = "compression")]
+fn write_gzip(path: &Path, content: &str) -> io::Result<()> {
+    use std::io::Write;
+
+    let mut encoder = flate2::write::GzEncoder::new(fs::File::create(path)?, flate2::Compression::default());
+    encoder.write_all(content.as_bytes())?;
+    encoder.finish()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "compression"))]
+fn read_gzip(_path: &Path) -> io::Result<String> {
+    Err(io::Error::other("gzip files require the `compression` feature"))
+}
+
+#[cfg(not(feature = "compression"))]
+fn write_gzip(_path: &Path, _content: &str) -> io::Result<()> {
+    Err(io::Error::other("gzip files require the `compression` feature"))
+}
@@ -0,0 +1,20 @@
+// Converts CSV to ORT, as `ort from-csv` does
+
+use clap::Parser;
+use std::process;
+
+#[allow(dead_code)]
+mod common;
+mod from_csv;
+
+#[derive(Parser)]
+#[command(name = "csv2ort", version, about = "Convert a CSV file to an ORT table")]
+struct Cli {
+    #[command(flatten)]
+    args: from_csv::FromCsvArgs,
+}
+
+fn main() {
+    let cli: Cli = common::parse_args();
+    process::exit(from_csv::run(cli.args));
+}
@@ -0,0 +1,82 @@
+// `ort from-csv`, also run by `csv2ort`
+
+use crate::common::{self, Failure, NestedArg};
+use clap::Args;
+use ort_rs::{generate_ort_with, CsvOptions, GenerateOptions, Map, OrtValue};
+use std::path::PathBuf;
+
+#[derive(Args)]
+pub struct FromCsvArgs {
+    /// CSV file to convert, or `-` for standard input, which is also read
+    /// when no file is given
+    #[arg(value_name = "FILE", default_value = "-")]
+    input: PathBuf,
+    /// Output file, directory to write <name>.ort into, or `-` for standard output
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Section to hold the table, named after the input file by default
+    #[arg(short, long, value_name = "NAME")]
+    key: Option<String>,
+    /// Read the first row as data, naming the columns column1, column2, ...
+    #[arg(long)]
+    no_header: bool,
+    /// What dotted column names such as `address.city` become
+    #[arg(long, value_enum, default_value_t = NestedArg::Error)]
+    nested: NestedArg,
+    /// Keep every cell as a string instead of reading numbers, booleans and
+    /// empty cells as such
+    #[arg(long)]
+    no_infer: bool,
+    /// CSV cell delimiter, such as ';' or 'tab'
+    #[arg(long, value_name = "CHAR", default_value = ",", value_parser = common::csv_delimiter)]
+    csv_delimiter: u8,
+    /// ORT value delimiter, such as '|' or 'tab'
+    #[arg(long, value_name = "CHAR", value_parser = common::delimiter)]
+    delimiter: Option<char>,
+}
+
+/// Convert the input, giving the exit code
+pub fn run(args: FromCsvArgs) -> i32 {
+    match convert(&args) {
+        Ok(()) => 0,
+        Err(failure) => {
+            eprintln!("{}", failure.message);
+            failure.code
+        }
+    }
+}
+
+/// Convert the input, giving the reason if it fails
+fn convert(args: &FromCsvArgs) -> Result<(), Failure> {
+    let input = args.input.as_path();
+    let csv_options = CsvOptions {
+        delimiter: args.csv_delimiter,
+        has_headers: !args.no_header,
+        nested: args.nested.into(),
+        infer_types: !args.no_infer,
+    };
+    let mut options = GenerateOptions::default();
+    if let Some(delimiter) = args.delimiter {
+        options.delimiter = delimiter;
+    }
+
+    // Read input file
+    let content = common::read_input(input).map_err(|e| Failure::io(format!("Failed to read file '{}': {}", input.display(), e)))?;
+
+    // Parse CSV to OrtValue
+    let table = ort_rs::from_csv_str_with(&content, &csv_options)
+        .map_err(|e| Failure::invalid(format!("Failed to parse CSV in '{}': {}", input.display(), e.message)))?;
+
+    // Put the table in its section
+    let key = args.key.clone().unwrap_or_else(|| common::file_stem(input));
+    let mut obj = Map::new();
+    obj.insert(key, table);
+
+    // Generate ORT
+    let ort_string = generate_ort_with(&OrtValue::Object(obj), &options)
+        .map_err(|e| Failure::invalid(format!("Failed to convert '{}': {}", input.display(), e.message)))?;
+
+    // Determine output path and write output file
+    let output_path = common::output_target(input, args.output.as_deref(), false, "ort");
+    common::write_converted(output_path.as_deref(), &ort_string, false)
+}
@@ -0,0 +1,123 @@
+// `ort from-json`, also run by `json2ort`
+
+use crate::common::{self, Failure, Input};
+use clap::Args;
+use ort_rs::{generate_ort_with, GenerateOptions, Map, OrtValue};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct FromJsonArgs {
+    /// JSON files to convert, or `-` for standard input, which is also read
+    /// when no file is given
+    #[arg(value_name = "FILE")]
+    inputs: Vec<String>,
+    /// Output file, directory to write <name>.ort into, or `-` for standard output
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Convert the .json files in directories and their subdirectories,
+    /// mirroring them under the output directory
+    #[arg(short, long)]
+    recursive: bool,
+    /// Number of files to convert at once
+    #[arg(short, long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: u16,
+    /// Stop at the first file that fails
+    #[arg(long)]
+    fail_fast: bool,
+    /// Read JSON Lines, one value per line, into a table; files whose first
+    /// line is a JSON value of its own but aren't one as a whole are read
+    /// this way without it
+    #[arg(long)]
+    ndjson: bool,
+    /// Put the converted value in a section with this name
+    #[arg(short, long, value_name = "NAME")]
+    key: Option<String>,
+    /// Value delimiter, such as '|' or 'tab'
+    #[arg(long, value_name = "CHAR", value_parser = common::delimiter)]
+    delimiter: Option<char>,
+}
+
+/// Options shared by every input
+struct Settings<'a> {
+    output: Option<&'a Path>,
+    output_dir: bool,
+    ndjson: bool,
+    key: Option<&'a str>,
+    options: GenerateOptions,
+}
+
+/// Convert the inputs, giving the exit code
+pub fn run(args: FromJsonArgs) -> i32 {
+    let inputs = common::collect_inputs(&args.inputs, args.recursive, "json");
+    let output_dir = inputs.len() > 1 || args.recursive;
+    let mut options = GenerateOptions::default();
+    if let Some(delimiter) = args.delimiter {
+        options.delimiter = delimiter;
+    }
+
+    let settings = Settings {
+        output: args.output.as_deref(),
+        output_dir,
+        ndjson: args.ndjson,
+        key: args.key.as_deref(),
+        options,
+    };
+
+    if let Err(failure) = common::check_outputs(&inputs, |input| output_target(input, &settings)) {
+        eprintln!("{}", failure.message);
+        return failure.code;
+    }
+
+    let batch = common::convert_all(&inputs, args.jobs.into(), args.fail_fast, |input| convert(input, &settings));
+    batch.finish(true)
+}
+
+/// Convert one input, giving the reason if it fails
+fn convert(file: &Input, settings: &Settings) -> Result<(), Failure> {
+    let input = file.path.as_path();
+
+    // Read input file
+    let content = common::read_input(input).map_err(|e| Failure::io(format!("Failed to read file '{}': {}", input.display(), e)))?;
+
+    // Parse JSON, or JSON Lines, into an OrtValue
+    let ort_value = if settings.ndjson {
+        ndjson(input, &content, settings.key)?
+    } else {
+        match serde_json::from_str::<serde_json::Value>(&content) {
+            Ok(json_value) => {
+                let ort_value = OrtValue::from(json_value);
+                match settings.key {
+                    Some(key) => OrtValue::Object(Map::from_iter([(key.to_string(), ort_value)])),
+                    None => ort_value,
+                }
+            }
+            Err(_) if is_ndjson(&content) => ndjson(input, &content, settings.key)?,
+            Err(e) => return Err(Failure::invalid(format!("Failed to parse JSON in '{}': {}", input.display(), e))),
+        }
+    };
+
+    // Generate ORT
+    let ort_string = generate_ort_with(&ort_value, &settings.options)
+        .map_err(|e| Failure::invalid(format!("Failed to convert '{}': {}", input.display(), e.message)))?;
+
+    // Write output file
+    common::write_converted(output_target(file, settings).as_deref(), &ort_string, settings.output_dir)
+}
+
+fn output_target(input: &Input, settings: &Settings) -> Option<PathBuf> {
+    common::output_target(&input.path, input.output(settings.output).as_deref(), settings.output_dir, "ort")
+}
+
+fn ndjson(input: &Path, content: &str, key: Option<&str>) -> Result<OrtValue, Failure> {
+    ort_rs::ndjson_to_ort(content.as_bytes(), key)
+        .map_err(|e| Failure::invalid(format!("Failed to parse JSON Lines in '{}': line {}: {}", input.display(), e.line, e.message)))
+}
+
+/// Whether content that isn't one JSON document is JSON Lines: its first line
+/// that isn't blank is a JSON value of its own
+fn is_ndjson(content: &str) -> bool {
+    content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .is_some_and(|line| serde_json::from_str::<serde_json::Value>(line).is_ok())
+}
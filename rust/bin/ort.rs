@@ -0,0 +1,731 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use common::{EXIT_INVALID, EXIT_IO, EXIT_USAGE};
+use ort_rs::{Change, ConflictPolicy, FieldSpec, GenerateOptions, OrtDocument, OrtError, OrtValue, ParseOptions, RowMatch, SaxHandler, SaxParser, Schema, TrailingNewline};
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[allow(dead_code)]
+mod common;
+#[cfg(feature = "csv")]
+mod from_csv;
+#[cfg(feature = "serde_json")]
+mod from_json;
+#[cfg(feature = "csv")]
+mod to_csv;
+#[cfg(feature = "serde_json")]
+mod to_json;
+
+/// Convert, check and query ORT files
+///
+/// Exits with 0 on success, 1 for invalid arguments, 2 for input that fails
+/// to parse and 3 for a file that can't be read or written.
+#[derive(Parser)]
+#[command(name = "ort", version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert ORT files to JSON
+    #[cfg(feature = "serde_json")]
+    ToJson(to_json::ToJsonArgs),
+    /// Convert JSON files to ORT
+    #[cfg(feature = "serde_json")]
+    FromJson(from_json::FromJsonArgs),
+    /// Convert a section of an ORT file, or each of them, to CSV
+    #[cfg(feature = "csv")]
+    ToCsv(to_csv::ToCsvArgs),
+    /// Convert a CSV file to an ORT table
+    #[cfg(feature = "csv")]
+    FromCsv(from_csv::FromCsvArgs),
+    /// Check that files parse, printing the errors of each that doesn't
+    Validate(ValidateArgs),
+    /// Print the value at a dot-separated path, such as `users.0.name`
+    Query {
+        /// Print each element of an array on its own line
+        #[arg(long)]
+        array: bool,
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+        /// File to read, or `-` for standard input
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+        path: String,
+    },
+    /// Print the result of a query expression such as `users[?active == true].name`
+    Get {
+        /// Print JSON
+        #[arg(long)]
+        json: bool,
+        /// File to read, or `-` for standard input
+        #[arg(value_name = "FILE")]
+        input: PathBuf,
+        expression: String,
+    },
+    /// Write the deep merge of several files, later files taking priority
+    Merge {
+        /// Append arrays instead of replacing them
+        #[arg(long)]
+        array_append: bool,
+        /// Output file, or `-` for standard output
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+        /// Files to merge, or `-` for standard input
+        #[arg(required = true, value_name = "FILE")]
+        inputs: Vec<PathBuf>,
+    },
+    /// Rewrite files in canonical form, keeping their comments
+    Fmt {
+        /// Print a diff and exit with 1 for files that aren't formatted, changing nothing
+        #[arg(long)]
+        check: bool,
+        /// Print the formatted files instead of rewriting them
+        #[arg(long, conflicts_with = "check")]
+        stdout: bool,
+        /// Files or patterns such as `data/*.ort` to format, or `-` for standard input
+        #[arg(required = true, value_name = "FILE")]
+        inputs: Vec<String>,
+    },
+    /// Print the differences between two files, exiting with 1 if there are
+    /// any and 2 if a file can't be read or parsed
+    Diff {
+        /// Match the rows of tables by this field instead of by equality,
+        /// showing the changed cells of each
+        #[arg(short, long, value_name = "FIELD")]
+        key: Option<String>,
+        /// Print the changes as text or as a JSON array
+        #[arg(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+        /// Same as --format json
+        #[arg(long, hide = true, conflicts_with = "format")]
+        json: bool,
+        #[arg(value_name = "OLD")]
+        old: PathBuf,
+        #[arg(value_name = "NEW")]
+        new: PathBuf,
+    },
+}
+
+fn main() {
+    let cli: Cli = common::parse_args();
+    let code = match cli.command {
+        #[cfg(feature = "serde_json")]
+        Command::ToJson(args) => to_json::run(args),
+        #[cfg(feature = "serde_json")]
+        Command::FromJson(args) => from_json::run(args),
+        #[cfg(feature = "csv")]
+        Command::ToCsv(args) => to_csv::run(args),
+        #[cfg(feature = "csv")]
+        Command::FromCsv(args) => from_csv::run(args),
+        Command::Validate(args) => validate(&args),
+        Command::Query { array, json, input, path } => query(&input, &path, array, json),
+        Command::Get { json, input, expression } => get(&input, &expression, json),
+        Command::Merge { array_append, output, inputs } => merge(&inputs, output.as_deref(), array_append),
+        Command::Fmt { check, stdout, inputs } => fmt(&inputs, check, stdout),
+        Command::Diff { key, format, json, old, new } => diff(&old, &new, key, json || format == DiffFormat::Json),
+    };
+    process::exit(code);
+}
+
+#[derive(Args)]
+struct ValidateArgs {
+    /// Also reject duplicate keys, warnings and sections without rows
+    #[arg(long)]
+    strict: bool,
+    /// Print nothing, only exiting with the result
+    #[arg(short, long)]
+    quiet: bool,
+    /// Also check each file against a schema written in ORT
+    #[arg(long, value_name = "FILE")]
+    schema: Option<PathBuf>,
+    /// Reject nesting deeper than this many levels
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+    /// Reject files with more than this many rows
+    #[arg(long, value_name = "N")]
+    max_rows: Option<usize>,
+    /// Files or patterns such as `data/*.ort` to check, or `-` for standard input
+    #[arg(required = true, value_name = "FILE")]
+    inputs: Vec<String>,
+}
+
+/// Check that files parse, printing every error of each that doesn't
+///
+/// Exits with 2 if a file is invalid and 3 if a file could not be read.
+fn validate(args: &ValidateArgs) -> i32 {
+    let schema = match &args.schema {
+        Some(path) => {
+            let value = match load(path) {
+                Ok(value) => value,
+                Err(code) => return code,
+            };
+            match Schema::from_value(&value) {
+                Ok(schema) => Some(schema),
+                Err(e) => {
+                    eprintln!("{}: {}", path.display(), e);
+                    return EXIT_INVALID;
+                }
+            }
+        }
+        None => None,
+    };
+
+    let mut code = 0;
+    for path in args.inputs.iter().flat_map(|arg| common::expand_input(arg)) {
+        let content = match common::read_input(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                if !args.quiet {
+                    eprintln!("Failed to read file '{}': {}", path.display(), e);
+                }
+                code = EXIT_IO;
+                continue;
+            }
+        };
+
+        let problems = check(&path, &content, args, schema.as_ref());
+        if !args.quiet {
+            for problem in &problems {
+                eprintln!("{}", problem);
+            }
+        }
+        if !problems.is_empty() && code == 0 {
+            code = EXIT_INVALID;
+        }
+    }
+    code
+}
+
+/// Print the value at a dot-separated path, such as `users.0.name`
+///
+/// Strings are printed without quotes, and arrays and objects in their inline
+/// ORT form, or with `--json` as one line of JSON. Exits with 1 if the path
+/// doesn't exist.
+fn query(input: &Path, path: &str, array: bool, json: bool) -> i32 {
+    let value = match load(input) {
+        Ok(value) => value,
+        Err(code) => return code,
+    };
+
+    let Some(found) = value.get_path(path) else {
+        eprintln!("Path '{}' not found in '{}'", path, input.display());
+        return EXIT_USAGE;
+    };
+    let items = match found.as_array() {
+        Some(items) if array => items.iter().collect(),
+        _ => vec![found],
+    };
+    for item in items {
+        match print_value(item, json) {
+            Ok(text) => println!("{}", text),
+            Err(message) => {
+                eprintln!("{}", message);
+                return EXIT_USAGE;
+            }
+        }
+    }
+    0
+}
+
+/// Print the result of a query expression such as `users[?active == true].name`
+///
+/// The result is printed in its inline ORT form, or with `--json` as one line
+/// of JSON. Exits with 1 if the expression is invalid.
+fn get(input: &Path, expr: &str, json: bool) -> i32 {
+    let value = match load(input) {
+        Ok(value) => value,
+        Err(code) => return code,
+    };
+    let found = match ort_rs::query(&value, expr) {
+        Ok(found) => found,
+        Err(e) => {
+            eprintln!("{}", e);
+            return EXIT_USAGE;
+        }
+    };
+    match print_value(&found, json) {
+        Ok(text) => {
+            println!("{}", text);
+            0
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            EXIT_USAGE
+        }
+    }
+}
+
+/// Write the deep merge of several files, later files taking priority
+///
+/// Objects are merged key by key. Arrays are replaced, or with
+/// `--array-append` appended to. The result goes to standard output unless
+/// `-o` names a file.
+fn merge(inputs: &[PathBuf], output: Option<&Path>, append: bool) -> i32 {
+    let mut merged = OrtValue::default();
+    for input in inputs {
+        let value = match load(input) {
+            Ok(value) => value,
+            Err(code) => return code,
+        };
+        if append {
+            merged.merge_append(value);
+        } else {
+            merged.merge(value);
+        }
+    }
+
+    let options = GenerateOptions {
+        trailing_newline: TrailingNewline::Always,
+        ..Default::default()
+    };
+    let content = match ort_rs::generate_ort_with(&merged, &options) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Failed to merge: {}", e.message);
+            return EXIT_INVALID;
+        }
+    };
+    let output = output.filter(|path| !common::is_stdin(path));
+    if let Err(e) = common::write_target(output, &content) {
+        eprintln!("Failed to write file '{}': {}", output.unwrap_or(Path::new("stdout")).display(), e);
+        return EXIT_IO;
+    }
+    0
+}
+
+/// Format files, or with `--check` print how they would change
+///
+/// Standard input is printed rather than rewritten, and files that are
+/// already formatted aren't touched. Exits with the worst result: 1 if a file
+/// isn't formatted under `--check`, 2 if one is invalid and 3 if one could
+/// not be read or written.
+fn fmt(inputs: &[String], check: bool, stdout: bool) -> i32 {
+    inputs
+        .iter()
+        .flat_map(|arg| common::expand_input(arg))
+        .map(|path| format_file(&path, check, stdout))
+        .max()
+        .unwrap_or(0)
+}
+
+fn format_file(path: &Path, check: bool, stdout: bool) -> i32 {
+    // Compare against the file as it is, so line endings and trailing spaces count
+    let content = match common::read_raw_input(path) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Failed to read file '{}': {}", path.display(), e);
+            return EXIT_IO;
+        }
+    };
+    let options = parse_options(path);
+    let formatted = match OrtDocument::parse_with(&content, &options) {
+        Ok(doc) => doc.formatted(),
+        Err(e) => Err(e),
+    };
+    let formatted = match formatted {
+        Ok(formatted) => formatted,
+        Err(e) => {
+            eprintln!("{}", report(path, &content, e));
+            return EXIT_INVALID;
+        }
+    };
+
+    // Never write out a file whose data would change
+    if ort_rs::parse_ort_with(&formatted, &options).ok() != ort_rs::parse_ort_with(&content, &options).ok() {
+        eprintln!("{}: formatting would change the data, so the file was left as it is", path.display());
+        return EXIT_INVALID;
+    }
+
+    if check {
+        if formatted == content {
+            return 0;
+        }
+        print!("{}", unified_diff(path, &content, &formatted));
+        return EXIT_USAGE;
+    }
+    if stdout || common::is_stdin(path) {
+        print!("{}", formatted);
+        return 0;
+    }
+    if formatted != content {
+        if let Err(e) = common::write_target(Some(path), &formatted) {
+            eprintln!("Failed to write file '{}': {}", path.display(), e);
+            return EXIT_IO;
+        }
+    }
+    0
+}
+
+/// Cells of the largest line table `unified_diff` builds to find the fewest
+/// changed lines; past it the lines between the unchanged start and end are
+/// all shown as replaced
+const DIFF_TABLE_LIMIT: usize = 4_000_000;
+
+/// Write a unified diff of two texts, with three lines of context
+fn unified_diff(path: &Path, old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+
+    // Only the lines between a shared start and end need comparing
+    let start = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let end = old[start..].iter().rev().zip(new[start..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old_middle, new_middle) = (&old[start..old.len() - end], &new[start..new.len() - end]);
+
+    // Lines as (prefix, text, old index, new index), where the indexes count
+    // the lines before it
+    let mut lines: Vec<(char, &str, usize, usize)> = (0..start).map(|i| (' ', old[i], i, i)).collect();
+    if (old_middle.len() + 1).saturating_mul(new_middle.len() + 1) <= DIFF_TABLE_LIMIT {
+        lines.extend(changed_lines(old_middle, new_middle).into_iter().map(|(prefix, text, i, j)| (prefix, text, start + i, start + j)));
+    } else {
+        lines.extend(old_middle.iter().enumerate().map(|(i, text)| ('-', *text, start + i, start)));
+        lines.extend(new_middle.iter().enumerate().map(|(j, text)| ('+', *text, start + old_middle.len(), start + j)));
+    }
+    let (i, j) = (old.len() - end, new.len() - end);
+    lines.extend((0..end).map(|k| (' ', old[i + k], i + k, j + k)));
+
+    let mut out = format!("--- {0}\n+++ {0}\n", path.display());
+    let changed: Vec<usize> = (0..lines.len()).filter(|&k| lines[k].0 != ' ').collect();
+    let mut k = 0;
+    while k < changed.len() {
+        // Take changes until the context around them would no longer touch
+        let start = changed[k].saturating_sub(3);
+        while k + 1 < changed.len() && changed[k + 1] - changed[k] <= 7 {
+            k += 1;
+        }
+        let end = (changed[k] + 4).min(lines.len());
+        k += 1;
+
+        let hunk = &lines[start..end];
+        let old_count = hunk.iter().filter(|line| line.0 != '+').count();
+        let new_count = hunk.iter().filter(|line| line.0 != '-').count();
+        // An empty range names the line before it
+        let old_start = hunk[0].2 + usize::from(old_count > 0);
+        let new_start = hunk[0].3 + usize::from(new_count > 0);
+        out.push_str(&format!("@@ -{},{} +{},{} @@\n", old_start, old_count, new_start, new_count));
+        for (prefix, text, _, _) in hunk {
+            out.push(*prefix);
+            out.push_str(text);
+            if !text.ends_with('\n') {
+                out.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    out
+}
+
+/// Lines of the fewest changes turning `old` into `new`, from their longest
+/// common subsequence
+fn changed_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str, usize, usize)> {
+    // Longest common subsequence of lines, from the end
+    let width = new.len() + 1;
+    let mut common = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i * width + j] = match old[i] == new[j] {
+                true => common[(i + 1) * width + j + 1] + 1,
+                false => common[(i + 1) * width + j].max(common[i * width + j + 1]),
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i], i, j));
+            (i, j) = (i + 1, j + 1);
+        } else if j == new.len() || (i < old.len() && common[(i + 1) * width + j] >= common[i * width + j + 1]) {
+            lines.push(('-', old[i], i, j));
+            i += 1;
+        } else {
+            lines.push(('+', new[j], i, j));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// Output of `ort diff --format`
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum DiffFormat {
+    Text,
+    Json,
+}
+
+/// Print the differences between two files
+///
+/// Changes are grouped under the section they are in, removals in red and
+/// additions in green, or with `--format json` or `--json` printed as a JSON
+/// array.
+fn diff(old: &Path, new: &Path, key: Option<String>, json: bool) -> i32 {
+    // Any failure is 2, as 1 means the files differ
+    let (old, new) = match (load(old), load(new)) {
+        (Ok(old), Ok(new)) => (old, new),
+        _ => return EXIT_INVALID,
+    };
+    let rows = key.clone().map_or(RowMatch::Equality, RowMatch::Key);
+    let changes: Vec<Located> = old.diff_with(&new, &rows).into_iter().map(|change| locate(change, &old, &new, key.as_deref())).collect();
+    let code = if changes.is_empty() { 0 } else { 1 };
+
+    if !json {
+        print_changes(&changes);
+        return code;
+    }
+    match changes_json(&changes) {
+        Ok(text) => {
+            println!("{}", text);
+            code
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            EXIT_INVALID
+        }
+    }
+}
+
+/// A change with the section and table row it is in
+struct Located {
+    change: Change,
+    section: Option<String>,
+    // The row's key, as `id=2`, or its index
+    row: Option<String>,
+    key: Option<OrtValue>,
+    // The path in the row, or in the section outside tables
+    path: String,
+}
+
+fn locate(change: Change, old: &OrtValue, new: &OrtValue, key: Option<&str>) -> Located {
+    // Removed values have paths into the old value, others into the new one
+    let root = if matches!(change, Change::Removed { .. }) { old } else { new };
+    let (section, table, path) = match (root, change.path().split_once('.')) {
+        (OrtValue::Array(_), _) => (None, Some(root), change.path()),
+        (_, Some((section, path))) => (Some(section), root.get(section), path),
+        (_, None) => (None, None, change.path()),
+    };
+    let (index, rest) = path.split_once('.').unwrap_or((path, ""));
+    let row = match (table, index.parse::<usize>()) {
+        (Some(OrtValue::Array(rows)), Ok(i)) => rows.get(i).map(|row| (i, row)),
+        _ => None,
+    };
+    let key_value = row.zip(key).and_then(|((_, row), key)| row.get(key)).cloned();
+
+    Located {
+        section: section.map(str::to_string),
+        row: row.map(|(i, _)| match (&key_value, key) {
+            (Some(value), Some(key)) => format!("{}={}", key, value.coerce_to_string()),
+            _ => i.to_string(),
+        }),
+        key: key_value,
+        path: if row.is_some() { rest } else { path }.to_string(),
+        change,
+    }
+}
+
+fn print_changes(changes: &[Located]) {
+    let mut current = None;
+    for located in changes {
+        // Changes inside a section are listed under its key
+        let section = located.section.as_deref();
+        if section.is_some() && section != current {
+            println!("{}", section.unwrap_or_default());
+        }
+        current = section;
+
+        let indent = if section.is_some() { "  " } else { "" };
+        let at = match (&located.row, located.path.as_str()) {
+            (Some(row), "") => format!("[{}]", row),
+            (Some(row), path) => format!("[{}] {}", row, path),
+            (None, path) => path.to_string(),
+        };
+        let line = match &located.change {
+            Change::Added { value, .. } => paint(&format!("+ {}: {}", at, value.coerce_to_string()), Color::Green),
+            Change::Removed { value, .. } => paint(&format!("- {}: {}", at, value.coerce_to_string()), Color::Red),
+            Change::Changed { old, new, .. } => {
+                paint(&format!("~ {}: {} -> {}", at, old.coerce_to_string(), new.coerce_to_string()), Color::Yellow)
+            }
+        };
+        println!("{}{}", indent, line);
+    }
+}
+
+/// Write changes as a JSON array of `{"change", "path", ...}` records, with
+/// the row's `key` for rows matched by one
+fn changes_json(changes: &[Located]) -> Result<String, String> {
+    let records: Vec<OrtValue> = changes
+        .iter()
+        .map(|located| {
+            let change = &located.change;
+            let mut record = ort_rs::Map::new();
+            record.insert("path".to_string(), OrtValue::from(change.path()));
+            if let Some(key) = &located.key {
+                record.insert("key".to_string(), key.clone());
+            }
+            let kind = match change {
+                Change::Added { value, .. } => {
+                    record.insert("value".to_string(), value.clone());
+                    "added"
+                }
+                Change::Removed { value, .. } => {
+                    record.insert("value".to_string(), value.clone());
+                    "removed"
+                }
+                Change::Changed { old, new, .. } => {
+                    record.insert("old".to_string(), old.clone());
+                    record.insert("new".to_string(), new.clone());
+                    "changed"
+                }
+            };
+            record.insert("change".to_string(), OrtValue::from(kind));
+            OrtValue::Object(record)
+        })
+        .collect();
+
+    #[cfg(feature = "serde_json")]
+    return OrtValue::Array(records)
+        .to_json_with(&ort_rs::NonFinite::Null)
+        .map_err(|e| e.message)
+        .and_then(|json| serde_json::to_string_pretty(&json).map_err(|e| e.to_string()));
+    #[cfg(not(feature = "serde_json"))]
+    {
+        let _ = records;
+        Err("--json needs the serde_json feature".to_string())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Color {
+    Green,
+    Red,
+    Yellow,
+}
+
+#[cfg(feature = "color")]
+fn paint(text: &str, color: Color) -> String {
+    use colored::Colorize;
+    match color {
+        Color::Green => text.green(),
+        Color::Red => text.red(),
+        Color::Yellow => text.yellow(),
+    }
+    .to_string()
+}
+
+#[cfg(not(feature = "color"))]
+fn paint(text: &str, _color: Color) -> String {
+    text.to_string()
+}
+
+/// Read and parse a file, printing why if that fails and giving the exit code
+fn load(input: &Path) -> Result<OrtValue, i32> {
+    let content = match common::read_input(input) {
+        Ok(content) => content,
+        Err(e) => {
+            eprintln!("Failed to read file '{}': {}", input.display(), e);
+            return Err(EXIT_IO);
+        }
+    };
+    ort_rs::parse_ort_with(&content, &parse_options(input)).map_err(|e| {
+        eprintln!("{}", report(input, &content, e));
+        EXIT_INVALID
+    })
+}
+
+/// Options for parsing a file, resolving its includes next to it
+fn parse_options(input: &Path) -> ParseOptions {
+    let options = ParseOptions::new().allow_includes(true);
+    if common::is_stdin(input) {
+        return options;
+    }
+    options.base_dir(input.parent().unwrap_or(Path::new("")))
+}
+
+fn print_value(value: &OrtValue, json: bool) -> Result<String, String> {
+    if !json {
+        return Ok(value.coerce_to_string());
+    }
+    #[cfg(feature = "serde_json")]
+    return value
+        .to_json_with(&ort_rs::NonFinite::Null)
+        .map(|json| json.to_string())
+        .map_err(|e| e.message);
+    #[cfg(not(feature = "serde_json"))]
+    Err("--json needs the serde_json feature".to_string())
+}
+
+/// Parse a file's content, returning its errors, or with `--strict` its
+/// warnings and empty sections and with `--schema` where it doesn't match
+fn check(path: &Path, content: &str, args: &ValidateArgs, schema: Option<&Schema>) -> Vec<String> {
+    let mut options = parse_options(path);
+    if args.strict {
+        options = options.duplicate_keys(ConflictPolicy::Error);
+    }
+    if let Some(max_depth) = args.max_depth {
+        options = options.max_depth(max_depth);
+    }
+    if let Some(max_rows) = args.max_rows {
+        options = options.max_rows(max_rows);
+    }
+
+    let (value, errors) = options.parse_all(content);
+    if !errors.is_empty() {
+        return errors.into_iter().map(|e| report(path, content, e)).collect();
+    }
+
+    let mut problems = vec![];
+    if args.strict {
+        if let Ok((_, warnings)) = options.parse_with_warnings(content) {
+            problems.extend(warnings.iter().map(|warning| format!("{}: warning: {}", path.display(), warning)));
+        }
+
+        let mut sections = EmptySections::default();
+        if SaxParser::new().parse(content, &mut sections).is_ok() {
+            for key in sections.empty {
+                problems.push(format!("{}: section '{}' has no rows", path.display(), key));
+            }
+        }
+    }
+    if let Some(schema) = schema {
+        problems.extend(ort_rs::validate_against_schema(&value, schema).iter().map(|e| format!("{}: {}", path.display(), e)));
+    }
+    problems
+}
+
+fn report(path: &Path, content: &str, error: OrtError) -> String {
+    let error = match error.source_file {
+        Some(_) => error,
+        None => error.with_source_file(path),
+    };
+    #[cfg(feature = "miette")]
+    return format!("{}: {:?}", path.display(), miette::Report::new(ort_rs::OrtDiagnostic::new(content.to_string(), error)));
+    #[cfg(not(feature = "miette"))]
+    {
+        let _ = content;
+        error.to_string()
+    }
+}
+
+/// Collects the keys of sections without data lines
+#[derive(Default)]
+struct EmptySections {
+    rows: usize,
+    empty: Vec<String>,
+}
+
+impl SaxHandler<'_> for EmptySections {
+    fn on_section_start(&mut self, _key: Option<&str>, _fields: &[FieldSpec]) {
+        self.rows = 0;
+    }
+
+    fn on_row(&mut self, _row: ort_rs::OrtValueRef<'_>) {
+        self.rows += 1;
+    }
+
+    fn on_section_end(&mut self, key: Option<&str>) {
+        if self.rows == 0 {
+            self.empty.push(key.unwrap_or("").to_string());
+        }
+    }
+}
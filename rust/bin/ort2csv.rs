@@ -0,0 +1,20 @@
+// Converts ORT to CSV, as `ort to-csv` does
+
+use clap::Parser;
+use std::process;
+
+#[allow(dead_code)]
+mod common;
+mod to_csv;
+
+#[derive(Parser)]
+#[command(name = "ort2csv", version, about = "Convert a section of an ORT file to CSV")]
+struct Cli {
+    #[command(flatten)]
+    args: to_csv::ToCsvArgs,
+}
+
+fn main() {
+    let cli: Cli = common::parse_args();
+    process::exit(to_csv::run(cli.args));
+}
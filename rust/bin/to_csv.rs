@@ -0,0 +1,118 @@
+// `ort to-csv`, also run by `ort2csv`
+
+use crate::common::{self, Failure, NestedArg, EXIT_USAGE};
+use clap::Args;
+use ort_rs::{CsvOptions, OrtValue, ParseOptions};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct ToCsvArgs {
+    /// ORT file to convert, or `-` for standard input, which is also read
+    /// when no file is given
+    #[arg(value_name = "FILE", default_value = "-")]
+    input: PathBuf,
+    /// Output file, or `-` for standard output; with `--all`, the directory to
+    /// write <section>.csv into
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Section to convert, needed when the file has several
+    #[arg(short, long, value_name = "NAME", conflicts_with = "all")]
+    section: Option<String>,
+    /// Convert every section into its own <section>.csv
+    #[arg(long)]
+    all: bool,
+    /// Leave out the header row
+    #[arg(long)]
+    no_header: bool,
+    /// What nested objects and arrays become
+    #[arg(long, value_enum, default_value_t = NestedArg::Error)]
+    nested: NestedArg,
+    /// CSV cell delimiter, such as ';' or 'tab'
+    #[arg(long, value_name = "CHAR", default_value = ",", value_parser = common::csv_delimiter)]
+    csv_delimiter: u8,
+    /// ORT value delimiter, such as '|' or 'tab'
+    #[arg(long, value_name = "CHAR", value_parser = common::delimiter)]
+    delimiter: Option<char>,
+}
+
+/// Convert the input, giving the exit code
+pub fn run(args: ToCsvArgs) -> i32 {
+    let input = args.input.as_path();
+    let mut options = ParseOptions::default();
+    if let Some(delimiter) = args.delimiter {
+        options.delimiter = delimiter;
+    }
+    let csv_options = CsvOptions {
+        delimiter: args.csv_delimiter,
+        has_headers: !args.no_header,
+        nested: args.nested.into(),
+        ..CsvOptions::default()
+    };
+
+    let value = match read(input, &options) {
+        Ok(value) => value,
+        Err(failure) => {
+            eprintln!("{}", failure.message);
+            return failure.code;
+        }
+    };
+
+    if !args.all {
+        let table = match common::section_table(&value, args.section.as_deref()) {
+            Ok(table) => table,
+            Err(message) => {
+                let all = if args.section.is_none() { " or convert them all with --all" } else { "" };
+                eprintln!("{}: {}{}", input.display(), message, all);
+                return EXIT_USAGE;
+            }
+        };
+        let output = common::output_target(input, args.output.as_deref(), false, "csv");
+        let mut batch = common::Batch::default();
+        batch.record(convert(input, table, output.as_deref(), false, &csv_options));
+        return batch.finish(true);
+    }
+
+    // One file per section, next to the input unless a directory is given
+    let dir = match args.output {
+        Some(dir) if common::is_stdin(&dir) => {
+            eprintln!("--all writes one file per section, so it needs an output directory");
+            return EXIT_USAGE;
+        }
+        Some(dir) => dir,
+        None if common::is_stdin(input) => PathBuf::from("."),
+        None => input.parent().unwrap_or(Path::new("")).to_path_buf(),
+    };
+    let sections: Vec<(String, &OrtValue)> = match &value {
+        OrtValue::Object(obj) => {
+            let mut sections: Vec<_> = obj.iter().map(|(key, table)| (key.clone(), table)).collect();
+            sections.sort_by(|a, b| a.0.cmp(&b.0));
+            sections
+        }
+        table => vec![(common::file_stem(input), table)],
+    };
+
+    let mut batch = common::Batch::default();
+    for (key, table) in sections {
+        let output = dir.join(format!("{}.csv", key));
+        batch.record(convert(input, table, Some(&output), true, &csv_options));
+    }
+    batch.finish(true)
+}
+
+/// Read and parse the input
+fn read(input: &Path, options: &ParseOptions) -> Result<OrtValue, Failure> {
+    let content = common::read_input(input).map_err(|e| Failure::io(format!("Failed to read file '{}': {}", input.display(), e)))?;
+    options.parse_with_warnings(&content).map(|(value, _)| value).map_err(|e| {
+        #[cfg(feature = "miette")]
+        return Failure::invalid(format!("{}: {:?}", input.display(), miette::Report::new(ort_rs::OrtDiagnostic::new(content, e))));
+        #[cfg(not(feature = "miette"))]
+        Failure::invalid(if common::is_stdin(input) { e } else { e.with_source_file(input) }.to_string())
+    })
+}
+
+/// Convert one table, giving the reason if it fails
+fn convert(input: &Path, table: &OrtValue, output: Option<&Path>, output_dir: bool, options: &CsvOptions) -> Result<(), Failure> {
+    let csv_string = ort_rs::to_csv_string_with(table, options)
+        .map_err(|e| Failure::invalid(format!("{}: {}", input.display(), e.message)))?;
+    common::write_converted(output, &csv_string, output_dir)
+}
@@ -0,0 +1,240 @@
+// `ort to-json`, also run by `ort2json`
+
+use crate::common::{self, Failure, Input};
+use clap::{Args, ValueEnum};
+use ort_rs::{Diagnostic, NonFinite, OrtError, OrtErrorKind, OrtValue, ParseOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Args)]
+pub struct ToJsonArgs {
+    /// ORT files to convert, or `-` for standard input, which is also read
+    /// when no file is given
+    #[arg(value_name = "FILE")]
+    inputs: Vec<String>,
+    /// Output file, directory to write <name>.json into, or `-` for standard output
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Convert the .ort files in directories and their subdirectories,
+    /// mirroring them under the output directory
+    #[arg(short, long)]
+    recursive: bool,
+    /// Number of files to convert at once
+    #[arg(short, long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: u16,
+    /// Stop at the first file that fails
+    #[arg(long)]
+    fail_fast: bool,
+    /// Write JSON without whitespace
+    #[arg(long, conflicts_with = "indent")]
+    compact: bool,
+    /// Indent pretty JSON by this many spaces
+    #[arg(long, value_name = "N", default_value_t = 2)]
+    indent: usize,
+    /// Write a table as JSON Lines, one row per line, to <name>.jsonl
+    #[arg(long, conflicts_with_all = ["compact", "indent"])]
+    ndjson: bool,
+    /// Section to write with --ndjson, needed when the file has several
+    #[arg(short, long, value_name = "NAME", requires = "ndjson")]
+    section: Option<String>,
+    /// Print errors as text or as JSON records
+    #[arg(long, value_enum, default_value_t = ErrorFormat::Human)]
+    error_format: ErrorFormat,
+    /// Value delimiter, such as '|' or 'tab'
+    #[arg(long, value_name = "CHAR", value_parser = common::delimiter)]
+    delimiter: Option<char>,
+    /// What NaN and infinities become
+    #[arg(long, value_enum, default_value_t = NonFiniteArg::Error)]
+    non_finite: NonFiniteArg,
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum NonFiniteArg {
+    Error,
+    Null,
+    String,
+}
+
+/// Options shared by every input
+struct Settings {
+    output: Option<PathBuf>,
+    // Whether `output` is a directory, as it is for several inputs or
+    // `--recursive`
+    output_dir: bool,
+    json_errors: bool,
+    options: ParseOptions,
+    non_finite: NonFinite,
+    // Spaces per indentation level, or `None` for compact output
+    indent: Option<usize>,
+    // `Some` for JSON Lines, with the section to write
+    ndjson: Option<Option<String>>,
+}
+
+/// Convert the inputs, giving the exit code
+pub fn run(args: ToJsonArgs) -> i32 {
+    let inputs = common::collect_inputs(&args.inputs, args.recursive, "ort");
+    let mut options = ParseOptions::default();
+    if let Some(delimiter) = args.delimiter {
+        options.delimiter = delimiter;
+    }
+    let settings = Settings {
+        output: args.output,
+        output_dir: inputs.len() > 1 || args.recursive,
+        json_errors: args.error_format == ErrorFormat::Json,
+        options,
+        non_finite: match args.non_finite {
+            NonFiniteArg::Error => NonFinite::Error,
+            NonFiniteArg::Null => NonFinite::Null,
+            NonFiniteArg::String => NonFinite::String,
+        },
+        indent: (!args.compact).then_some(args.indent),
+        ndjson: args.ndjson.then_some(args.section),
+    };
+
+    if let Err(failure) = common::check_outputs(&inputs, |input| output_target(input, &settings)) {
+        eprintln!("{}", failure.message);
+        return failure.code;
+    }
+
+    // Diagnostics printed as JSON with --error-format json
+    let diagnostics = Mutex::new(vec![]);
+    let batch = common::convert_all(&inputs, args.jobs.into(), args.fail_fast, |input| {
+        convert(input, &settings, &diagnostics)
+    });
+    let diagnostics = diagnostics.into_inner().unwrap();
+
+    // Keep standard output for the JSON when it is written there
+    let to_stdout = inputs.iter().any(|input| output_target(input, &settings).is_none());
+    if settings.json_errors && to_stdout {
+        eprintln!("{}", diagnostics_json(diagnostics));
+    } else if settings.json_errors {
+        println!("{}", diagnostics_json(diagnostics));
+    }
+    batch.finish(!settings.json_errors)
+}
+
+fn output_target(input: &Input, settings: &Settings) -> Option<PathBuf> {
+    let extension = if settings.ndjson.is_some() { "jsonl" } else { "json" };
+    common::output_target(&input.path, input.output(settings.output.as_deref()).as_deref(), settings.output_dir, extension)
+}
+
+/// Convert one input, giving the reason if it fails
+///
+/// Warnings are printed right away. With `--error-format json`, errors and
+/// warnings are collected in `diagnostics` instead.
+fn convert(input: &Input, settings: &Settings, diagnostics: &Mutex<Vec<Diagnostic>>) -> Result<(), Failure> {
+    let mut found = vec![];
+    let result = json_string(&input.path, settings, &mut found)
+        .and_then(|json_string| common::write_converted(output_target(input, settings).as_deref(), &json_string, settings.output_dir));
+    diagnostics.lock().unwrap().extend(found);
+    result
+}
+
+/// Read and convert an input to JSON text
+fn json_string(input: &Path, settings: &Settings, diagnostics: &mut Vec<Diagnostic>) -> Result<String, Failure> {
+    let json_errors = settings.json_errors;
+
+    // Read input file
+    let content = match common::read_input(input) {
+        Ok(c) => c,
+        Err(e) => {
+            let message = format!("Failed to read file '{}': {}", input.display(), e);
+            if json_errors {
+                let error = OrtError::new(0, String::new(), message.clone()).with_kind(OrtErrorKind::Io);
+                diagnostics.push(error.to_diagnostic());
+            }
+            return Err(Failure::io(message));
+        }
+    };
+
+    // Parse ORT to OrtValue
+    let ort_value = match settings.options.parse_with_warnings(&content) {
+        Ok((v, warnings)) => {
+            for warning in warnings {
+                if json_errors {
+                    diagnostics.push(warning.to_diagnostic());
+                } else {
+                    eprintln!("{}: warning: {}", input.display(), warning);
+                }
+            }
+            v
+        }
+        Err(e) => {
+            if json_errors {
+                diagnostics.push(e.to_diagnostic());
+            }
+            #[cfg(feature = "miette")]
+            return Err(Failure::invalid(format!("{}: {:?}", input.display(), miette::Report::new(ort_rs::OrtDiagnostic::new(content, e)))));
+            #[cfg(not(feature = "miette"))]
+            return Err(Failure::invalid(if common::is_stdin(input) { e } else { e.with_source_file(input) }.to_string()));
+        }
+    };
+
+    if let Some(section) = &settings.ndjson {
+        return ndjson_string(input, &ort_value, section.as_deref(), settings, diagnostics);
+    }
+
+    // Convert OrtValue to JSON Value
+    let json_value = match ort_value.to_json_with(&settings.non_finite) {
+        Ok(v) => v,
+        Err(e) => {
+            if json_errors {
+                diagnostics.push(e.to_diagnostic());
+            }
+            return Err(Failure::invalid(format!("{}: {} (use --non-finite null or string to convert it)", input.display(), e.message)));
+        }
+    };
+
+    // Convert to JSON string
+    json_text(&json_value, settings.indent).map_err(|e| Failure::invalid(format!("Failed to serialize JSON: {}", e)))
+}
+
+/// Convert a section to JSON Lines text
+fn ndjson_string(input: &Path, value: &OrtValue, section: Option<&str>, settings: &Settings, diagnostics: &mut Vec<Diagnostic>) -> Result<String, Failure> {
+    let table = common::section_table(value, section).map_err(|message| Failure {
+        code: common::EXIT_USAGE,
+        message: format!("{}: {}", input.display(), message),
+    })?;
+
+    let mut lines = vec![];
+    if let Err(e) = ort_rs::ort_to_ndjson(table, &mut lines, &settings.non_finite) {
+        if settings.json_errors {
+            diagnostics.push(e.to_diagnostic());
+        }
+        return Err(Failure::invalid(format!("{}: {} (use --non-finite null or string to convert it)", input.display(), e.message)));
+    }
+    Ok(String::from_utf8_lossy(&lines).into_owned())
+}
+
+/// Write JSON indented by `indent` spaces per level, or on one line for `None`
+fn json_text(value: &serde_json::Value, indent: Option<usize>) -> serde_json::Result<String> {
+    let Some(indent) = indent else {
+        return serde_json::to_string(value);
+    };
+
+    let pretty = serde_json::to_string_pretty(value)?;
+    if indent == 2 {
+        return Ok(pretty);
+    }
+
+    // Strings hold no raw line breaks, so the leading spaces of each line are
+    // all indentation, two per level
+    let lines: Vec<String> = pretty
+        .lines()
+        .map(|line| {
+            let body = line.trim_start_matches(' ');
+            format!("{}{}", " ".repeat((line.len() - body.len()) / 2 * indent), body)
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+fn diagnostics_json(diagnostics: Vec<Diagnostic>) -> serde_json::Value {
+    serde_json::Value::Array(diagnostics.into_iter().map(Into::into).collect())
+}
@@ -0,0 +1,227 @@
+use crate::error::{OrtError, OrtResult};
+use crate::ort_value::OrtValue;
+use crate::parser::infer_scalar;
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+
+/// How CSV conversion treats nested objects and arrays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CsvNested {
+    /// Reject nested values when writing, and read column names as written
+    #[default]
+    Error,
+    /// Flatten nested values into dot-separated columns such as `address.city`
+    /// and `tags.0` when writing, and rebuild them from those columns when
+    /// reading, as `to_flat_map` and `from_flat_map` do
+    Dotted,
+}
+
+/// Options for reading and writing CSV
+#[derive(Debug, Clone, PartialEq)]
+pub struct CsvOptions {
+    /// Byte between cells, `,` by default
+    pub delimiter: u8,
+    /// Whether the first row holds the column names; without it, columns are
+    /// read as `column1`, `column2` and so on, and no header row is written
+    pub has_headers: bool,
+    pub nested: CsvNested,
+    /// Whether numbers, booleans and empty cells are read as such, or every
+    /// cell is read as a string
+    pub infer_types: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: b',',
+            has_headers: true,
+            nested: CsvNested::Error,
+            infer_types: true,
+        }
+    }
+}
+
+/// Parse CSV string into an array of objects
+///
+/// The first row holds the field names. Cells are typed the same way the
+/// ORT parser types them: empty cells become null, and numbers and booleans
+/// are inferred.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_csv_str("id,name\n1,John\n2,\"Doe, Jane\"")?;
+/// assert_eq!(value[1]["name"].as_str(), Some("Doe, Jane"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_csv_str(s: &str) -> OrtResult<OrtValue> {
+    from_csv_str_with(s, &CsvOptions::default())
+}
+
+/// Parse CSV string into an array of objects with the given options
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{CsvNested, CsvOptions};
+///
+/// let options = CsvOptions { delimiter: b';', nested: CsvNested::Dotted, ..CsvOptions::default() };
+/// let value = ort_rs::from_csv_str_with("id;address.city;tags.0\n1;Paris;admin", &options)?;
+/// assert_eq!(value[0]["address"]["city"].as_str(), Some("Paris"));
+/// assert_eq!(value[0]["tags"][0].as_str(), Some("admin"));
+///
+/// let options = CsvOptions { has_headers: false, infer_types: false, ..CsvOptions::default() };
+/// let value = ort_rs::from_csv_str_with("007,true", &options)?;
+/// assert_eq!(value[0]["column1"].as_str(), Some("007"));
+/// assert_eq!(value[0]["column2"].as_str(), Some("true"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_csv_str_with(s: &str, options: &CsvOptions) -> OrtResult<OrtValue> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(options.has_headers)
+        .delimiter(options.delimiter)
+        .flexible(!options.has_headers)
+        .from_reader(s.as_bytes());
+
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .enumerate()
+        .map(|(i, h)| if options.has_headers { h.trim().to_string() } else { format!("column{}", i + 1) })
+        .collect();
+
+    let mut result = vec![];
+    for record in reader.records() {
+        let record = record.map_err(csv_error)?;
+        let cells = headers.iter().zip(record.iter()).map(|(field, cell)| (field.clone(), parse_cell(cell, options)));
+
+        result.push(match options.nested {
+            CsvNested::Error => OrtValue::Object(cells.collect()),
+            CsvNested::Dotted => OrtValue::from_flat_map(cells.collect())?,
+        });
+    }
+
+    Ok(OrtValue::Array(result))
+}
+
+/// Convert a uniform array of objects into CSV string
+///
+/// Columns are emitted in alphabetical order, matching the generator's key
+/// order. Nested arrays and objects cannot be represented and are rejected.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_str(":id,name:\n1,John\n2,Jane")?;
+/// assert_eq!(ort_rs::to_csv_string(&value)?, "id,name\n1,John\n2,Jane\n");
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_csv_string(v: &OrtValue) -> OrtResult<String> {
+    to_csv_string_with(v, &CsvOptions::default())
+}
+
+/// Convert an array of objects into CSV string with the given options
+///
+/// With `CsvNested::Dotted`, rows may have different fields: the columns are
+/// all of them, and a row without one leaves its cell empty.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{CsvNested, CsvOptions};
+///
+/// let value = ort_rs::from_str(include_str!("../example/04_nested_objects.ort"))?;
+/// let options = CsvOptions { delimiter: b'\t', nested: CsvNested::Dotted, ..CsvOptions::default() };
+/// let csv = ort_rs::to_csv_string_with(&value["users"], &options)?;
+/// assert_eq!(csv.lines().next(), Some("id\tprofile.address.city\tprofile.address.country\tprofile.age\tprofile.name"));
+/// assert_eq!(ort_rs::from_csv_str_with(&csv, &options)?, value["users"]);
+///
+/// let error = ort_rs::to_csv_string(&value["users"]).unwrap_err();
+/// assert_eq!(error.message, "Field 'profile' contains a nested value which cannot be written as CSV");
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_csv_string_with(v: &OrtValue, options: &CsvOptions) -> OrtResult<String> {
+    let arr = match v {
+        OrtValue::Array(arr) => arr,
+        _ => return Err(error("Expected an array of objects".to_string())),
+    };
+
+    let mut rows = Vec::with_capacity(arr.len());
+    for (row, item) in arr.iter().enumerate() {
+        match item {
+            OrtValue::Object(_) if options.nested == CsvNested::Dotted => rows.push(Cow::Owned(item.to_flat_map().into_iter().collect())),
+            OrtValue::Object(obj) => rows.push(Cow::Borrowed(obj)),
+            _ => return Err(error(format!("Row {} is not an object", row + 1))),
+        }
+    }
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .flexible(true)
+        .from_writer(vec![]);
+
+    // Dotted rows share the columns of all of them, others need the same fields
+    let mut fields: Vec<&String> = match options.nested {
+        CsvNested::Dotted => rows.iter().flat_map(|obj| obj.keys()).collect::<BTreeSet<_>>().into_iter().collect(),
+        CsvNested::Error => rows.first().map(|obj| obj.keys().collect()).unwrap_or_default(),
+    };
+    fields.sort();
+    if options.has_headers && !rows.is_empty() {
+        writer.write_record(&fields).map_err(csv_error)?;
+    }
+
+    for (row, obj) in rows.iter().enumerate() {
+        if options.nested == CsvNested::Error && (obj.len() != fields.len() || !fields.iter().all(|key| obj.contains_key(*key))) {
+            return Err(error(format!("Row {} has different fields than the header", row + 1)));
+        }
+
+        let mut cells = Vec::with_capacity(fields.len());
+        for key in &fields {
+            cells.push(format_cell(key, obj.get(*key).unwrap_or(&OrtValue::Null))?);
+        }
+        writer.write_record(&cells).map_err(csv_error)?;
+    }
+
+    let bytes = writer
+        .into_inner()
+        .map_err(|e| error(format!("Failed to write CSV: {}", e)))?;
+    String::from_utf8(bytes).map_err(|e| error(format!("Failed to write CSV: {}", e)))
+}
+
+fn parse_cell(cell: &str, options: &CsvOptions) -> OrtValue {
+    if !options.infer_types {
+        return OrtValue::String(cell.to_string());
+    }
+    let trimmed = cell.trim();
+    if trimmed.is_empty() {
+        return OrtValue::Null;
+    }
+    infer_scalar(Cow::Borrowed(trimmed)).into()
+}
+
+fn format_cell(key: &str, value: &OrtValue) -> OrtResult<String> {
+    match value {
+        OrtValue::Null => Ok(String::new()),
+        OrtValue::Bool(b) => Ok(b.to_string()),
+        OrtValue::Number(n) => Ok(n.to_string()),
+        OrtValue::String(s) => Ok(s.clone()),
+        OrtValue::Array(_) | OrtValue::Object(_) => Err(error(format!(
+            "Field '{}' contains a nested value which cannot be written as CSV",
+            key
+        ))),
+    }
+}
+
+fn csv_error(e: csv::Error) -> OrtError {
+    let line = e.position().map(|p| p.line() as usize).unwrap_or(0);
+    OrtError::new(line, String::new(), format!("Invalid CSV: {}", e))
+}
+
+fn error(message: String) -> OrtError {
+    OrtError::new(0, String::new(), message)
+}
@@ -0,0 +1,195 @@
+use crate::ort_value::OrtValue;
+use std::collections::HashMap;
+use std::fmt;
+
+/// One difference between two values, found by `OrtValue::diff`
+///
+/// Paths are dot-separated object keys and array indexes, as for `get_path`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// The path exists only in the new value
+    Added { path: String, value: OrtValue },
+    /// The path exists only in the old value
+    Removed { path: String, value: OrtValue },
+    /// The path holds a different value
+    Changed { path: String, old: OrtValue, new: OrtValue },
+}
+
+impl Change {
+    /// Get the path of the changed value
+    pub fn path(&self) -> &str {
+        match self {
+            Change::Added { path, .. } | Change::Removed { path, .. } | Change::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, value } => write!(f, "+ {}: {}", path, value.coerce_to_string()),
+            Change::Removed { path, value } => write!(f, "- {}: {}", path, value.coerce_to_string()),
+            Change::Changed { path, old, new } => {
+                write!(f, "~ {}: {} -> {}", path, old.coerce_to_string(), new.coerce_to_string())
+            }
+        }
+    }
+}
+
+/// How `OrtValue::diff_with` pairs up the items of two arrays, such as the
+/// rows of a table
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum RowMatch {
+    /// By position, as `OrtValue::diff` does
+    #[default]
+    Index,
+    /// Equal items match wherever they are, and the others are removed and
+    /// added as a whole
+    Equality,
+    /// Objects with the same value at this key match, and are compared field
+    /// by field; arrays whose items aren't all objects with the key are
+    /// compared by position
+    Key(String),
+}
+
+impl OrtValue {
+    /// List the differences from this value to `other`
+    ///
+    /// Objects are compared key by key, in sorted key order, and arrays
+    /// element by element, so a change deep inside is reported at its own
+    /// path. Values of different types are reported as changed as a whole.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::{Change, OrtValue};
+    ///
+    /// let old = ort_rs::from_str("users:id,name:\n1,John\n2,Jane\n\nport:\n80")?;
+    /// let new = ort_rs::from_str("users:name,id:\nJohn,1\nJanet,2\nBob,3\n\nhost:\nlocalhost")?;
+    ///
+    /// let changes = old.diff(&new);
+    /// assert_eq!(changes.len(), 4);
+    /// assert_eq!(changes[0], Change::Added { path: "host".to_string(), value: OrtValue::from("localhost") });
+    /// assert_eq!(changes[1], Change::Removed { path: "port".to_string(), value: OrtValue::from(80) });
+    /// assert_eq!(changes[2].to_string(), "~ users.1.name: Jane -> Janet");
+    /// assert_eq!(changes[3].to_string(), "+ users.2: (id:3,name:Bob)");
+    /// assert!(old.diff(&old).is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff(&self, other: &OrtValue) -> Vec<Change> {
+        self.diff_with(other, &RowMatch::Index)
+    }
+
+    /// List the differences like `diff`, pairing up array items as `rows` says
+    ///
+    /// Removed items have their index in this value, and the others their
+    /// index in `other`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::RowMatch;
+    ///
+    /// let old = ort_rs::from_str("users:id,name:\n1,John\n2,Jane")?;
+    /// let new = ort_rs::from_str("users:id,name:\n2,Janet\n3,Bob\n1,John")?;
+    ///
+    /// let changes: Vec<String> = old.diff_with(&new, &RowMatch::Key("id".to_string())).iter().map(|c| c.to_string()).collect();
+    /// assert_eq!(changes, ["~ users.0.name: Jane -> Janet", "+ users.1: (id:3,name:Bob)"]);
+    ///
+    /// let changes: Vec<String> = old.diff_with(&new, &RowMatch::Equality).iter().map(|c| c.to_string()).collect();
+    /// assert_eq!(changes, ["- users.1: (id:2,name:Jane)", "+ users.0: (id:2,name:Janet)", "+ users.1: (id:3,name:Bob)"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn diff_with(&self, other: &OrtValue, rows: &RowMatch) -> Vec<Change> {
+        let mut changes = vec![];
+        diff_into(self, other, String::new(), rows, &mut changes);
+        changes
+    }
+}
+
+fn diff_into(old: &OrtValue, new: &OrtValue, path: String, rows: &RowMatch, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (OrtValue::Object(old), OrtValue::Object(new)) => {
+            let mut keys: Vec<&String> = old.keys().chain(new.keys().filter(|key| !old.contains_key(*key))).collect();
+            keys.sort();
+            for key in keys {
+                let path = child_path(&path, key);
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => diff_into(old, new, path, rows, changes),
+                    (Some(value), None) => changes.push(Change::Removed { path, value: value.clone() }),
+                    (None, Some(value)) => changes.push(Change::Added { path, value: value.clone() }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (OrtValue::Array(old), OrtValue::Array(new)) => match rows {
+            RowMatch::Key(key) if old.iter().chain(new).all(|item| item.get(key).is_some()) => {
+                let pairs = match_items(old, new, |item| item.get(key));
+                diff_matched(old, new, &pairs, &path, rows, changes);
+            }
+            RowMatch::Equality => {
+                let pairs = match_items(old, new, Some);
+                diff_matched(old, new, &pairs, &path, rows, changes);
+            }
+            _ => diff_by_index(old, new, &path, rows, changes),
+        },
+        _ if old != new => changes.push(Change::Changed { path, old: old.clone(), new: new.clone() }),
+        _ => {}
+    }
+}
+
+fn diff_by_index(old: &[OrtValue], new: &[OrtValue], path: &str, rows: &RowMatch, changes: &mut Vec<Change>) {
+    for i in 0..old.len().max(new.len()) {
+        let path = child_path(path, &i.to_string());
+        match (old.get(i), new.get(i)) {
+            (Some(old), Some(new)) => diff_into(old, new, path, rows, changes),
+            (Some(value), None) => changes.push(Change::Removed { path, value: value.clone() }),
+            (None, Some(value)) => changes.push(Change::Added { path, value: value.clone() }),
+            (None, None) => {}
+        }
+    }
+}
+
+/// Pair each old item with the first unpaired new item of the same identity,
+/// giving the new index for each old item
+fn match_items<'a, F>(old: &'a [OrtValue], new: &'a [OrtValue], identity: F) -> Vec<Option<usize>>
+where
+    F: Fn(&'a OrtValue) -> Option<&'a OrtValue>,
+{
+    let mut unpaired: HashMap<&OrtValue, Vec<usize>> = HashMap::new();
+    for (j, item) in new.iter().enumerate().rev() {
+        if let Some(id) = identity(item) {
+            unpaired.entry(id).or_default().push(j);
+        }
+    }
+    old.iter()
+        .map(|item| identity(item).and_then(|id| unpaired.get_mut(id)).and_then(Vec::pop))
+        .collect()
+}
+
+/// Compare paired items, then list the old items without a pair as removed
+/// and the new ones as added
+fn diff_matched(old: &[OrtValue], new: &[OrtValue], pairs: &[Option<usize>], path: &str, rows: &RowMatch, changes: &mut Vec<Change>) {
+    let mut paired = vec![false; new.len()];
+    for (i, pair) in pairs.iter().enumerate() {
+        match *pair {
+            Some(j) => {
+                paired[j] = true;
+                diff_into(&old[i], &new[j], child_path(path, &j.to_string()), rows, changes);
+            }
+            None => changes.push(Change::Removed { path: child_path(path, &i.to_string()), value: old[i].clone() }),
+        }
+    }
+    for (j, value) in new.iter().enumerate().filter(|(j, _)| !paired[*j]) {
+        changes.push(Change::Added { path: child_path(path, &j.to_string()), value: value.clone() });
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    match path {
+        "" => key.to_string(),
+        _ => format!("{}.{}", path, key),
+    }
+}
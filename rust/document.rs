@@ -0,0 +1,419 @@
+use crate::error::{OrtError, OrtErrorKind, OrtResult};
+use crate::escape::{escape, unescape};
+use crate::fields::{format_fields, FieldSpec};
+use crate::generator::{generate_field, generate_value, GenerateOptions};
+use crate::ort_value::OrtValue;
+use crate::parser::{
+    is_blank_or_comment, is_header, offset_in, parse_fields, parse_header, parse_ort_with, parse_row, parse_value_line,
+    split_values, strip_block_comments, to_field_specs, trim_row, unterminated_quote, Context, ParseOptions,
+};
+use std::fmt;
+
+/// ORT document that keeps its comments, blank lines and spelling
+///
+/// Every line is kept as written, so `to_string` gives back the parsed
+/// content byte for byte. Cells and rows can be replaced by section key and
+/// row index; only the edited line changes, and within it only the edited
+/// cell, so alignment padding and the other cells stay as they were.
+///
+/// The top-level `:fields:` section has the empty key. Rows count the data
+/// lines of a section, skipping blank lines and comments.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{OrtDocument, OrtValue};
+///
+/// let content = "\
+/// ## Release settings, edited by hand
+/// config:version,channel:
+/// 1.4.0 , stable
+///
+/// ## Everyone who can publish
+/// users:id,name:
+/// 1,  John
+/// 2,  Jane
+/// ";
+/// let mut doc = OrtDocument::parse(content)?;
+/// assert_eq!(doc.to_string(), content);
+/// assert_eq!(doc.cell("users", 1, "name"), Some("Jane"));
+///
+/// doc.set_cell("users", 1, "name", &OrtValue::from("Jane Doe, Jr."))?;
+/// let edited = doc.to_string();
+/// let changed: Vec<_> = content.lines().zip(edited.lines()).filter(|(a, b)| a != b).collect();
+/// assert_eq!(changed, [("2,  Jane", "2,  Jane Doe\\, Jr.")]);
+///
+/// assert_eq!(doc.to_value()?["users"][1]["name"].as_str(), Some("Jane Doe, Jr."));
+/// assert!(doc.set_cell("users", 2, "name", &OrtValue::from("Bob")).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OrtDocument {
+    lines: Vec<Line>,
+    sections: Vec<Section>,
+    options: ParseOptions,
+}
+
+/// A line as written, without its line break
+#[derive(Debug, Clone)]
+struct Line {
+    text: String,
+    ending: &'static str,
+}
+
+#[derive(Debug, Clone)]
+struct Section {
+    key: String,
+    fields: Vec<FieldSpec>,
+    /// Index of the header in `lines`
+    header: usize,
+    /// Indexes of the data lines in `lines`
+    rows: Vec<usize>,
+}
+
+impl OrtDocument {
+    /// Parse a document with the default options
+    pub fn parse(content: &str) -> OrtResult<Self> {
+        Self::parse_with(content, &ParseOptions::default())
+    }
+
+    /// Parse a document, failing if `content` isn't valid with `options`
+    pub fn parse_with(content: &str, options: &ParseOptions) -> OrtResult<Self> {
+        parse_ort_with(content, options)?;
+
+        let lines: Vec<Line> = content
+            .split_inclusive('\n')
+            .map(|raw| {
+                let (text, ending) = match raw.strip_suffix("\r\n") {
+                    Some(text) => (text, "\r\n"),
+                    None => match raw.strip_suffix('\n') {
+                        Some(text) => (text, "\n"),
+                        None => (raw, ""),
+                    },
+                };
+                Line { text: text.to_string(), ending }
+            })
+            .collect();
+
+        let mut texts: Vec<&str> = lines.iter().map(|line| line.text.as_str()).collect();
+        if options.multi_line_comment {
+            strip_block_comments(&mut texts)?;
+        }
+
+        let mut sections: Vec<Section> = vec![];
+        for (i, text) in texts.iter().enumerate() {
+            let line = trim_row(text, options.delimiter);
+            if is_blank_or_comment(line, options) {
+                continue;
+            }
+            if is_header(line) {
+                let (key, fields_str) = parse_header(line, i + 1)?;
+                let fields = parse_fields(fields_str, line, i + 1, options)?;
+                sections.push(Section {
+                    key: key.map(|key| unescape(key, options.delimiter).into_owned()).unwrap_or_default(),
+                    fields: to_field_specs(&fields, options.delimiter),
+                    header: i,
+                    rows: vec![],
+                });
+            } else if let Some(section) = sections.last_mut() {
+                section.rows.push(i);
+            }
+        }
+
+        Ok(Self {
+            lines,
+            sections,
+            options: options.clone(),
+        })
+    }
+
+    /// Keys of the sections in document order
+    pub fn section_keys(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().map(|section| section.key.as_str())
+    }
+
+    /// Fields of the first section with the key
+    pub fn fields(&self, key: &str) -> Option<&[FieldSpec]> {
+        self.section(key).ok().map(|section| section.fields.as_slice())
+    }
+
+    /// Number of data lines in the first section with the key
+    pub fn row_count(&self, key: &str) -> Option<usize> {
+        self.section(key).ok().map(|section| section.rows.len())
+    }
+
+    /// Get a data line as written, without its indentation
+    pub fn row(&self, key: &str, index: usize) -> Option<&str> {
+        let line = *self.section(key).ok()?.rows.get(index)?;
+        Some(trim_row(&self.lines[line].text, self.options.delimiter))
+    }
+
+    /// Get a cell as written, still escaped or quoted
+    pub fn cell(&self, key: &str, index: usize, field: &str) -> Option<&str> {
+        let (line, column) = self.locate(key, index, field).ok()?;
+        let cells = self.cells(line).ok()?;
+        cells.get(column).map(|cell| cell.trim())
+    }
+
+    /// Replace a cell, writing `value` the way the generator would
+    ///
+    /// Fails without changing the document if the section, row or field
+    /// doesn't exist, or if the edited row wouldn't parse, such as a value
+    /// that doesn't fit a typed field.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::{OrtDocument, OrtValue};
+    ///
+    /// let fixture = include_str!("../example/07_multiple_objects.ort");
+    /// let mut doc = OrtDocument::parse(fixture)?;
+    /// assert_eq!(doc.to_string(), fixture);
+    /// assert_eq!(doc.cell("products", 1, "price"), Some("29.99"));
+    ///
+    /// doc.set_cell("products", 1, "price", &OrtValue::from(24.5))?;
+    /// let edited = doc.to_string();
+    /// let changed: Vec<_> = fixture.lines().zip(edited.lines()).filter(|(a, b)| a != b).collect();
+    /// assert_eq!(changed, [("102,Mouse,29.99", "102,Mouse,24.5")]);
+    /// assert_eq!(edited.lines().count(), fixture.lines().count());
+    ///
+    /// let error = doc.set_cell("products", 0, "stock", &OrtValue::from(3)).unwrap_err();
+    /// assert_eq!(error.message, "Section 'products' has no field 'stock'");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_cell(&mut self, key: &str, index: usize, field: &str, value: &OrtValue) -> OrtResult<()> {
+        let (line, column) = self.locate(key, index, field)?;
+        let section = self.section(key)?;
+        let text = &self.lines[line].text;
+        let cells = self.cells(line)?;
+        let Some(cell) = cells.get(column) else {
+            return Err(OrtError::new(line + 1, text.clone(), format!("Row {} of section '{}' has no value for field '{}'", index, key, field))
+                .with_kind(OrtErrorKind::ValueCountMismatch));
+        };
+
+        let cell = cell.trim();
+        let start = offset_in(text, cell);
+        let generated = generate_field(&section.fields[column], value, &self.generate_options());
+        let edited = format!("{}{}{}", &text[..start], generated, &text[start + cell.len()..]);
+        self.replace(line, edited)
+    }
+
+    /// Replace a whole data line, keeping its indentation
+    ///
+    /// Rows of sections with fields must be objects with exactly those fields,
+    /// as for `append_rows`; a section without fields takes any value.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::{Map, OrtDocument, OrtValue};
+    ///
+    /// let mut doc = OrtDocument::parse("users:id int,name:\r\n  1,John\r\n  2,Jane\r\n\r\ncount:\r\n2")?;
+    /// let row = |id: OrtValue| OrtValue::Object(Map::from([
+    ///     ("id".to_string(), id),
+    ///     ("name".to_string(), OrtValue::from("Bob (admin)")),
+    /// ]));
+    /// doc.set_row("users", 0, &row(OrtValue::from(7)))?;
+    /// doc.set_row("count", 0, &OrtValue::from(3))?;
+    /// assert_eq!(doc.to_string(), "users:id int,name:\r\n  7,Bob \\(admin\\)\r\n  2,Jane\r\n\r\ncount:\r\n3");
+    ///
+    /// // A value that doesn't fit a typed field leaves the document unchanged
+    /// assert!(doc.set_row("users", 1, &row(OrtValue::from("seven"))).is_err());
+    /// assert_eq!(doc.row("users", 1), Some("2,Jane"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_row(&mut self, key: &str, index: usize, row: &OrtValue) -> OrtResult<()> {
+        let section = self.section(key)?;
+        let line = *section.rows.get(index).ok_or_else(|| missing_row(key, index))?;
+        let options = self.generate_options();
+
+        let generated = if section.fields.is_empty() {
+            generate_value(row, &options)
+        } else {
+            let obj = row
+                .as_object()
+                .filter(|obj| obj.len() == section.fields.len() && section.fields.iter().all(|f| obj.contains_key(f.name())));
+            let Some(obj) = obj else {
+                let names: Vec<&str> = section.fields.iter().map(FieldSpec::name).collect();
+                return Err(OrtError::new(0, String::new(), format!("Row must have the fields {} of section '{}'", names.join(","), key))
+                    .with_kind(OrtErrorKind::ValueCountMismatch));
+            };
+            section
+                .fields
+                .iter()
+                .map(|field| generate_field(field, &obj[field.name()], &options))
+                .collect::<Vec<_>>()
+                .join(&self.options.delimiter.to_string())
+        };
+
+        let text = &self.lines[line].text;
+        let trimmed = trim_row(text, self.options.delimiter);
+        let start = offset_in(text, trimmed);
+        let edited = format!("{}{}{}", &text[..start], generated, &text[start + trimmed.len()..]);
+        self.replace(line, edited)
+    }
+
+    /// Write the document in canonical form, keeping its comments
+    ///
+    /// Headers and rows are written the way the generator writes them, with
+    /// inline object keys in alphabetical order and no padding around cells.
+    /// Comments are kept and trimmed, runs of blank lines become one blank
+    /// line, and every section after the first is preceded by a blank line,
+    /// ahead of the comments above it. Lines end in `\n`, including the last.
+    ///
+    /// Formatting is idempotent and the result parses to the same value.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::OrtDocument;
+    ///
+    /// let content = "\n\n## Settings\nconfig:limits:\n  (retries:3 ,  timeout:30)   \n# Everyone\nusers:id,name:\n1 , John\n\n\n\n2,  Jane";
+    /// let formatted = OrtDocument::parse(content)?.formatted()?;
+    /// assert_eq!(formatted, "## Settings\nconfig:limits:\n(retries:3,timeout:30)\n\n# Everyone\nusers:id,name:\n1,John\n\n2,Jane\n");
+    ///
+    /// assert_eq!(OrtDocument::parse(&formatted)?.formatted()?, formatted);
+    /// assert_eq!(ort_rs::from_str(&formatted)?, ort_rs::from_str(content)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn formatted(&self) -> OrtResult<String> {
+        let options = self.generate_options();
+        let delimiter = self.options.delimiter;
+
+        // Lines inside block comments are kept as written
+        let mut texts: Vec<&str> = self.lines.iter().map(|line| line.text.as_str()).collect();
+        if self.options.multi_line_comment {
+            strip_block_comments(&mut texts)?;
+        }
+
+        let mut out: Vec<String> = vec![];
+        for (i, line) in self.lines.iter().enumerate() {
+            let text = trim_row(texts[i], delimiter);
+            if texts[i] != line.text {
+                out.push(line.text.trim_end().to_string());
+                continue;
+            }
+            if text.is_empty() {
+                if out.last().is_some_and(|last| !last.is_empty()) {
+                    out.push(String::new());
+                }
+                continue;
+            }
+            if is_blank_or_comment(text, &self.options) {
+                out.push(text.to_string());
+                continue;
+            }
+
+            if let Some(section) = self.sections.iter().find(|section| section.header == i) {
+                // Separate the section, along with the comments above it
+                let start = out.len() - out.iter().rev().take_while(|l| l.starts_with(self.options.comment_char)).count();
+                if start > 0 && !out[start - 1].is_empty() {
+                    out.insert(start, String::new());
+                }
+                out.push(match (section.key.is_empty(), section.fields.is_empty()) {
+                    // A top-level array such as `:[1,2]` is written as is
+                    (true, true) => text.to_string(),
+                    (false, true) => format!("{}:", escape(&section.key, delimiter)),
+                    _ => format!("{}:{}:", escape(&section.key, delimiter), format_fields(&section.fields, delimiter)),
+                });
+                continue;
+            }
+
+            let Some(section) = self.sections.iter().find(|section| section.rows.contains(&i)) else {
+                out.push(text.to_string());
+                continue;
+            };
+            let mut ctx = Context::with_options(&self.options);
+            out.push(if section.fields.is_empty() {
+                generate_value(&parse_value_line(text, i + 1, &mut ctx)?, &options)
+            } else {
+                let row = parse_row(text, i + 1, &section.fields, &mut ctx)?;
+                section
+                    .fields
+                    .iter()
+                    .map(|field| generate_field(field, &row[field.name()], &options))
+                    .collect::<Vec<_>>()
+                    .join(&delimiter.to_string())
+            });
+        }
+
+        while out.last().is_some_and(String::is_empty) {
+            out.pop();
+        }
+        Ok(out.iter().map(|line| format!("{}\n", line)).collect())
+    }
+
+    /// Parse the document as it is now into a value, as `parse_ort_with` would
+    pub fn to_value(&self) -> OrtResult<OrtValue> {
+        parse_ort_with(&self.to_string(), &self.options)
+    }
+
+    fn section(&self, key: &str) -> OrtResult<&Section> {
+        self.sections
+            .iter()
+            .find(|section| section.key == key)
+            .ok_or_else(|| OrtError::new(0, String::new(), format!("Section '{}' not found", key)))
+    }
+
+    /// Find the line of a row and the column of a field in it
+    fn locate(&self, key: &str, index: usize, field: &str) -> OrtResult<(usize, usize)> {
+        let section = self.section(key)?;
+        let line = *section.rows.get(index).ok_or_else(|| missing_row(key, index))?;
+        let column = section
+            .fields
+            .iter()
+            .position(|f| f.name() == field)
+            .ok_or_else(|| OrtError::new(0, String::new(), format!("Section '{}' has no field '{}'", key, field)))?;
+        Ok((line, column))
+    }
+
+    fn cells(&self, line: usize) -> OrtResult<Vec<&str>> {
+        let text = &self.lines[line].text;
+        split_values(trim_row(text, self.options.delimiter), self.options.delimiter)
+            .map_err(|rest| unterminated_quote(rest, text, line + 1))
+    }
+
+    /// Swap in an edited data line once it parses under its section's fields
+    fn replace(&mut self, line: usize, edited: String) -> OrtResult<()> {
+        let section = self.sections.iter().find(|section| section.rows.contains(&line)).expect("line belongs to a section");
+        let row = trim_row(&edited, self.options.delimiter);
+        if is_blank_or_comment(edited.trim(), &self.options) {
+            return Err(OrtError::new(line + 1, edited, "Row would read as a blank line or comment".to_string()));
+        }
+        let mut ctx = Context::with_options(&self.options);
+        if section.fields.is_empty() {
+            parse_value_line(row, line + 1, &mut ctx)?;
+        } else {
+            parse_row(row, line + 1, &section.fields, &mut ctx)?;
+        }
+
+        self.lines[line].text = edited;
+        Ok(())
+    }
+
+    fn generate_options(&self) -> GenerateOptions {
+        GenerateOptions {
+            delimiter: self.options.delimiter,
+            comment_char: self.options.comment_char,
+            ..Default::default()
+        }
+    }
+}
+
+fn missing_row(key: &str, index: usize) -> OrtError {
+    OrtError::new(0, String::new(), format!("Section '{}' has no row {}", key, index))
+}
+
+impl fmt::Display for OrtDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            f.write_str(&line.text)?;
+            f.write_str(line.ending)?;
+        }
+        Ok(())
+    }
+}
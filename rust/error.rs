@@ -1,32 +1,454 @@
 use std::fmt;
+use std::path::Path;
 
+/// Error raised while reading or writing ORT
+///
+/// `code` holds the offending line. When the failing part of the line is
+/// known, `span` gives its byte range within `code` and `column` its 1-based
+/// character column; otherwise they are `None`. Errors from
+/// `from_file` carry the file in `source_file`, and both renderings then
+/// start with `path:line:` like compiler output.
+///
+/// # Example
+/// ```
+/// let error = ort_rs::from_str("users:id,name:\n1,John,extra").unwrap_err();
+/// assert_eq!((error.line, error.column, error.span.as_deref()), (2, Some(8), Some(&(7, 12))));
+///
+/// let error = ort_rs::from_str("users:id,name,age:\n1,John").unwrap_err();
+/// assert_eq!((error.column, error.span.as_deref()), (Some(7), Some(&(6, 6))));
+///
+/// let error = ort_rs::from_str("places:id,pos(lat,lng):\n1,(1.5,2.5,3.5)").unwrap_err();
+/// assert_eq!((error.column, error.span.as_deref()), (Some(12), Some(&(11, 14))));
+///
+/// let error = ort_rs::from_str("users:id),name:\n1,John").unwrap_err();
+/// assert_eq!((error.line, error.column, error.span.as_deref()), (1, Some(9), Some(&(8, 9))));
+///
+/// let error = ort_rs::from_str("places:id,pos(lat,lng):\n1,(1.5)").unwrap_err();
+/// assert_eq!((error.column, error.span.as_deref()), (Some(7), Some(&(6, 7))));
+///
+/// assert!(error.to_string().starts_with("  2:7 | 1,(1.5)"));
+///
+/// let plain = format!("{:#}", error);
+/// assert_eq!(plain, "line 2: Expected 2 nested values but got 1 — \"1,(1.5)\"");
+/// assert!(!plain.contains("\x1b["));
+/// ```
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct OrtError {
     pub line: usize,
+    pub column: Option<usize>,
+    // Boxed, like `source`, to keep `OrtResult` small
+    pub span: Option<Box<(usize, usize)>>,
+    pub source_file: Option<Box<Path>>,
+    pub kind: OrtErrorKind,
     pub code: String,
     pub message: String,
+    /// Underlying cause, such as the `io::Error` of a failed read
+    pub source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+/// Category of an `OrtError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OrtErrorKind {
+    /// A header line could not be parsed
+    InvalidHeader,
+    /// A `)` without a matching `(`
+    UnmatchedParenthesis,
+    /// A row has more or fewer values than its header has fields
+    ValueCountMismatch,
+    /// A nested group has more or fewer values than its nested fields
+    NestedValueCountMismatch,
+    /// A section or key is defined more than once
+    DuplicateKey,
+    /// A quoted string has no closing `"`
+    UnterminatedQuote,
+    /// A `/*` comment has no closing `*/`
+    UnterminatedComment,
+    /// A cell does not hold the type its column declares
+    TypeMismatch,
+    /// An included file includes the file it is included from, directly or not
+    CircularInclude,
+    /// An included file is outside the directory of the file including it
+    IncludeOutsideDirectory,
+    /// Arrays, inline objects or nested fields are nested deeper than `max_depth`
+    DepthLimit,
+    /// A line or the row count is over its limit in `ParseOptions`
+    InputLimit,
+    /// The input is not valid UTF-8 or UTF-16
+    Encoding,
+    /// Reading or writing failed
+    Io,
+    /// Any other error
+    Other,
+}
+
+impl OrtErrorKind {
+    /// Get the snake_case name used in diagnostics
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrtErrorKind::InvalidHeader => "invalid_header",
+            OrtErrorKind::UnmatchedParenthesis => "unmatched_parenthesis",
+            OrtErrorKind::ValueCountMismatch => "value_count_mismatch",
+            OrtErrorKind::NestedValueCountMismatch => "nested_value_count_mismatch",
+            OrtErrorKind::DuplicateKey => "duplicate_key",
+            OrtErrorKind::UnterminatedQuote => "unterminated_quote",
+            OrtErrorKind::UnterminatedComment => "unterminated_comment",
+            OrtErrorKind::TypeMismatch => "type_mismatch",
+            OrtErrorKind::CircularInclude => "circular_include",
+            OrtErrorKind::IncludeOutsideDirectory => "include_outside_directory",
+            OrtErrorKind::DepthLimit => "depth_limit",
+            OrtErrorKind::InputLimit => "input_limit",
+            OrtErrorKind::Encoding => "encoding",
+            OrtErrorKind::Io => "io",
+            OrtErrorKind::Other => "other",
+        }
+    }
 }
 
 impl OrtError {
     pub fn new(line: usize, code: String, message: String) -> Self {
-        Self { line, code, message }
+        Self {
+            line,
+            column: None,
+            span: None,
+            source_file: None,
+            source: None,
+            kind: OrtErrorKind::Other,
+            code,
+            message,
+        }
+    }
+
+    /// Set the 1-based character column, without a span
+    pub fn with_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    /// Set the error category
+    pub fn with_kind(mut self, kind: OrtErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Set the file the error was read from
+    pub fn with_source_file<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.source_file = Some(path.as_ref().into());
+        self
+    }
+
+    /// Set the underlying cause returned by `Error::source`
+    pub fn with_source(mut self, source: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Point the error at the bytes `start..end` of the offending line
+    pub fn with_span(mut self, start: usize, end: usize) -> Self {
+        let start = start.min(self.code.len());
+        let end = end.clamp(start, self.code.len());
+        self.column = Some(self.code.get(..start).map_or(start, |s| s.chars().count()) + 1);
+        self.span = Some(Box::new((start, end)));
+        self
+    }
+
+    // Caret line under the span, keeping tabs so it lines up with the code
+    fn caret_line(&self) -> Option<String> {
+        let (before, width) = match self.span.as_deref() {
+            Some(&(start, end)) => {
+                let width = self.code.get(start..end).map_or(0, |s| s.chars().count());
+                (self.code.get(..start)?, width.max(1))
+            }
+            None => {
+                let start = self.code.char_indices().nth(self.column?.checked_sub(1)?)?.0;
+                (&self.code[..start], 1)
+            }
+        };
+
+        let mut result: String = before
+            .chars()
+            .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+        result.push_str(&"^".repeat(width));
+        Some(result)
+    }
+}
+
+impl OrtError {
+    /// Convert into a structured diagnostic for editors and tools
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let length = match self.span.as_deref() {
+            Some(&(start, end)) => self.code.get(start..end).map_or(0, |s| s.chars().count()),
+            None => 0,
+        };
+        Diagnostic {
+            line: self.line,
+            column: self.column.unwrap_or(0),
+            length,
+            kind: self.kind.name(),
+            severity: Severity::Error,
+            message: self.message.clone(),
+        }
+    }
+
+    /// Render the error on a single line without color
+    ///
+    /// This is what `{:#}` formats to, e.g. `line 2: Expected 2 values but got 3 — "1,John,extra"`.
+    pub fn to_plain_string(&self) -> String {
+        format!("{:#}", self)
+    }
+
+    /// Render the error over several lines: the code, a caret line and the message
+    ///
+    /// This is what `{}` formats to. The prefix reads `line:column` when the
+    /// column is known. It is colored when built with the `color` feature and
+    /// writing to a terminal.
+    pub fn render_pretty(&self) -> String {
+        let mut result = String::new();
+        if let Some(path) = &self.source_file {
+            result.push_str(&format!("{}\n", paint(&self.location(path), Color::Blue)));
+        }
+
+        let line_num = match self.column {
+            Some(column) => format!("{:3}:{}", self.line, column),
+            None => format!("{:3}", self.line),
+        };
+        result.push_str(&format!("{} | {}\n", paint(&line_num, Color::Blue), paint(&self.code, Color::White)));
+        if let Some(carets) = self.caret_line() {
+            result.push_str(&format!("{} | {}\n", " ".repeat(line_num.len()), paint(&carets, Color::Red)));
+        }
+        result.push_str(&format!("{} : {}", paint("Exception", Color::Red), paint(&self.message, Color::White)));
+        result
+    }
+
+    // `path:line:column:`, leaving out the parts that are unknown
+    fn location(&self, path: &Path) -> String {
+        let mut result = path.display().to_string();
+        if self.line > 0 {
+            result.push_str(&format!(":{}", self.line));
+            if let Some(column) = self.column {
+                result.push_str(&format!(":{}", column));
+            }
+        }
+        result.push(':');
+        result
     }
 }
 
 impl fmt::Display for OrtError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        use colored::Colorize;
-        writeln!(f, "{} | {}",
-            format!("{:3}", self.line).blue(),
-            self.code.white()
-        )?;
-        write!(f, "{} : {}",
-            "Exception".red(),
-            self.message.white()
-        )
+        if !f.alternate() {
+            return f.write_str(&self.render_pretty());
+        }
+
+        if let Some(path) = &self.source_file {
+            write!(f, "{} ", self.location(path))?;
+        } else if self.line > 0 {
+            write!(f, "line {}: ", self.line)?;
+        }
+        write!(f, "{}", self.message)?;
+        if !self.code.is_empty() {
+            write!(f, " — {:?}", self.code)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Color {
+    Blue,
+    Red,
+    White,
+}
+
+#[cfg(feature = "color")]
+fn paint(text: &str, color: Color) -> String {
+    use colored::Colorize;
+    match color {
+        Color::Blue => text.blue(),
+        Color::Red => text.red(),
+        Color::White => text.white(),
+    }
+    .to_string()
+}
+
+#[cfg(not(feature = "color"))]
+fn paint(text: &str, _color: Color) -> String {
+    text.to_string()
+}
+
+impl std::error::Error for OrtError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|e| e as _)
+    }
+}
+
+/// Every error found by a lenient parse, ordered by line
+///
+/// Displays one error per line in the single-line `{:#}` form of `OrtError`.
+#[derive(Debug, Default)]
+pub struct ParseErrors {
+    errors: Vec<OrtError>,
+}
+
+impl ParseErrors {
+    /// Check if no errors were found
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Get the number of errors
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Get the errors
+    pub fn errors(&self) -> &[OrtError] {
+        &self.errors
+    }
+
+    /// Convert into the list of errors
+    pub fn into_vec(self) -> Vec<OrtError> {
+        self.errors
+    }
+}
+
+impl From<OrtError> for ParseErrors {
+    fn from(error: OrtError) -> Self {
+        Self { errors: vec![error] }
+    }
+}
+
+impl From<Vec<OrtError>> for ParseErrors {
+    fn from(errors: Vec<OrtError>) -> Self {
+        Self { errors }
+    }
+}
+
+impl IntoIterator for ParseErrors {
+    type Item = OrtError;
+    type IntoIter = std::vec::IntoIter<OrtError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a ParseErrors {
+    type Item = &'a OrtError;
+    type IntoIter = std::slice::Iter<'a, OrtError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{:#}", error)?;
+        }
+        Ok(())
     }
 }
 
-impl std::error::Error for OrtError {}
+impl std::error::Error for ParseErrors {}
+
+/// Kind of an `OrtWarning`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarningKind {
+    /// An inline object repeats a key; the last value is kept
+    DuplicateKey,
+    /// A header repeats a field name; the last column is kept
+    DuplicateField,
+    /// An integer is too large to be stored exactly as f64
+    PrecisionLoss,
+}
+
+impl WarningKind {
+    /// Get the snake_case name used in diagnostics
+    pub fn name(&self) -> &'static str {
+        match self {
+            WarningKind::DuplicateKey => "duplicate_key",
+            WarningKind::DuplicateField => "duplicate_field",
+            WarningKind::PrecisionLoss => "precision_loss",
+        }
+    }
+}
+
+/// Suspicious input that was still parsed, reported by `parse_ort_with_warnings`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrtWarning {
+    pub line: usize,
+    pub column: usize,
+    /// Length of the offending token in characters
+    pub length: usize,
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl OrtWarning {
+    /// Convert into a structured diagnostic for editors and tools
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            line: self.line,
+            column: self.column,
+            length: self.length,
+            kind: self.kind.name(),
+            severity: Severity::Warning,
+            message: self.message.clone(),
+        }
+    }
+}
+
+/// Severity of a `Diagnostic`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    /// Get the lowercase name used in diagnostics
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// Machine-readable form of an error or warning
+///
+/// `column` is 1-based and, like `length`, counts characters; both are `0`
+/// when unknown. With the `serde` feature it serializes as
+/// `{"line": 2, "column": 8, "length": 5, "kind": "value_count_mismatch", "severity": "error", "message": "..."}`.
+///
+/// # Example
+/// ```
+/// let error = ort_rs::from_str("users:id,name:\n1,John,extra").unwrap_err();
+/// let diagnostic = error.to_diagnostic();
+/// assert_eq!((diagnostic.line, diagnostic.column, diagnostic.length), (2, 8, 5));
+/// assert_eq!(diagnostic.kind, "value_count_mismatch");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub length: usize,
+    pub kind: &'static str,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for OrtWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
 
 pub type OrtResult<T> = Result<T, OrtError>;
@@ -0,0 +1,173 @@
+use std::borrow::Cow;
+
+/// Escape a bare value, key or field name
+///
+/// Backslashes, the structural characters `,()[]:`, the delimiter and line
+/// breaks are escaped, as is a leading `#` that would otherwise start a
+/// comment and a leading `"` that would start a quoted string. `unescape` with
+/// the same delimiter gives back the original string.
+///
+/// # Example
+/// ```
+/// use ort_rs::escape::{escape, unescape};
+///
+/// assert_eq!(escape(r"C:\temp\x", ','), r"C\:\\temp\\x");
+/// assert_eq!(escape("#1, (a)", ','), r"\#1\, \(a\)");
+/// assert_eq!(escape("a|b", '|'), r"a\|b");
+/// assert_eq!(escape("\"a\" b", ','), r#"\"a" b"#);
+///
+/// assert_eq!(unescape(&escape(r"a\\,b\n", '|'), '|'), r"a\\,b\n");
+/// ```
+pub fn escape(s: &str, delimiter: char) -> String {
+    let mut result = String::with_capacity(s.len());
+
+    for ch in s.chars() {
+        match ch {
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            _ if is_structural(ch, delimiter) => {
+                result.push('\\');
+                result.push(ch);
+            }
+            _ => result.push(ch),
+        }
+    }
+
+    if result.starts_with('#') || result.starts_with('"') {
+        result.insert(0, '\\');
+    }
+    result
+}
+
+/// Check if a character is escaped by putting a backslash before it
+fn is_structural(ch: char, delimiter: char) -> bool {
+    matches!(ch, '\\' | ',' | '(' | ')' | '[' | ']' | ':') || ch == delimiter
+}
+
+/// Check if a character can separate values
+///
+/// Tab and punctuation such as `,`, `|` and `;` can. ASCII letters and
+/// digits, `.+-_#`, other whitespace and the structural characters `:\()[]"`
+/// cannot, as they are part of values or of the syntax around them.
+///
+/// # Example
+/// ```
+/// use ort_rs::escape::is_valid_delimiter;
+///
+/// assert!([',', '|', ';', '\t'].into_iter().all(is_valid_delimiter));
+/// assert!(!['a', '1', '.', '-', '#', ' ', ':', '(', '"'].into_iter().any(is_valid_delimiter));
+///
+/// // Parsing and generating check it too
+/// use ort_rs::{GenerateOptions, OrtValue, ParseOptions};
+/// let error = ort_rs::parse_ort_with("n:\n1.5", &ParseOptions::new().delimiter('.')).unwrap_err();
+/// assert_eq!(error.message, "Invalid delimiter '.'");
+/// let options = GenerateOptions { delimiter: '-', ..Default::default() };
+/// assert!(ort_rs::generate_ort_with(&OrtValue::from(-1), &options).is_err());
+/// ```
+pub fn is_valid_delimiter(ch: char) -> bool {
+    ch == '\t' || !(ch.is_ascii_alphanumeric() || ch.is_whitespace() || ch.is_control() || ".+-_#:\\()[]\"".contains(ch))
+}
+
+/// Count the characters `escape` would escape, ignoring a leading `#`
+pub(crate) fn count_escapes(s: &str, delimiter: char) -> usize {
+    s.chars()
+        .filter(|&ch| matches!(ch, '\n' | '\t' | '\r') || is_structural(ch, delimiter))
+        .count()
+}
+
+/// Unescape a bare value, key or field name
+///
+/// The escapes are `\\`, `\,`, `\(`, `\)`, `\[`, `\]`, `\:`, `\#`, `\"`, `\n`,
+/// `\t`, `\r` and a backslash before the delimiter or a space. Any other
+/// backslash is kept as it is, so `\x` reads as `\x` and a trailing `\` stays.
+///
+/// # Example
+/// ```
+/// use ort_rs::escape::unescape;
+///
+/// assert_eq!(unescape(r"a\,b\:c", ','), "a,b:c");
+/// assert_eq!(unescape(r"C:\\temp\x\", ','), r"C:\temp\x\");
+/// assert_eq!(unescape(r"a\|b", '|'), "a|b");
+/// assert_eq!(unescape(r"a\|b", ','), r"a\|b");
+/// assert_eq!(unescape(r"size\ int", ','), "size int");
+/// ```
+pub fn unescape(s: &str, delimiter: char) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(ch @ ('\\' | ',' | '(' | ')' | '[' | ']' | ':' | '#' | '"')) => result.push(ch),
+            Some(ch) if ch == delimiter || ch.is_whitespace() => result.push(ch),
+            Some(ch) => {
+                result.push('\\');
+                result.push(ch);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    Cow::Owned(result)
+}
+
+/// Wrap a string in double quotes, escaping only quotes, backslashes and line breaks
+pub(crate) fn quote(s: &str) -> String {
+    let mut result = String::from('"');
+
+    for ch in s.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            _ => result.push(ch),
+        }
+    }
+
+    result.push('"');
+    result
+}
+
+/// Unescape the content of a quoted string, where only `\"`, `\\` and the
+/// line escapes `\n`, `\t` and `\r` are recognized
+pub(crate) fn unescape_quoted(s: &str) -> Cow<'_, str> {
+    if !s.contains('\\') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut result = String::new();
+    let mut chars = s.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(ch @ ('"' | '\\')) => result.push(ch),
+            Some(ch) => {
+                result.push('\\');
+                result.push(ch);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    Cow::Owned(result)
+}
@@ -0,0 +1,239 @@
+use crate::error::OrtResult;
+use crate::escape::unescape;
+use crate::fields::FieldSpec;
+use crate::ort_value_ref::OrtValueRef;
+use crate::parser::{
+    is_blank_or_comment, is_header, parse_fields, parse_header, parse_row_borrowed, section_at, parse_value_borrowed,
+    split_values, to_field_specs, unterminated_quote, ParseOptions,
+};
+use std::iter::Enumerate;
+use std::str::Lines;
+
+/// Callbacks for the sections and rows found by `SaxParser`
+///
+/// Every method has an empty default, so handlers only implement the events
+/// they care about. Rows borrow from the parsed content wherever no unescaping
+/// was needed, and may be kept for as long as the content lives.
+pub trait SaxHandler<'a> {
+    /// A header was read; `key` is `None` for a top-level `:fields:` section
+    fn on_section_start(&mut self, _key: Option<&str>, _fields: &[FieldSpec]) {}
+
+    /// A data line was read, as an object for sections with fields and as the
+    /// plain value otherwise
+    fn on_row(&mut self, _row: OrtValueRef<'a>) {}
+
+    /// The last row of the section has been delivered
+    fn on_section_end(&mut self, _key: Option<&str>) {}
+}
+
+/// Event-driven ORT parser that hands sections and rows to a `SaxHandler`
+///
+/// No document tree is built: each row is parsed, passed to the handler and
+/// dropped unless the handler keeps it. Sections are reported in document
+/// order, including repeated keys, and each data line of a section without
+/// fields becomes its own row.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use std::borrow::Cow;
+/// use ort_rs::{FieldSpec, OrtValueRef, SaxHandler, SaxParser};
+///
+/// #[derive(Default)]
+/// struct Names<'a> {
+///     sections: Vec<String>,
+///     names: Vec<&'a str>,
+/// }
+///
+/// impl<'a> SaxHandler<'a> for Names<'a> {
+///     fn on_section_start(&mut self, key: Option<&str>, fields: &[FieldSpec]) {
+///         self.sections.push(format!("{}:{}", key.unwrap_or(""), fields.len()));
+///     }
+///
+///     fn on_row(&mut self, row: OrtValueRef<'a>) {
+///         if let Some(OrtValueRef::String(Cow::Borrowed(name))) = row.get("name") {
+///             self.names.push(*name);
+///         }
+///     }
+/// }
+///
+/// let content = "users:id,name:\n1,John\n2,Jane\n\ncount:\n2\n";
+/// let mut handler = Names::default();
+/// let mut parser = SaxParser::new();
+/// parser.parse(content, &mut handler)?;
+///
+/// assert_eq!(handler.sections, ["users:2", "count:0"]);
+/// assert_eq!(handler.names, ["John", "Jane"]);
+/// assert_eq!(parser.rows_read(), 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct SaxParser {
+    rows: usize,
+}
+
+impl SaxParser {
+    /// Create a parser
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of rows delivered by the last call to `parse`
+    pub fn rows_read(&self) -> usize {
+        self.rows
+    }
+
+    /// Parse `content`, calling the handler for every section and row
+    ///
+    /// Parsing stops at the first error, after the events for everything
+    /// before it have been delivered.
+    pub fn parse<'a>(&mut self, content: &'a str, handler: &mut dyn SaxHandler<'a>) -> OrtResult<()> {
+        let options = ParseOptions::default();
+        let lines: Vec<&'a str> = content.lines().collect();
+        let mut line_idx = 0;
+        self.rows = 0;
+
+        while line_idx < lines.len() {
+            let line = lines[line_idx].trim();
+            if is_blank_or_comment(line, &options) || !line.contains(':') {
+                line_idx += 1;
+                continue;
+            }
+
+            let (key, fields, data_lines) = section_at(&lines, line_idx, &options)?;
+            let key = key.map(|key| unescape(key, options.delimiter));
+            handler.on_section_start(key.as_deref(), &to_field_specs(&fields, options.delimiter));
+
+            line_idx += 1;
+            let mut remaining = data_lines;
+            while remaining > 0 {
+                let row = lines[line_idx].trim();
+                let line_num = line_idx + 1;
+                line_idx += 1;
+                if is_blank_or_comment(row, &options) {
+                    continue;
+                }
+
+                let value = if fields.is_empty() {
+                    parse_value_borrowed(row, row, line_num, 0)?
+                } else {
+                    parse_row_borrowed(row, line_num, &fields)?
+                };
+                handler.on_row(value);
+                self.rows += 1;
+                remaining -= 1;
+            }
+
+            handler.on_section_end(key.as_deref());
+        }
+
+        Ok(())
+    }
+}
+
+/// A token produced by `OrtPullParser`
+///
+/// Tokens are raw slices of the input: keys, field names and cells are still
+/// escaped, and quoted cells keep their quotes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrtToken<'a> {
+    /// A header line, with `key` set to `None` for a top-level `:fields:` header
+    /// and one entry per top-level field, nested fields included as written
+    SectionHeader { key: Option<&'a str>, fields: Vec<&'a str> },
+    /// A data line split into its cells
+    DataRow(Vec<&'a str>),
+}
+
+/// Pull parser that turns ORT content into tokens one line at a time
+///
+/// Nothing past the current line is looked at, so a search can stop as soon
+/// as it finds what it needs. Blank lines and comments produce no tokens.
+/// Rows are split but not checked against their header; the first error ends
+/// the iteration.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{OrtPullParser, OrtToken};
+///
+/// let content = "users:id,name,address(city,zip):\n1,John,(Seoul,04524)\n2,Jane,(Busan,48058)\n";
+/// let mut parser = OrtPullParser::new(content);
+///
+/// assert_eq!(
+///     parser.next().transpose()?,
+///     Some(OrtToken::SectionHeader { key: Some("users"), fields: vec!["id", "name", "address(city,zip)"] })
+/// );
+///
+/// // Stop at the first row for Jane without reading further
+/// let row = parser
+///     .find_map(|token| match token {
+///         Ok(OrtToken::DataRow(cells)) if cells[1] == "Jane" => Some(Ok(cells)),
+///         Err(e) => Some(Err(e)),
+///         _ => None,
+///     })
+///     .transpose()?;
+/// assert_eq!(row, Some(vec!["2", "Jane", "(Busan,48058)"]));
+/// # Ok(())
+/// # }
+/// ```
+pub struct OrtPullParser<'a> {
+    lines: Enumerate<Lines<'a>>,
+    options: ParseOptions,
+    failed: bool,
+}
+
+impl<'a> OrtPullParser<'a> {
+    /// Create a pull parser over ORT content
+    pub fn new(content: &'a str) -> Self {
+        Self {
+            lines: content.lines().enumerate(),
+            options: ParseOptions::default(),
+            failed: false,
+        }
+    }
+
+    fn token(&self, line: &'a str, line_num: usize) -> OrtResult<OrtToken<'a>> {
+        let delimiter = self.options.delimiter;
+
+        if is_header(line) {
+            let (key, fields_str) = parse_header(line, line_num)?;
+            parse_fields(fields_str, line, line_num, &self.options)?;
+            let fields = if fields_str.is_empty() {
+                vec![]
+            } else {
+                split_values(fields_str, delimiter)
+                    .map_err(|rest| unterminated_quote(rest, line, line_num))?
+                    .into_iter()
+                    .map(str::trim)
+                    .collect()
+            };
+            return Ok(OrtToken::SectionHeader { key, fields });
+        }
+
+        let cells = split_values(line, delimiter).map_err(|rest| unterminated_quote(rest, line, line_num))?;
+        Ok(OrtToken::DataRow(cells))
+    }
+}
+
+impl<'a> Iterator for OrtPullParser<'a> {
+    type Item = OrtResult<OrtToken<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        for (i, line) in self.lines.by_ref() {
+            let line = line.trim();
+            if is_blank_or_comment(line, &self.options) {
+                continue;
+            }
+
+            let token = self.token(line, i + 1);
+            self.failed = token.is_err();
+            return Some(token);
+        }
+        None
+    }
+}
@@ -0,0 +1,204 @@
+use crate::error::OrtResult;
+use crate::escape::escape;
+use crate::parser::{parse_fields, to_field_specs, ParseOptions};
+use std::fmt;
+
+/// Field declaration from an ORT header
+///
+/// `id,profile(name,age)` is made of a `Simple("id")` field and a
+/// `Nested("profile", ...)` field. A nested field holds either one object,
+/// written `(John,30)`, or an array of them, written `[(John,30),(Jane,25)]`.
+/// A field may declare its type after its name, as in `id int`, which makes it
+/// `Typed("id", FieldType::Int)`.
+///
+/// Headers are parsed and written with the same grammar as `parse_ort` and
+/// the generator use:
+///
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{FieldSpec, FieldType};
+///
+/// let header = "id int,name,profile(address(city,geo(lat,lng)),tags [str]),a\\,b";
+/// let fields = FieldSpec::parse(header)?;
+/// assert_eq!(fields[0], FieldSpec::Typed("id".to_string(), FieldType::Int));
+/// assert_eq!(fields[3], FieldSpec::Simple("a,b".to_string()));
+/// assert_eq!(fields[2].to_string(), "profile(address(city,geo(lat,lng)),tags [str])");
+///
+/// let written = FieldSpec::to_header_string(&fields);
+/// assert_eq!(written, header);
+/// assert_eq!(FieldSpec::parse(&written)?, fields);
+///
+/// // A name ending in a type name keeps it, with the space before it escaped
+/// let untyped = vec![FieldSpec::Simple("size int".to_string())];
+/// assert_eq!(FieldSpec::to_header_string(&untyped), "size\\ int");
+/// assert_eq!(FieldSpec::parse("size\\ int")?, untyped);
+///
+/// assert_eq!(FieldSpec::parse("name)").unwrap_err().message, "Unmatched closing parenthesis");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldSpec {
+    Simple(String),
+    Nested(String, Vec<FieldSpec>),
+    Typed(String, FieldType),
+}
+
+impl FieldSpec {
+    /// Get the field name
+    pub fn name(&self) -> &str {
+        match self {
+            FieldSpec::Simple(name) => name,
+            FieldSpec::Nested(name, _) => name,
+            FieldSpec::Typed(name, _) => name,
+        }
+    }
+
+    /// Parse the fields of a header, written without the key and colons
+    pub fn parse(s: &str) -> OrtResult<Vec<FieldSpec>> {
+        let options = ParseOptions::default();
+        let fields = parse_fields(s, s, 1, &options)?;
+        Ok(to_field_specs(&fields, options.delimiter))
+    }
+
+    /// Write fields as they appear in a header, without the key and colons
+    pub fn to_header_string(fields: &[FieldSpec]) -> String {
+        format_fields(fields, ',')
+    }
+}
+
+impl fmt::Display for FieldSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_field(self, ','))
+    }
+}
+
+/// Type declared for a column in a header
+///
+/// Cells of a typed column are checked instead of inferred, so a number under
+/// a `str` column stays a string. Empty cells are null under any type.
+///
+/// # Example
+/// ```
+/// use ort_rs::{parse_ort, FieldType, OrtErrorKind};
+///
+/// let value = parse_ort("users:id int,zip str,score float,active bool,tags [str]:\n1,02134,2.5,true,[1,a]\n2,,3,false,[]").unwrap();
+/// let user = &value["users"][0];
+/// assert_eq!(user["id"].as_f64(), Some(1.0));
+/// assert_eq!(user["zip"].as_str(), Some("02134"));
+/// assert_eq!(user["score"].as_f64(), Some(2.5));
+/// assert_eq!(user["active"].as_bool(), Some(true));
+/// assert_eq!(user["tags"][0].as_str(), Some("1"));
+/// assert!(value["users"][1]["zip"].is_null());
+///
+/// let error = parse_ort("users:id int:\nabc").unwrap_err();
+/// assert_eq!(error.kind, OrtErrorKind::TypeMismatch);
+/// assert_eq!(error.message, "Column 'id' expects int but found 'abc'");
+///
+/// let error = parse_ort("users:tags [int]:\n[1,x]").unwrap_err();
+/// assert_eq!(error.message, "Column 'tags' expects int but found 'x'");
+///
+/// assert_eq!(FieldType::parse("[bool]"), Some(FieldType::Array(Box::new(FieldType::Bool))));
+/// assert_eq!(FieldType::parse("name"), None);
+/// ```
+///
+/// With `typed_headers`, the generator declares the type of every column
+/// whose values share one:
+///
+/// ```
+/// use ort_rs::{generate_ort_with, parse_ort, GenerateOptions, Map, OrtValue};
+///
+/// let row = |id: i64, zip: &str, score: f64, tags: &[&str]| OrtValue::Object(Map::from([
+///     ("id".to_string(), OrtValue::from(id)),
+///     ("zip".to_string(), OrtValue::from(zip)),
+///     ("score".to_string(), OrtValue::from(score)),
+///     ("active".to_string(), OrtValue::from(id == 1)),
+///     ("tags".to_string(), OrtValue::Array(tags.iter().map(|&t| OrtValue::from(t)).collect())),
+/// ]));
+/// let value = OrtValue::Object(Map::from([
+///     ("users".to_string(), OrtValue::Array(vec![row(1, "02134", 1.0, &["7"]), row(2, "x", 2.5, &[])])),
+/// ]));
+///
+/// let ort = generate_ort_with(&value, &GenerateOptions { typed_headers: true, ..Default::default() }).unwrap();
+/// assert_eq!(ort.lines().next(), Some("users:active bool,id int,score float,tags [str],zip str:"));
+/// assert_eq!(parse_ort(&ort).unwrap(), value);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    /// A whole number, written without a fraction or exponent
+    Int,
+    /// Any number
+    Float,
+    /// A string, even if it looks like a number or boolean
+    Str,
+    /// `true` or `false`
+    Bool,
+    /// An array whose items all have the given type
+    Array(Box<FieldType>),
+}
+
+impl FieldType {
+    /// Parse a type name such as `int` or `[str]`
+    pub fn parse(s: &str) -> Option<FieldType> {
+        // Unwrap the brackets first, so deeply nested arrays don't recurse
+        let mut item = s;
+        let mut levels = 0;
+        while let Some(inner) = item.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+            item = inner.trim();
+            levels += 1;
+        }
+
+        let mut ty = match item {
+            "int" => FieldType::Int,
+            "float" => FieldType::Float,
+            "str" => FieldType::Str,
+            "bool" => FieldType::Bool,
+            _ => return None,
+        };
+        for _ in 0..levels {
+            ty = FieldType::Array(Box::new(ty));
+        }
+        Some(ty)
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldType::Int => f.write_str("int"),
+            FieldType::Float => f.write_str("float"),
+            FieldType::Str => f.write_str("str"),
+            FieldType::Bool => f.write_str("bool"),
+            FieldType::Array(item) => write!(f, "[{}]", item),
+        }
+    }
+}
+
+/// Format fields as they appear in a header, without the surrounding colons
+pub(crate) fn format_fields(fields: &[FieldSpec], delimiter: char) -> String {
+    fields
+        .iter()
+        .map(|field| format_field(field, delimiter))
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+// A last word naming a type would read back as the field's type, so the
+// space before it is escaped
+fn escape_name(name: &str, delimiter: char) -> String {
+    let mut result = escape(name, delimiter);
+    if let Some((i, ch)) = result.char_indices().rev().find(|&(_, ch)| ch.is_whitespace()) {
+        if FieldType::parse(&result[i + ch.len_utf8()..]).is_some() {
+            result.insert(i, '\\');
+        }
+    }
+    result
+}
+
+fn format_field(field: &FieldSpec, delimiter: char) -> String {
+    match field {
+        FieldSpec::Simple(name) => escape_name(name, delimiter),
+        FieldSpec::Nested(name, nested) => format!("{}({})", escape(name, delimiter), format_fields(nested, delimiter)),
+        FieldSpec::Typed(name, ty) => format!("{} {}", escape(name, delimiter), ty),
+    }
+}
@@ -1,322 +1,1108 @@
-use crate::ort_value::OrtValue;
+use crate::error::{OrtError, OrtErrorKind, OrtResult};
+use crate::escape::{count_escapes, escape, quote};
+use crate::fields::{format_fields, FieldSpec, FieldType};
+use crate::ort_value::{Map, OrtValue};
+use crate::parser::{check_delimiter, infer_scalar, Scalar};
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt::{self, Write};
 
+/// Generate ORT from a value
+///
+/// Strings that would read back as a number or boolean, such as `"true"` or
+/// the zip code `"02134"`, are written in double quotes to keep their type.
+/// Other strings stay bare. An empty cell is null and `""` is the empty string.
+/// A leading `#` is written as `\\#` so the line isn't read as a comment.
+///
+/// # Example
+/// ```
+/// use ort_rs::{generate_ort, parse_ort, OrtValue};
+/// use ort_rs::Map;
+///
+/// let ambiguous = ["", "true", "false", "42", "1e5", "-0", "null", "02134", "007", "1.50", "NaN", "inf", " padded ", "\"quoted\""];
+/// for s in ambiguous.iter().chain(&["plain", "null-ish", "1,2"]) {
+///     let string = OrtValue::String(s.to_string());
+///     let row = OrtValue::Object(Map::from([("code".to_string(), string.clone())]));
+///     let values = [
+///         OrtValue::Object(Map::from([("v".to_string(), string.clone())])),
+///         OrtValue::Object(Map::from([("v".to_string(), OrtValue::Array(vec![string.clone()]))])),
+///         OrtValue::Object(Map::from([("v".to_string(), OrtValue::Array(vec![row.clone(), row]))])),
+///     ];
+///     for value in values {
+///         assert_eq!(parse_ort(&generate_ort(&value)).unwrap(), value, "{:?}", s);
+///     }
+/// }
+///
+/// let empty = || OrtValue::String(String::new());
+/// let group = OrtValue::Object(Map::from([("a".to_string(), OrtValue::Null), ("b".to_string(), empty())]));
+/// let row = OrtValue::Object(Map::from([
+///     ("null".to_string(), OrtValue::Null),
+///     ("empty".to_string(), empty()),
+///     ("list".to_string(), OrtValue::Array(vec![OrtValue::Null, empty(), OrtValue::Null, empty()])),
+///     ("group".to_string(), group),
+/// ]));
+/// let value = OrtValue::Object(Map::from([("rows".to_string(), OrtValue::Array(vec![row.clone(), row]))]));
+/// assert_eq!(parse_ort(&generate_ort(&value)).unwrap(), value);
+///
+/// let hashtags = OrtValue::Object(Map::from([
+///     ("#tags".to_string(), OrtValue::Array(vec![
+///         OrtValue::Object(Map::from([("id".to_string(), OrtValue::String("#1".to_string()))])),
+///         OrtValue::Object(Map::from([("id".to_string(), OrtValue::String("#2".to_string()))])),
+///     ])),
+///     ("hash".to_string(), OrtValue::String("#".to_string())),
+/// ]));
+/// let ort = generate_ort(&hashtags);
+/// assert!(ort.contains("\\#tags:id:\n\\#1\n\\#2") && ort.contains("hash:\n\\#"));
+/// assert_eq!(parse_ort(&ort).unwrap(), hashtags);
+///
+/// let zips = OrtValue::Object(Map::from([("zip".to_string(), OrtValue::String("02134".to_string()))]));
+/// assert_eq!(generate_ort(&zips), "zip:\n\"02134\"");
+/// ```
+///
+/// NaN and the infinities are written as `nan`, `inf` and `-inf`, which read
+/// back as numbers in any letter case. NaN equals NaN as an `OrtValue`, so
+/// values holding it still compare equal after a round trip.
+///
+/// ```
+/// use ort_rs::{generate_ort, parse_ort, Map, OrtValue};
+///
+/// let specials = || vec![OrtValue::from(f64::NAN), OrtValue::from(f64::INFINITY), OrtValue::from(f64::NEG_INFINITY)];
+/// let row = |value: OrtValue| OrtValue::Object(Map::from([("reading".to_string(), value)]));
+/// let value = OrtValue::Object(Map::from([
+///     ("list".to_string(), OrtValue::Array(specials())),
+///     ("rows".to_string(), OrtValue::Array(specials().into_iter().map(row).collect())),
+/// ]));
+///
+/// let ort = generate_ort(&value);
+/// assert_eq!(ort, "list:\n[nan,inf,-inf]\n\nrows:reading:\nnan\ninf\n-inf\n");
+/// assert_eq!(parse_ort(&ort).unwrap(), value);
+///
+/// let parsed = &parse_ort("values:\n[NaN,Inf,-INF,\"inf\"]").unwrap()["values"];
+/// assert!(parsed[0].as_f64().unwrap().is_nan());
+/// assert_eq!(parsed[1].as_f64(), Some(f64::INFINITY));
+/// assert_eq!(parsed[2].as_f64(), Some(f64::NEG_INFINITY));
+/// assert_eq!(parsed[3].as_str(), Some("inf"));
+/// ```
+///
+/// Rows with different fields still make one table, whose header is the union
+/// of their fields. A row lacking a field reads back with null there.
+///
+/// ```
+/// use ort_rs::{generate_ort, parse_ort, OrtValue};
+/// use ort_rs::Map;
+///
+/// let object = |pairs: Vec<(&str, OrtValue)>| OrtValue::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect());
+/// let rows = vec![
+///     object(vec![("name", "John".into()), ("nickname", "Jo".into()), ("pos", object(vec![("lat", 1.5.into())]))]),
+///     object(vec![("id", 2.into()), ("nickname", "Ja".into()), ("pos", object(vec![("lng", 2.5.into())]))]),
+///     object(vec![("id", 3.into()), ("name", "Bob".into())]),
+/// ];
+/// let value = object(vec![("users", OrtValue::Array(rows))]);
+///
+/// let parsed = parse_ort(&generate_ort(&value)).unwrap();
+/// for row in parsed["users"].as_array().unwrap() {
+///     let mut keys: Vec<_> = row.as_object().unwrap().keys().collect();
+///     keys.sort();
+///     assert_eq!(keys, ["id", "name", "nickname", "pos"]);
+/// }
+/// assert_eq!(parsed["users"][0]["id"], OrtValue::Null);
+/// assert_eq!(parsed["users"][0]["pos"]["lat"].as_f64(), Some(1.5));
+/// assert_eq!(parsed["users"][1]["pos"]["lat"], OrtValue::Null);
+/// assert_eq!(parsed["users"][1]["pos"]["lng"].as_f64(), Some(2.5));
+/// assert_eq!(parsed["users"][2]["pos"], OrtValue::Null);
+/// assert_eq!(parsed["users"][2]["name"].as_str(), Some("Bob"));
+/// ```
+///
+/// A column holding arrays of objects that all have the same keys declares
+/// them in the header, as in `orders(id,total)`, and each cell lists only the
+/// values, as in `[(1,9.99),(2,4.5)]`. Arrays whose objects differ keep their
+/// keys inline.
+///
+/// ```
+/// use ort_rs::{generate_ort, parse_ort, OrtValue};
+///
+/// let object = |pairs: Vec<(&str, OrtValue)>| OrtValue::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect());
+/// let item = |sku: &str, qty: i64| object(vec![("sku", sku.into()), ("qty", qty.into())]);
+/// let order = |id: i64, total: f64, items: Vec<OrtValue>| {
+///     object(vec![("id", id.into()), ("total", total.into()), ("items", OrtValue::Array(items))])
+/// };
+/// let user = |name: &str, orders: Vec<OrtValue>| object(vec![("name", name.into()), ("orders", OrtValue::Array(orders))]);
+///
+/// let value = object(vec![("users", OrtValue::Array(vec![
+///     user("John", vec![order(1, 9.99, vec![item("a,1", 2), item("b", 1)]), order(2, 4.5, vec![])]),
+///     user("Jane", vec![]),
+///     user("Bob", vec![order(3, 1.25, vec![item("(c)", 5)])]),
+/// ]))]);
+/// let ort = generate_ort(&value);
+/// assert_eq!(
+///     ort,
+///     "users:name,orders(id,items(qty,sku),total):\n\
+///      John,[(1,[(2,a\\,1),(1,b)],9.99),(2,[],4.5)]\n\
+///      Jane,[]\n\
+///      Bob,[(3,[(5,\\(c\\))],1.25)]",
+/// );
+/// assert_eq!(parse_ort(&ort).unwrap(), value);
+///
+/// let ragged = object(vec![("users", OrtValue::Array(vec![
+///     user("John", vec![object(vec![("id", 1.into())]), object(vec![("id", 2.into()), ("note", "x".into())])]),
+/// ]))]);
+/// let ort = generate_ort(&ragged);
+/// assert_eq!(ort, "users:name,orders:\nJohn,[(id:1),(id:2,note:x)]");
+/// assert_eq!(parse_ort(&ort).unwrap(), ragged);
+/// ```
 pub fn generate_ort(value: &OrtValue) -> String {
+    let mut result = String::new();
+    write_ort(&mut result, value, &GenerateOptions::default()).expect("writing to a String cannot fail");
+    result
+}
+
+/// Options for `generate_ort_with`
+///
+/// The defaults are what `generate_ort` writes.
+///
+/// # Example
+/// ```
+/// use ort_rs::{generate_ort_with, parse_ort_with, GenerateOptions, OrtValue, ParseOptions};
+/// use ort_rs::Map;
+///
+/// let row = OrtValue::Object(Map::from([("name".to_string(), OrtValue::from("Doe, John"))]));
+/// let value = OrtValue::Object(Map::from([("users".to_string(), OrtValue::Array(vec![row.clone(), row]))]));
+///
+/// let tsv = generate_ort_with(&value, &GenerateOptions { delimiter: '\t', ..Default::default() }).unwrap();
+/// assert_eq!(tsv, "users:name:\nDoe\\, John\nDoe\\, John");
+///
+/// let parsed = parse_ort_with(&tsv, &ParseOptions::new().delimiter('\t')).unwrap();
+/// assert_eq!(parsed, value);
+///
+/// let quoted = generate_ort_with(&value, &GenerateOptions { quote_strings: true, ..Default::default() }).unwrap();
+/// assert_eq!(quoted, "users:name:\n\"Doe, John\"\n\"Doe, John\"");
+/// assert_eq!(ort_rs::parse_ort(&quoted).unwrap(), value);
+/// ```
+///
+/// Strings that would need more than `quote_threshold` backslashes are quoted
+/// instead, which keeps prose readable:
+///
+/// ```
+/// use ort_rs::{generate_ort, generate_ort_with, parse_ort, GenerateOptions, Map, OrtValue};
+///
+/// let prose = "Hello, world (draft) [v2]: see #3, \"quotes\" and a \\ too";
+/// let note = OrtValue::Object(Map::from([("note".to_string(), OrtValue::from(prose))]));
+/// assert_eq!(generate_ort(&note), format!("note:\n{:?}", prose));
+///
+/// let row = OrtValue::Object(Map::from([("text".to_string(), OrtValue::from(prose)), ("n".to_string(), OrtValue::from(1))]));
+/// let value = OrtValue::Object(Map::from([
+///     ("rows".to_string(), OrtValue::Array(vec![row.clone(), row.clone()])),
+///     ("list".to_string(), OrtValue::Array(vec![OrtValue::from(prose), OrtValue::from("a,b")])),
+///     ("inline".to_string(), row),
+/// ]));
+/// for quote_threshold in [0, 2, usize::MAX] {
+///     let ort = generate_ort_with(&value, &GenerateOptions { quote_threshold, ..Default::default() }).unwrap();
+///     assert_eq!(parse_ort(&ort).unwrap(), value);
+/// }
+/// ```
+///
+/// Keys are sorted by default. Nulls are empty cells unless `null_output`
+/// says otherwise, and `number_precision` rounds fractional numbers.
+///
+/// ```
+/// use ort_rs::{generate_ort_with, GenerateOptions, KeyOrder, LineEnding, NullOutput, OrtValue};
+/// use ort_rs::Map;
+///
+/// let value = OrtValue::Object(Map::from([
+///     ("name".to_string(), OrtValue::from("Alice")),
+///     ("id".to_string(), OrtValue::from(1)),
+///     ("score".to_string(), OrtValue::from(2.0 / 3.0)),
+///     ("note".to_string(), OrtValue::Null),
+/// ]));
+///
+/// let options = GenerateOptions {
+///     key_order: KeyOrder::Custom(vec!["name".to_string(), "id".to_string()]),
+///     null_output: NullOutput::Skip,
+///     number_precision: Some(2),
+///     line_ending: LineEnding::CrLf,
+///     indent_nested: true,
+///     ..Default::default()
+/// };
+/// assert_eq!(
+///     generate_ort_with(&value, &options).unwrap(),
+///     "name:\r\n  Alice\r\n\r\nid:\r\n  1\r\n\r\nscore:\r\n  0.67\r\n",
+/// );
+///
+/// let keyword = GenerateOptions { null_output: NullOutput::Keyword, ..Default::default() };
+/// let list = OrtValue::Object(Map::from([("list".to_string(), OrtValue::Array(vec![OrtValue::Null, "null".into()]))]));
+/// assert_eq!(generate_ort_with(&list, &keyword).unwrap(), "list:\n[null,\"null\"]");
+///
+/// let grouped = GenerateOptions { underscore_grouping: true, ..Default::default() };
+/// let list = OrtValue::Object(Map::from([("list".to_string(), OrtValue::Array(vec![
+///     1234.into(), 12345.into(), (-1234567).into(), 12345.5.into(), "12_345".into(),
+/// ]))]));
+/// let ort = generate_ort_with(&list, &grouped).unwrap();
+/// assert_eq!(ort, "list:\n[1234,12_345,-1_234_567,12345.5,\"12_345\"]");
+/// assert_eq!(ort_rs::parse_ort(&ort).unwrap(), list);
+/// ```
+///
+/// `align_columns` pads table cells after their value so the columns line up.
+/// Cells are trimmed when parsed, and strings with leading or trailing spaces
+/// are always quoted, so the padding never becomes part of a value.
+///
+/// ```
+/// use ort_rs::{generate_ort_with, parse_ort_with, GenerateOptions, OrtValue, ParseOptions};
+/// use ort_rs::Map;
+///
+/// let user = |id: i64, name: &str, city: &str| {
+///     let address = OrtValue::Object(Map::from([("city".to_string(), OrtValue::from(city))]));
+///     OrtValue::Object(Map::from([
+///         ("id".to_string(), OrtValue::from(id)),
+///         ("name".to_string(), OrtValue::from(name)),
+///         ("address".to_string(), address),
+///     ]))
+/// };
+/// let value = OrtValue::Object(Map::from([("users".to_string(), OrtValue::Array(vec![
+///     user(1, "Al", "서울"),
+///     user(1_234_567, "Jane Doe-Smithson, Esq.", ""),
+///     user(-42, " padded ", "Busan (south)"),
+///     user(7, "", "Rome"),
+/// ]))]));
+///
+/// let options = GenerateOptions { align_columns: true, ..Default::default() };
+/// assert_eq!(
+///     generate_ort_with(&value, &options).unwrap(),
+///     "users:address(city),id,name:\n\
+///      (서울)           ,1      ,Al\n\
+///      (\"\")             ,1234567,Jane Doe-Smithson\\, Esq.\n\
+///      (Busan \\(south\\)),-42    ,\" padded \"\n\
+///      (Rome)           ,7      ,\"\"",
+/// );
+///
+/// for delimiter in [',', '|', '\t'] {
+///     let ort = generate_ort_with(&value, &GenerateOptions { delimiter, ..options.clone() }).unwrap();
+///     assert_eq!(parse_ort_with(&ort, &ParseOptions::new().delimiter(delimiter)).unwrap(), value);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerateOptions {
+    /// Separates values in rows, arrays and inline objects, and fields in headers
+    pub delimiter: char,
+    /// Write every string in double quotes instead of escaping its structural characters
+    pub quote_strings: bool,
+    /// Write a string in double quotes when escaping it would take more than this many backslashes
+    pub quote_threshold: usize,
+    /// Order of sections, table columns and inline object keys
+    pub key_order: KeyOrder,
+    /// How null values are written
+    pub null_output: NullOutput,
+    /// Round fractional numbers to at most this many decimal places
+    pub number_precision: Option<usize>,
+    /// How numbers are written
+    pub number_format: NumberFormat,
+    /// Line break written between headers and rows
+    pub line_ending: LineEnding,
+    /// Indent the rows under each header by two spaces
+    pub indent_nested: bool,
+    /// Declare the type of table columns whose values all share one, as in `id int`
+    pub typed_headers: bool,
+    /// Group the digits of integers longer than four digits with `_`, as in `1_000_000`
+    pub underscore_grouping: bool,
+    /// Blank lines written between sections
+    pub section_spacing: usize,
+    /// Whether the output ends with a line break
+    pub trailing_newline: TrailingNewline,
+    /// Pad table cells with spaces so the columns line up
+    pub align_columns: bool,
+    /// Comment character the output will be parsed with, as set by
+    /// `ParseOptions::comment_char`; strings starting with it are quoted
+    ///
+    /// ```
+    /// use ort_rs::{generate_ort_with, parse_ort_with, GenerateOptions, Map, OrtValue, ParseOptions};
+    ///
+    /// let row = |id: &str| OrtValue::Object(Map::from([("id".to_string(), OrtValue::from(id))]));
+    /// let value = OrtValue::Object(Map::from([("users".to_string(), OrtValue::Array(vec![row(";1"), row("#2")]))]));
+    ///
+    /// let ort = generate_ort_with(&value, &GenerateOptions { comment_char: ';', ..Default::default() }).unwrap();
+    /// assert_eq!(ort, "users:id:\n\";1\"\n\\#2");
+    /// assert_eq!(parse_ort_with(&ort, &ParseOptions::new().comment_char(';')).unwrap(), value);
+    /// ```
+    pub comment_char: char,
+    /// Fail if the rows of a table have different fields, instead of filling
+    /// the fields a row lacks with null
+    ///
+    /// ```
+    /// use ort_rs::{generate_ort_with, GenerateOptions, Map, OrtValue};
+    ///
+    /// let row = |pairs: &[(&str, i64)]| OrtValue::Object(pairs.iter().map(|(k, v)| (k.to_string(), OrtValue::from(*v))).collect());
+    /// let users = |rows| OrtValue::Object(Map::from([("users".to_string(), OrtValue::Array(rows))]));
+    /// let strict = GenerateOptions { strict: true, delimiter: '|', ..Default::default() };
+    ///
+    /// let ort = generate_ort_with(&users(vec![row(&[("id", 1)]), row(&[("id", 2)])]), &strict).unwrap();
+    /// assert_eq!(ort, "users:id:\n1\n2");
+    ///
+    /// let ragged = users(vec![row(&[("id", 1), ("age", 30)]), row(&[("id", 2)])]);
+    /// let error = generate_ort_with(&ragged, &strict).unwrap_err();
+    /// assert_eq!(error.message, "Row 2 of 'users' is missing field 'age'");
+    /// assert!(generate_ort_with(&ragged, &GenerateOptions::default()).is_ok());
+    /// ```
+    pub strict: bool,
+}
+
+impl Default for GenerateOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            quote_strings: false,
+            quote_threshold: 2,
+            key_order: KeyOrder::default(),
+            null_output: NullOutput::default(),
+            number_precision: None,
+            number_format: NumberFormat::default(),
+            line_ending: LineEnding::default(),
+            indent_nested: false,
+            typed_headers: false,
+            underscore_grouping: false,
+            section_spacing: 1,
+            trailing_newline: TrailingNewline::default(),
+            align_columns: false,
+            comment_char: '#',
+            strict: false,
+        }
+    }
+}
+
+/// Order in which the generator writes the keys of an object
+///
+/// The default is `Alphabetical`, which `generate_ort` always uses.
+/// `Insertion` writes sections and columns in the order they were parsed, so
+/// a document regenerates with its layout intact.
+///
+/// # Example
+/// ```
+/// use ort_rs::{generate_ort, generate_ort_with, parse_ort, GenerateOptions, KeyOrder};
+///
+/// let ort = "users:name,id:\nJohn,1\nJane,2\n\nconfig:\n(port:80,host:local)\n";
+/// let value = parse_ort(ort).unwrap();
+///
+/// let options = GenerateOptions { key_order: KeyOrder::Insertion, ..Default::default() };
+/// assert_eq!(generate_ort_with(&value, &options).unwrap(), ort);
+/// assert_eq!(generate_ort(&value), "config:\n(host:local,port:80)\n\nusers:id,name:\n1,John\n2,Jane\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum KeyOrder {
+    /// The order the keys were inserted or parsed in
+    Insertion,
+    /// Sorted by key
+    #[default]
+    Alphabetical,
+    /// The listed keys first, in the given order, then the rest sorted by key
+    Custom(Vec<String>),
+}
+
+impl KeyOrder {
+    /// Sort items by their key, keeping the incoming order for `Insertion`
+    fn sort<T>(&self, items: &mut [T], key: impl Fn(&T) -> &str) {
+        match self {
+            KeyOrder::Insertion => {}
+            KeyOrder::Alphabetical => items.sort_by(|a, b| key(a).cmp(key(b))),
+            KeyOrder::Custom(order) => {
+                let rank = |k: &str| order.iter().position(|o| o == k).unwrap_or(usize::MAX);
+                items.sort_by(|a, b| rank(key(a)).cmp(&rank(key(b))).then_with(|| key(a).cmp(key(b))));
+            }
+        }
+    }
+}
+
+/// How the generator writes null values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NullOutput {
+    /// An empty cell, which reads back as null
+    #[default]
+    EmptyString,
+    /// The bare word `null`, for consumers that expect it
+    ///
+    /// ORT itself reads `null` back as a string, so strings spelled `null`
+    /// are quoted to tell them apart.
+    Keyword,
+    /// Leave out object keys whose value is null, and table columns that
+    /// are null in every row
+    ///
+    /// Array items, and cells of columns that other rows fill, keep their
+    /// position and are written empty.
+    ///
+    /// ```
+    /// use ort_rs::{generate_ort_with, GenerateOptions, Map, NullOutput, OrtValue};
+    ///
+    /// let row = |id: i64, nickname: Option<&str>| OrtValue::Object(Map::from([
+    ///     ("id".to_string(), OrtValue::from(id)),
+    ///     ("nickname".to_string(), nickname.map_or(OrtValue::Null, OrtValue::from)),
+    ///     ("deleted_at".to_string(), OrtValue::Null),
+    /// ]));
+    /// let users = OrtValue::Object(Map::from([
+    ///     ("users".to_string(), OrtValue::Array(vec![row(1, Some("Jo")), row(2, None)])),
+    /// ]));
+    ///
+    /// let options = GenerateOptions { null_output: NullOutput::Skip, ..Default::default() };
+    /// assert_eq!(generate_ort_with(&users, &options).unwrap(), "users:id,nickname:\n1,Jo\n2,");
+    /// ```
+    Skip,
+}
+
+/// How the generator writes numbers
+///
+/// Numbers are never written in scientific notation: `1.5e20` becomes
+/// `150000000000000000000`. `number_precision` rounds fractions in every
+/// format but `Fixed`.
+///
+/// # Example
+/// ```
+/// use ort_rs::{generate_ort_with, GenerateOptions, Map, NumberFormat, OrtValue};
+///
+/// let numbers = OrtValue::Object(Map::from([(
+///     "n".to_string(),
+///     OrtValue::Array(vec![1.5e20.into(), 2.0.into(), 0.125.into(), (-0.0).into(), 1e-7.into()]),
+/// )]));
+/// let generate = |number_format| generate_ort_with(&numbers, &GenerateOptions { number_format, ..Default::default() }).unwrap();
+///
+/// assert_eq!(generate(NumberFormat::Default), "n:\n[150000000000000000000,2,0.125,-0,0.0000001]");
+/// assert_eq!(generate(NumberFormat::NoExponent), "n:\n[150000000000000000000,2,0.125,-0,0.0000001]");
+/// assert_eq!(generate(NumberFormat::SmartInt), "n:\n[150000000000000000000,2,0.125,0,0.0000001]");
+/// assert_eq!(generate(NumberFormat::Fixed(2)), "n:\n[150000000000000000000.00,2.00,0.12,-0.00,0.00]");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// The shortest decimal form that reads back as the same number
+    #[default]
+    Default,
+    /// Always exactly this many decimal places
+    Fixed(usize),
+    /// Full decimal form without an exponent
+    ///
+    /// This is what `Default` writes too; choose it to state the requirement.
+    NoExponent,
+    /// Whole numbers as integers, with `-0` written as `0`
+    SmartInt,
+}
+
+/// Whether generated output ends with a line break
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrailingNewline {
+    /// Only after a document of several sections, as `generate_ort` writes it
+    #[default]
+    Auto,
+    /// After any non-empty output
+    Always,
+    /// Never
+    Never,
+}
+
+impl TrailingNewline {
+    /// Check if output of this many sections ends with a line break
+    fn applies(self, sections: usize) -> bool {
+        match self {
+            TrailingNewline::Auto => sections > 1,
+            TrailingNewline::Always => sections > 0,
+            TrailingNewline::Never => false,
+        }
+    }
+}
+
+/// Line break written by the generator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Generate ORT from a value with the given options
+///
+/// This fails if `escape::is_valid_delimiter` rejects the delimiter, or with
+/// `strict` if the rows of a table have different fields.
+///
+/// # Example
+/// ```
+/// use ort_rs::{generate_ort, generate_ort_with, GenerateOptions, KeyOrder, NullOutput, NumberFormat, OrtValue};
+/// use ort_rs::{Map, TrailingNewline};
+///
+/// let value = OrtValue::Object(Map::from([
+///     ("b".to_string(), OrtValue::from(2.0 / 3.0)),
+///     ("a".to_string(), OrtValue::Null),
+/// ]));
+/// let generate = |options: GenerateOptions| generate_ort_with(&value, &options).unwrap();
+///
+/// assert_eq!(generate(GenerateOptions::default()), generate_ort(&value));
+/// assert_eq!(generate_ort(&value), "a:\n\n\nb:\n0.6666666666666666\n");
+///
+/// let order = KeyOrder::Custom(vec!["b".to_string()]);
+/// assert_eq!(generate(GenerateOptions { key_order: order, ..Default::default() }), "b:\n0.6666666666666666\n\na:\n\n");
+/// assert_eq!(generate(GenerateOptions { section_spacing: 0, ..Default::default() }), "a:\n\nb:\n0.6666666666666666\n");
+/// assert_eq!(generate(GenerateOptions { section_spacing: 2, ..Default::default() }), "a:\n\n\n\nb:\n0.6666666666666666\n");
+/// assert_eq!(generate(GenerateOptions { null_output: NullOutput::Keyword, ..Default::default() }), "a:\nnull\n\nb:\n0.6666666666666666\n");
+/// assert_eq!(generate(GenerateOptions { number_precision: Some(3), ..Default::default() }), "a:\n\n\nb:\n0.667\n");
+/// assert_eq!(generate(GenerateOptions { number_format: NumberFormat::Fixed(1), ..Default::default() }), "a:\n\n\nb:\n0.7\n");
+/// assert_eq!(generate(GenerateOptions { trailing_newline: TrailingNewline::Never, ..Default::default() }), "a:\n\n\nb:\n0.6666666666666666");
+///
+/// // A single section has no trailing line break unless it is asked for
+/// let single = OrtValue::Object(Map::from([("a".to_string(), OrtValue::from(1))]));
+/// assert_eq!(generate_ort(&single), "a:\n1");
+/// let options = GenerateOptions { trailing_newline: TrailingNewline::Always, ..Default::default() };
+/// assert_eq!(generate_ort_with(&single, &options).unwrap(), "a:\n1\n");
+/// ```
+pub fn generate_ort_with(value: &OrtValue, options: &GenerateOptions) -> OrtResult<String> {
+    check_options(value, options)?;
+    let mut result = String::new();
+    write_ort(&mut result, value, options).expect("writing to a String cannot fail");
+    Ok(result)
+}
+
+/// Check the delimiter and, with `strict`, that the rows of every table have the same fields
+pub(crate) fn check_options(value: &OrtValue, options: &GenerateOptions) -> OrtResult<()> {
+    check_delimiter(options.delimiter)?;
+    if !options.strict {
+        return Ok(());
+    }
     match value {
         OrtValue::Object(obj) => {
-            // Check if this is a multi-key object
-            if obj.len() > 1 || obj.is_empty() {
-                generate_multi_object(obj)
-            } else if obj.len() == 1 {
-                // Single key - might be a named array
-                let (key, val) = obj.iter().next().unwrap();
+            for (key, val) in obj {
                 if let OrtValue::Array(arr) = val {
-                    if arr.is_empty() {
-                        format!("{}:\n[]", key)
-                    } else if is_uniform_object_array(arr) {
-                        generate_object_array(key, arr)
-                    } else {
-                        generate_simple_array(key, arr)
+                    if is_object_array(arr, options) {
+                        check_table(key, arr, options)?;
                     }
-                } else {
-                    // Single key with non-array value
-                    format!("{}:\n{}", key, generate_value(val, false))
                 }
+            }
+        }
+        OrtValue::Array(arr) if is_object_array(arr, options) => check_table("", arr, options)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Write ORT output section by section and row by row
+pub(crate) fn write_ort<W: Write>(out: &mut W, value: &OrtValue, options: &GenerateOptions) -> fmt::Result {
+    let sections = match value {
+        OrtValue::Object(obj) => {
+            let entries = entries(obj, options);
+            // Check if this is a multi-key object
+            if entries.len() > 1 || entries.is_empty() {
+                write_multi_object(out, &entries, options)?;
             } else {
-                String::new()
+                // Single key - might be a named array
+                let (key, val) = entries[0];
+                write_section(out, key, val, options)?;
             }
+            entries.len()
         }
         OrtValue::Array(arr) => {
-            // Top-level array
-            if is_uniform_object_array(arr) {
-                generate_top_level_object_array(arr)
+            // Top-level array; a table of one row would read back as that row
+            if arr.len() > 1 && is_object_array(arr, options) {
+                write_object_array(out, "", arr, options)?;
             } else {
-                format!(":{}", generate_array_content(arr, false))
+                write!(out, ":{}{}", options.line_ending.as_str(), generate_array_content(arr, false, options))?;
             }
+            1
         }
-        _ => generate_value(value, false),
+        _ => {
+            write!(out, ":{}{}", options.line_ending.as_str(), generate_value(value, options))?;
+            1
+        }
+    };
+
+    if options.trailing_newline.applies(sections) {
+        out.write_str(options.line_ending.as_str())?;
     }
+    Ok(())
 }
 
-fn generate_multi_object(obj: &HashMap<String, OrtValue>) -> String {
-    let mut result = Vec::new();
-    let mut entries: Vec<_> = obj.iter().collect();
-    entries.sort_by(|a, b| a.0.cmp(b.0));
+/// The entries of an object in output order, without nulls if they are skipped
+fn entries<'a>(obj: &'a Map<String, OrtValue>, options: &GenerateOptions) -> Vec<(&'a String, &'a OrtValue)> {
+    let mut entries: Vec<_> = obj
+        .iter()
+        .filter(|(_, v)| !(options.null_output == NullOutput::Skip && v.is_null()))
+        .collect();
+    options.key_order.sort(&mut entries, |(k, _)| k);
+    entries
+}
+
+fn write_multi_object<W: Write>(out: &mut W, entries: &[(&String, &OrtValue)], options: &GenerateOptions) -> fmt::Result {
+    let newline = options.line_ending.as_str();
 
     for (i, (key, val)) in entries.iter().enumerate() {
-        if let OrtValue::Array(arr) = val {
-            if is_uniform_object_array(arr) {
-                result.push(generate_object_array(key, arr).trim_end().to_string());
-            } else {
-                result.push(generate_simple_array(key, arr).trim_end().to_string());
+        if i > 0 {
+            out.write_str(newline)?;
+            for _ in 0..options.section_spacing {
+                out.write_str(newline)?;
             }
-        } else {
-            result.push(format!("{}:\n{}", key, generate_value(val, false)));
-        }
-
-        if i < entries.len() - 1 {
-            result.push("\n\n".to_string());
-        } else {
-            result.push("\n".to_string());
         }
+        write_section(out, key, val, options)?;
     }
 
-    result.join("")
+    Ok(())
 }
 
-fn get_value_type(val: &OrtValue) -> &'static str {
+fn write_section<W: Write>(out: &mut W, key: &str, val: &OrtValue, options: &GenerateOptions) -> fmt::Result {
     match val {
-        OrtValue::Null => "null",
-        OrtValue::Bool(_) => "bool",
-        OrtValue::Number(_) => "number",
-        OrtValue::String(_) => "string",
-        OrtValue::Array(_) => "array",
-        OrtValue::Object(_) => "object",
+        OrtValue::Array(arr) if is_object_array(arr, options) => write_object_array(out, key, arr, options),
+        OrtValue::Array(arr) => {
+            write!(out, "{}:", escape(key, options.delimiter))?;
+            start_row(out, options)?;
+            out.write_str(&generate_array_content(arr, false, options))
+        }
+        _ => {
+            write!(out, "{}:", escape(key, options.delimiter))?;
+            start_row(out, options)?;
+            out.write_str(&generate_value(val, options))
+        }
     }
 }
 
-fn is_uniform_object_array(arr: &[OrtValue]) -> bool {
-    if arr.is_empty() {
+/// Break the line before a row, indenting it if asked to
+fn start_row<W: Write>(out: &mut W, options: &GenerateOptions) -> fmt::Result {
+    out.write_str(options.line_ending.as_str())?;
+    if options.indent_nested {
+        out.write_str("  ")?;
+    }
+    Ok(())
+}
+
+/// Check if an array is written as a table, which needs a field to write and
+/// rows that aren't blank lines
+fn is_object_array(arr: &[OrtValue], options: &GenerateOptions) -> bool {
+    if !arr.iter().all(OrtValue::is_object) || rows(arr).all(|row| row.is_empty()) {
         return false;
     }
+    if rows(arr).any(|row| row.len() > 1) {
+        return true;
+    }
+    // With one column, a row whose cell is empty would be a blank line
+    match &table_fields(rows(arr), options)[..] {
+        [] => false,
+        [field] => rows(arr).all(|row| !generate_field(field, row.get(field.name()).unwrap_or(&OrtValue::Null), options).is_empty()),
+        _ => true,
+    }
+}
 
-    // Check if all elements are objects with the same keys
-    let first_obj = match &arr[0] {
-        OrtValue::Object(obj) => obj,
-        _ => return false,
-    };
+fn rows(arr: &[OrtValue]) -> impl Iterator<Item = &Map<String, OrtValue>> {
+    arr.iter().filter_map(OrtValue::as_object)
+}
 
-    let mut first_keys: Vec<_> = first_obj.keys().collect();
-    first_keys.sort();
+/// Collect the header of a table: every key found in any row, with nested
+/// fields for columns holding objects
+///
+/// Keys come in order of first appearance before `key_order` is applied.
+/// With `NullOutput::Skip`, keys that are null in every row are left out, and
+/// with `typed_headers`, columns whose values share a type declare it.
+pub(crate) fn table_fields<'a>(rows: impl Iterator<Item = &'a Map<String, OrtValue>>, options: &GenerateOptions) -> Vec<FieldSpec> {
+    let mut names: Vec<&String> = vec![];
+    let mut columns: HashMap<&String, Vec<&OrtValue>> = HashMap::new();
 
-    for item in arr.iter().skip(1) {
-        match item {
-            OrtValue::Object(obj) => {
-                let mut keys: Vec<_> = obj.keys().collect();
-                keys.sort();
-                if keys != first_keys {
-                    return false;
-                }
+    for row in rows {
+        for (key, value) in row {
+            if options.null_output == NullOutput::Skip && value.is_null() {
+                continue;
+            }
+            if !columns.contains_key(key) {
+                names.push(key);
+            }
+            columns.entry(key).or_default().push(value);
+        }
+    }
 
-                // Check if value types match for each key
-                for key in &first_keys {
-                    let first_type = first_obj.get(*key).map(get_value_type).unwrap_or("null");
-                    let current_type = obj.get(*key).map(get_value_type).unwrap_or("null");
-                    if first_type != current_type {
-                        return false;
-                    }
-                }
+    options.key_order.sort(&mut names, |name| name);
+    names
+        .into_iter()
+        .map(|name| {
+            let values = &columns[name];
+            if let Some(fields) = nested_object(values, options) {
+                return FieldSpec::Nested(name.clone(), fields);
+            }
+            if let Some(fields) = nested_table(values, options) {
+                return FieldSpec::Nested(name.clone(), fields);
             }
-            _ => return false,
+            match column_type(values.iter().copied(), options).filter(|_| options.typed_headers) {
+                Some(ty) => FieldSpec::Typed(name.clone(), ty),
+                None => FieldSpec::Simple(name.clone()),
+            }
+        })
+        .collect()
+}
+
+/// Collect the nested fields of a column holding objects that all have the
+/// same keys, whose cells are then written by position as `(1,John)`
+fn nested_object(values: &[&OrtValue], options: &GenerateOptions) -> Option<Vec<FieldSpec>> {
+    let mut objects = vec![];
+    for value in values {
+        match value {
+            OrtValue::Null => {}
+            OrtValue::Object(obj) => objects.push(obj),
+            _ => return None,
         }
     }
 
-    true
+    let fields = table_fields(objects.iter().copied(), options);
+    let uniform = !fields.is_empty() && objects.iter().all(|obj| obj.is_empty() || positional(&fields, obj, options));
+    uniform.then_some(fields)
 }
 
-fn generate_object_array(key: &str, arr: &[OrtValue]) -> String {
-    if arr.is_empty() {
-        return format!("{}:\n[]", key);
+/// Collect the nested fields of a column holding arrays of objects that all
+/// have the same keys, whose cells are then written as `[(1,9.99),(2,4.50)]`
+fn nested_table(values: &[&OrtValue], options: &GenerateOptions) -> Option<Vec<FieldSpec>> {
+    let mut objects = vec![];
+    for value in values {
+        match value {
+            OrtValue::Null => {}
+            OrtValue::Array(arr) if arr.iter().all(OrtValue::is_object) => objects.extend(rows(arr)),
+            _ => return None,
+        }
+    }
+    if objects.is_empty() {
+        return None;
     }
 
-    let first = &arr[0];
-    if let OrtValue::Object(obj) = first {
-        let keys: Vec<_> = obj.keys().cloned().collect();
-        let header = generate_header(&keys, obj);
+    let fields = table_fields(objects.iter().copied(), options);
+    let uniform = !fields.is_empty() && objects.iter().all(|obj| positional(&fields, obj, options));
+    uniform.then_some(fields)
+}
 
-        let mut result = vec![format!("{}:{}", key, header)];
+/// Check if an object can be written by position under nested fields, where
+/// `()` would read back as an empty object
+fn positional(fields: &[FieldSpec], obj: &Map<String, OrtValue>, options: &GenerateOptions) -> bool {
+    let empty = match fields {
+        [field] => generate_field(field, obj.get(field.name()).unwrap_or(&OrtValue::Null), options).is_empty(),
+        _ => false,
+    };
+    fits(fields, obj, options) && !empty
+}
 
-        for item in arr {
-            if let OrtValue::Object(obj) = item {
-                let values: Vec<String> = keys
-                    .iter()
-                    .map(|k| generate_object_field_value(obj.get(k).unwrap_or(&OrtValue::Null), &keys, k, obj))
-                    .collect();
-                result.push(values.join(","));
-            }
-        }
+/// Check if an object has exactly the given fields, including those of its
+/// nested objects, so its values can be written by position
+fn fits(fields: &[FieldSpec], obj: &Map<String, OrtValue>, options: &GenerateOptions) -> bool {
+    let known = obj
+        .iter()
+        .all(|(key, value)| (options.null_output == NullOutput::Skip && value.is_null()) || fields.iter().any(|f| f.name() == key));
+    known
+        && fields.iter().all(|field| match (field, obj.get(field.name())) {
+            (_, None) => false,
+            (FieldSpec::Nested(_, nested), Some(OrtValue::Object(inner))) if !inner.is_empty() => fits(nested, inner, options),
+            _ => true,
+        })
+}
 
-        result.join("\n")
-    } else {
-        format!("{}:\n{}", key, generate_array_content(arr, false))
+/// Find the type shared by the non-null values of a column
+///
+/// Whole numbers are `int` unless a fixed number format gives them a fraction,
+/// and `int` widens to `float` when both appear. Empty arrays fit any array type.
+fn column_type<'a>(values: impl Iterator<Item = &'a OrtValue>, options: &GenerateOptions) -> Option<FieldType> {
+    shared_type(values, options).ok().flatten()
+}
+
+/// Fails if the values have different types, and gives `None` if no value
+/// tells its type
+fn shared_type<'a>(values: impl Iterator<Item = &'a OrtValue>, options: &GenerateOptions) -> Result<Option<FieldType>, ()> {
+    let mut shared = None;
+    let mut empty_array = false;
+
+    for value in values {
+        let ty = match value {
+            OrtValue::Null => continue,
+            OrtValue::Bool(_) => FieldType::Bool,
+            OrtValue::Number(n) if n.fract() == 0.0 && !matches!(options.number_format, NumberFormat::Fixed(_)) => FieldType::Int,
+            OrtValue::Number(_) => FieldType::Float,
+            OrtValue::String(_) => FieldType::Str,
+            OrtValue::Array(items) => match shared_type(items.iter(), options)? {
+                Some(item) => FieldType::Array(Box::new(item)),
+                None => {
+                    empty_array = true;
+                    continue;
+                }
+            },
+            OrtValue::Object(_) => return Err(()),
+        };
+        shared = Some(match shared {
+            None => ty,
+            Some(shared) => merge_types(shared, ty).ok_or(())?,
+        });
+    }
+
+    match shared {
+        Some(ty) if empty_array && !matches!(ty, FieldType::Array(_)) => Err(()),
+        shared => Ok(shared),
     }
 }
 
-fn generate_top_level_object_array(arr: &[OrtValue]) -> String {
-    if arr.is_empty() {
-        return ":[]".to_string();
+fn merge_types(a: FieldType, b: FieldType) -> Option<FieldType> {
+    match (a, b) {
+        (a, b) if a == b => Some(a),
+        (FieldType::Int, FieldType::Float) | (FieldType::Float, FieldType::Int) => Some(FieldType::Float),
+        (FieldType::Array(a), FieldType::Array(b)) => merge_types(*a, *b).map(|item| FieldType::Array(Box::new(item))),
+        _ => None,
     }
+}
 
-    let first = &arr[0];
-    if let OrtValue::Object(obj) = first {
-        let keys: Vec<_> = obj.keys().cloned().collect();
-        let header = generate_header(&keys, obj);
+/// Write an array of objects as a table (`key` is empty for top-level tables)
+///
+/// Fields missing from a row are written as null.
+fn write_object_array<W: Write>(out: &mut W, key: &str, arr: &[OrtValue], options: &GenerateOptions) -> fmt::Result {
+    let fields = table_fields(rows(arr), options);
+    write!(out, "{}:{}:", escape(key, options.delimiter), format_fields(&fields, options.delimiter))?;
 
-        let mut result = vec![format!(":{}", header)];
+    let cells = |row: &Map<String, OrtValue>| -> Vec<String> {
+        fields
+            .iter()
+            .map(|field| generate_field(field, row.get(field.name()).unwrap_or(&OrtValue::Null), options))
+            .collect()
+    };
 
-        for item in arr {
-            if let OrtValue::Object(obj) = item {
-                let values: Vec<String> = keys
-                    .iter()
-                    .map(|k| generate_object_field_value(obj.get(k).unwrap_or(&OrtValue::Null), &keys, k, obj))
-                    .collect();
-                result.push(values.join(","));
-            }
+    if !options.align_columns {
+        for row in rows(arr) {
+            write_row(out, &cells(row), &[], options)?;
         }
+        return Ok(());
+    }
 
-        result.join("\n")
-    } else {
-        format!(":{}", generate_array_content(arr, false))
+    // Measure every cell before writing the first row
+    let lines: Vec<Vec<String>> = rows(arr).map(cells).collect();
+    let mut widths = vec![0; fields.len()];
+    for line in &lines {
+        for (width, cell) in widths.iter_mut().zip(line) {
+            *width = (*width).max(display_width(cell));
+        }
     }
+    for line in &lines {
+        write_row(out, line, &widths, options)?;
+    }
+
+    Ok(())
 }
 
-fn generate_header(keys: &[String], first_obj: &HashMap<String, OrtValue>) -> String {
-    keys.iter()
-        .map(|k| {
-            if let Some(value) = first_obj.get(k) {
-                match value {
-                    OrtValue::Object(nested_obj) => {
-                        // Generate nested field
-                        let nested_keys: Vec<_> = nested_obj.keys().cloned().collect();
-                        let nested_header = generate_header_fields(&nested_keys, nested_obj);
-                        format!("{}({})", k, nested_header)
-                    }
-                    _ => k.clone(),
-                }
-            } else {
-                k.clone()
-            }
-        })
-        .collect::<Vec<_>>()
-        .join(",") + ":"
-}
-
-fn generate_header_fields(keys: &[String], obj: &HashMap<String, OrtValue>) -> String {
-    keys.iter()
-        .map(|k| {
-            if let Some(value) = obj.get(k) {
-                match value {
-                    OrtValue::Object(nested_obj) => {
-                        // Recursively generate nested field
-                        let nested_keys: Vec<_> = nested_obj.keys().cloned().collect();
-                        let nested_header = generate_header_fields(&nested_keys, nested_obj);
-                        format!("{}({})", k, nested_header)
-                    }
-                    _ => k.clone(),
-                }
-            } else {
-                k.clone()
-            }
+/// Count the terminal columns a cell takes up, where combining marks take
+/// none and East Asian wide characters such as Hangul and CJK take two
+fn display_width(cell: &str) -> usize {
+    cell.chars()
+        .map(|ch| match ch as u32 {
+            0x0300..=0x036F | 0x200B..=0x200F | 0xFE00..=0xFE0F => 0,
+            0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F900..=0x1F9FF
+            | 0x20000..=0x3FFFD => 2,
+            _ => 1,
         })
-        .collect::<Vec<_>>()
-        .join(",")
+        .sum()
 }
 
-fn generate_object_field_value(
-    value: &OrtValue,
-    _keys: &[String],
-    _current_key: &str,
-    _parent: &HashMap<String, OrtValue>,
-) -> String {
-    match value {
-        OrtValue::Null => String::new(),
-        OrtValue::Object(obj) => {
-            if obj.is_empty() {
-                "()".to_string()
-            } else {
-                // Nested object - output values only (keys are in header)
-                let nested_keys: Vec<_> = obj.keys().cloned().collect();
-                let values: Vec<String> = nested_keys
-                    .iter()
-                    .map(|k| {
-                        generate_object_field_value(
-                            obj.get(k).unwrap_or(&OrtValue::Null),
-                            &nested_keys,
-                            k,
-                            obj,
-                        )
-                    })
-                    .collect();
-                format!("({})", values.join(","))
-            }
+/// Write the cells of a row, padding each but the last with spaces to its
+/// column's width
+fn write_row<W: Write>(out: &mut W, cells: &[String], widths: &[usize], options: &GenerateOptions) -> fmt::Result {
+    start_row(out, options)?;
+    for (i, cell) in cells.iter().enumerate() {
+        if i > 0 {
+            out.write_char(options.delimiter)?;
         }
-        OrtValue::Array(arr) => {
-            if arr.is_empty() {
-                "[]".to_string()
-            } else {
-                format!("[{}]", generate_array_content(arr, true))
+        out.write_str(cell)?;
+        if let Some(&width) = widths.get(i).filter(|_| i + 1 < cells.len()) {
+            for _ in display_width(cell)..width {
+                out.write_char(' ')?;
             }
         }
-        _ => generate_value(value, true),
     }
+    Ok(())
 }
 
-fn generate_simple_array(key: &str, arr: &[OrtValue]) -> String {
-    format!("{}:\n{}", key, generate_array_content(arr, false))
+/// Generate a table cell, where objects under nested fields only carry their values
+pub(crate) fn generate_field(field: &FieldSpec, value: &OrtValue, options: &GenerateOptions) -> String {
+    match (field, value) {
+        (FieldSpec::Nested(_, nested), OrtValue::Object(obj)) if !obj.is_empty() => {
+            let values: Vec<String> = nested
+                .iter()
+                .map(|f| generate_field(f, obj.get(f.name()).unwrap_or(&OrtValue::Null), options))
+                .collect();
+            format!("({})", values.join(&options.delimiter.to_string()))
+        }
+        (FieldSpec::Nested(_, nested), OrtValue::Array(items))
+            if !items.is_empty() && items.iter().all(|item| item.as_object().is_some_and(|obj| fits(nested, obj, options))) =>
+        {
+            let values: Vec<String> = items.iter().map(|item| generate_field(field, item, options)).collect();
+            format!("[{}]", values.join(&options.delimiter.to_string()))
+        }
+        _ => generate_value(value, options),
+    }
+}
+
+/// Find the first field missing from a row, as a dotted path
+fn missing_field(fields: &[FieldSpec], row: &Map<String, OrtValue>) -> Option<String> {
+    fields.iter().find_map(|field| match (field, row.get(field.name())) {
+        (_, None) => Some(field.name().to_string()),
+        (FieldSpec::Nested(name, nested), Some(OrtValue::Object(obj))) => {
+            missing_field(nested, obj).map(|path| format!("{}.{}", name, path))
+        }
+        _ => None,
+    })
 }
 
-fn generate_array_content(arr: &[OrtValue], inline: bool) -> String {
+/// Check that every row of a table has the same fields
+fn check_table(key: &str, arr: &[OrtValue], options: &GenerateOptions) -> OrtResult<()> {
+    let fields = table_fields(rows(arr), options);
+    for (i, row) in rows(arr).enumerate() {
+        if let Some(path) = missing_field(&fields, row) {
+            let table = if key.is_empty() { "the top-level table".to_string() } else { format!("'{}'", key) };
+            return Err(OrtError::new(
+                0,
+                String::new(),
+                format!("Row {} of {} is missing field '{}'", i + 1, table, path),
+            ).with_kind(OrtErrorKind::ValueCountMismatch));
+        }
+    }
+    Ok(())
+}
+
+fn generate_array_content(arr: &[OrtValue], inline: bool, options: &GenerateOptions) -> String {
     if arr.is_empty() {
         return "[]".to_string();
     }
 
-    let values: Vec<String> = arr.iter().map(|v| generate_value(v, inline)).collect();
+    let values: Vec<String> = arr.iter().map(|v| generate_value(v, options)).collect();
+    let mut content = values.join(&options.delimiter.to_string());
+    // A trailing empty item is dropped when parsed, so a null at the end needs another
+    if values.last().is_some_and(String::is_empty) {
+        content.push(options.delimiter);
+    }
 
     if inline {
-        values.join(",")
+        content
     } else {
-        format!("[{}]", values.join(","))
+        format!("[{}]", content)
     }
 }
 
-fn generate_value(value: &OrtValue, _inline: bool) -> String {
+pub(crate) fn generate_value(value: &OrtValue, options: &GenerateOptions) -> String {
     match value {
+        OrtValue::Null if options.null_output == NullOutput::Keyword => "null".to_string(),
         OrtValue::Null => String::new(),
         OrtValue::Bool(b) => b.to_string(),
-        OrtValue::Number(n) => n.to_string(),
-        OrtValue::String(s) => escape(s),
+        OrtValue::Number(n) => generate_number(*n, options),
+        OrtValue::String(s) => generate_string(s, options),
         OrtValue::Array(arr) => {
             if arr.is_empty() {
                 "[]".to_string()
             } else {
-                format!("[{}]", generate_array_content(arr, true))
+                format!("[{}]", generate_array_content(arr, true, options))
             }
         }
         OrtValue::Object(obj) => {
             if obj.is_empty() {
                 "()".to_string()
             } else {
-                generate_inline_object(obj)
+                generate_inline_object(obj, options)
             }
         }
     }
 }
 
-fn generate_inline_object(obj: &HashMap<String, OrtValue>) -> String {
-    let pairs: Vec<String> = obj
-        .iter()
-        .map(|(k, v)| format!("{}:{}", k, generate_value(v, true)))
+fn generate_inline_object(obj: &Map<String, OrtValue>, options: &GenerateOptions) -> String {
+    let pairs: Vec<String> = entries(obj, options)
+        .into_iter()
+        .map(|(k, v)| format!("{}:{}", escape(k, options.delimiter), generate_value(v, options)))
         .collect();
-    format!("({})", pairs.join(","))
+    format!("({})", pairs.join(&options.delimiter.to_string()))
 }
 
-fn escape(s: &str) -> String {
-    let mut result = String::new();
+/// Write a number in `number_format`, rounding its fraction to
+/// `number_precision` digits if set
+fn generate_number(n: f64, options: &GenerateOptions) -> String {
+    let text = format_number(n, options);
+    if options.underscore_grouping {
+        group_digits(&text).unwrap_or(text)
+    } else {
+        text
+    }
+}
 
-    for ch in s.chars() {
-        match ch {
-            '(' => result.push_str("\\("),
-            ')' => result.push_str("\\)"),
-            '[' => result.push_str("\\["),
-            ']' => result.push_str("\\]"),
-            ',' => result.push_str("\\,"),
-            '\\' => result.push_str("\\\\"),
-            '\n' => result.push_str("\\n"),
-            '\t' => result.push_str("\\t"),
-            '\r' => result.push_str("\\r"),
-            _ => result.push(ch),
+fn format_number(n: f64, options: &GenerateOptions) -> String {
+    if let Some(token) = non_finite_token(n) {
+        return token.to_string();
+    }
+    match options.number_format {
+        NumberFormat::Fixed(places) if n.is_finite() => return format!("{:.*}", places, n),
+        NumberFormat::SmartInt if n == 0.0 => return "0".to_string(),
+        _ => {}
+    }
+    match options.number_precision {
+        Some(precision) if n.is_finite() && n.fract() != 0.0 => {
+            let rounded = format!("{:.*}", precision, n);
+            if rounded.contains('.') {
+                rounded.trim_end_matches('0').trim_end_matches('.').to_string()
+            } else {
+                rounded
+            }
         }
+        _ => n.to_string(),
+    }
+}
+
+/// Group an integer's digits in threes with `_`, if it has more than four
+fn group_digits(text: &str) -> Option<String> {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    if digits.len() <= 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
 
-    result
+    let mut grouped = String::from(&text[..text.len() - digits.len()]);
+    for (i, ch) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push('_');
+        }
+        grouped.push(ch);
+    }
+    Some(grouped)
+}
+
+/// Get the token for NaN or an infinity
+pub(crate) fn non_finite_token(n: f64) -> Option<&'static str> {
+    if n.is_nan() {
+        Some("nan")
+    } else if n.is_infinite() {
+        Some(if n > 0.0 { "inf" } else { "-inf" })
+    } else {
+        None
+    }
+}
+
+/// Escape a string, quoting it if it would otherwise parse back as another
+/// value or if quotes read better than its escapes
+fn generate_string(s: &str, options: &GenerateOptions) -> String {
+    let keyword = options.null_output == NullOutput::Keyword && s == "null";
+    let quote_instead = options.quote_strings || count_escapes(s, options.delimiter) > options.quote_threshold;
+    // `escape` only escapes a leading `#`
+    let comment = options.comment_char != '#' && s.starts_with(options.comment_char);
+    if quote_instead || keyword || comment || is_ambiguous(s) {
+        quote(s)
+    } else {
+        escape(s, options.delimiter)
+    }
+}
+
+/// Check if a bare string would be read back as null, a number or boolean,
+/// lose surrounding whitespace, or start a quoted string
+fn is_ambiguous(s: &str) -> bool {
+    s.is_empty()
+        || !matches!(infer_scalar(Cow::Borrowed(s)), Scalar::String(_))
+        || s.trim() != s
+        || s.starts_with('"')
 }
@@ -0,0 +1,74 @@
+use crate::error::{OrtError, OrtErrorKind, OrtResult};
+use crate::ort_value::OrtValue;
+use crate::generator::GenerateOptions;
+use crate::value::{from_reader, to_writer_with};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Compression level used by `to_file` for `.gz` paths
+pub const DEFAULT_LEVEL: u32 = 6;
+
+/// Parse gzip-compressed ORT from any reader into an OrtValue
+///
+/// `from_file` already does this for paths ending in `.gz`; use this when the
+/// extension doesn't tell.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let dir = std::env::temp_dir().join(format!("ort-gzip-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+///
+/// let value = ort_rs::from_str("users:id,name:\n1,John\n2,Jane")?;
+///
+/// // Compressed by extension
+/// let gz_path = dir.join("users.ort.gz");
+/// ort_rs::to_file(&value, &gz_path)?;
+/// assert_eq!(&std::fs::read(&gz_path).unwrap()[..2], b"\x1f\x8b");
+/// assert_eq!(ort_rs::from_file(&gz_path)?, value);
+///
+/// // Explicit reader and writer, whatever the extension
+/// let raw_path = dir.join("users.bin");
+/// ort_rs::to_gzip_writer(&value, std::fs::File::create(&raw_path).unwrap(), 9)?;
+/// assert_eq!(ort_rs::from_gzip_reader(std::fs::File::open(&raw_path).unwrap())?, value);
+///
+/// // Plain text is untouched
+/// let plain_path = dir.join("users.ort");
+/// ort_rs::to_file(&value, &plain_path)?;
+/// assert_eq!(std::fs::read_to_string(&plain_path).unwrap(), ort_rs::to_string(&value));
+/// assert_eq!(ort_rs::from_file(&plain_path)?, value);
+///
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_gzip_reader<R: Read>(reader: R) -> OrtResult<OrtValue> {
+    from_reader(GzDecoder::new(reader))
+}
+
+/// Convert an OrtValue to ORT and write it gzip-compressed to any writer
+///
+/// `level` ranges from 0 (no compression) to 9 (best compression).
+pub fn to_gzip_writer<W: Write>(value: &OrtValue, writer: W, level: u32) -> OrtResult<()> {
+    to_gzip_writer_with(value, writer, level, &GenerateOptions::default())
+}
+
+/// Write gzip-compressed ORT generated with the given options
+pub(crate) fn to_gzip_writer_with<W: Write>(value: &OrtValue, writer: W, level: u32, options: &GenerateOptions) -> OrtResult<()> {
+    let mut encoder = GzEncoder::new(writer, Compression::new(level.min(9)));
+    to_writer_with(value, &mut encoder, options)?;
+    encoder.finish().map_err(|e| {
+        OrtError::new(0, String::new(), format!("Failed to write output: {}", e))
+            .with_kind(OrtErrorKind::Io)
+            .with_source(e)
+    })?;
+    Ok(())
+}
+
+/// Check if a path names a gzip file
+pub(crate) fn is_gzip_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("gz"))
+}
@@ -1,15 +1,84 @@
 pub mod error;
+pub mod escape;
+pub mod fields;
 pub mod parser;
+pub mod event_parser;
 pub mod generator;
 pub mod ort_value;
+pub mod ort_value_ref;
 pub mod value;
+pub mod document;
+pub mod diff;
+pub mod source;
+pub mod schema;
+pub mod query;
+pub mod reader;
+pub mod writer;
+pub mod visitor;
 
 // Optional serde compatibility
 #[cfg(feature = "serde_json")]
 pub mod serde_compat;
 
-pub use error::{OrtError, OrtResult};
-pub use parser::parse_ort;
-pub use generator::generate_ort;
-pub use ort_value::OrtValue;
-pub use value::{from_str, from_file, to_string, to_file};
+// Optional serde Serialize/Deserialize support
+#[cfg(feature = "serde")]
+pub mod serde_impl;
+
+// Optional MessagePack encoding
+#[cfg(feature = "msgpack")]
+pub mod msgpack_compat;
+
+// Optional CSV import/export
+#[cfg(feature = "csv")]
+pub mod csv_compat;
+
+// Optional gzip compression
+#[cfg(feature = "compression")]
+pub mod gzip_compat;
+
+// Optional miette diagnostics
+#[cfg(feature = "miette")]
+pub mod miette_compat;
+
+// Optional arbitrary::Arbitrary support for fuzzing
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary_compat;
+
+pub use error::{Diagnostic, OrtError, OrtErrorKind, OrtResult, OrtWarning, ParseErrors, Severity, WarningKind};
+pub use fields::{FieldSpec, FieldType};
+pub use parser::{parse_ort, parse_ort_all, parse_ort_borrowed, parse_ort_lenient, parse_ort_with, parse_ort_with_warnings, parse_section, outline, ParseOptions, RowArity, SectionInfo};
+pub use generator::{generate_ort, generate_ort_with, GenerateOptions, KeyOrder, LineEnding, NullOutput, NumberFormat, TrailingNewline};
+pub use ort_value::{FromOrtValue, Map, OrtType, OrtValue};
+pub use ort_value_ref::OrtValueRef;
+pub use value::{from_str, from_file, from_file_with, from_reader, from_slice, to_string, to_string_with, to_file, to_file_with, to_file_atomic, to_writer, to_writer_with, append_rows, section_from_file, section_from_file_with, AtomicWriteOptions};
+pub use value::{from_dir, from_dir_with, ConflictPolicy, DirOptions};
+pub use document::OrtDocument;
+pub use diff::{Change, RowMatch};
+pub use query::query;
+pub use schema::{validate_against_schema, Schema, SchemaType, ValidationError};
+pub use source::{from_str_with_source, OrtValueWithSource, SourceSpan, SourceTree};
+pub use event_parser::{OrtPullParser, OrtToken, SaxHandler, SaxParser};
+pub use reader::OrtReader;
+pub use writer::OrtWriter;
+pub use visitor::Visitor;
+
+#[cfg(feature = "tokio")]
+pub use value::{from_file_async, from_reader_async, to_file_async};
+
+#[cfg(feature = "serde_json")]
+pub use serde_compat::{ndjson_to_ort, ort_to_ndjson, NonFinite};
+
+#[cfg(feature = "msgpack")]
+pub use msgpack_compat::{from_msgpack, to_msgpack};
+
+#[cfg(feature = "csv")]
+pub use csv_compat::{from_csv_str, from_csv_str_with, to_csv_string, to_csv_string_with, CsvNested, CsvOptions};
+
+#[cfg(feature = "compression")]
+pub use gzip_compat::{from_gzip_reader, to_gzip_writer};
+
+#[cfg(feature = "miette")]
+pub use miette_compat::{parse_ort_diagnostic, OrtDiagnostic};
+
+#[cfg(feature = "arbitrary")]
+pub use arbitrary_compat::BoundedOrtValue;
@@ -0,0 +1,132 @@
+use crate::error::{OrtError, OrtErrorKind};
+use crate::ort_value::OrtValue;
+use crate::parser::parse_ort;
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+use std::fmt;
+
+/// Parse error together with the document it came from, for rich reports
+///
+/// Implements `miette::Diagnostic`, pointing a label at the offending token
+/// within the full source.
+///
+/// # Example
+/// ```
+/// use miette::{GraphicalReportHandler, GraphicalTheme};
+///
+/// let content = "users:id,name:\n1,John\n2,Jane,extra\n";
+/// let diagnostic = ort_rs::parse_ort_diagnostic(content).unwrap_err();
+///
+/// let mut report = String::new();
+/// GraphicalReportHandler::new_themed(GraphicalTheme::unicode_nocolor())
+///     .with_width(80)
+///     .render_report(&mut report, &diagnostic)
+///     .unwrap();
+///
+/// assert_eq!(report, r"ort::value_count_mismatch
+///
+///   × Expected 2 values but got 3
+///    ╭─[3:8]
+///  2 │ 1,John
+///  3 │ 2,Jane,extra
+///    ·        ──┬──
+///    ·          ╰── Expected 2 values but got 3
+///    ╰────
+///   help: each row needs one value per header field; escape commas inside values
+///         as \,
+/// ");
+/// ```
+#[derive(Debug)]
+pub struct OrtDiagnostic {
+    source: String,
+    error: Box<OrtError>,
+}
+
+impl OrtDiagnostic {
+    /// Pair an error with the document that produced it
+    pub fn new(source: impl Into<String>, error: OrtError) -> Self {
+        Self {
+            source: source.into(),
+            error: Box::new(error),
+        }
+    }
+
+    /// Get the underlying error
+    pub fn error(&self) -> &OrtError {
+        &self.error
+    }
+
+    /// Get the parsed document
+    pub fn source_text(&self) -> &str {
+        &self.source
+    }
+
+    /// Convert back into the underlying error
+    pub fn into_error(self) -> OrtError {
+        *self.error
+    }
+
+    // Byte range of the error within the whole document
+    fn source_span(&self) -> Option<(usize, usize)> {
+        let line = self.source.lines().nth(self.error.line.checked_sub(1)?)?;
+        let line_start = line.as_ptr() as usize - self.source.as_ptr() as usize;
+        let code_start = line_start + (line.len() - line.trim_start().len());
+
+        match self.error.span.as_deref() {
+            Some(&(start, end)) => Some((code_start + start, code_start + end)),
+            None => Some((code_start, code_start + line.trim().len())),
+        }
+    }
+}
+
+impl fmt::Display for OrtDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.error.message)
+    }
+}
+
+impl std::error::Error for OrtDiagnostic {}
+
+impl Diagnostic for OrtDiagnostic {
+    fn code<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        Some(Box::new(format!("ort::{}", self.error.kind.name())))
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn fmt::Display + 'a>> {
+        let help = match self.error.kind {
+            OrtErrorKind::InvalidHeader => "headers look like `key:field1,field2:`",
+            OrtErrorKind::UnmatchedParenthesis => "remove the `)` or add the matching `(`",
+            OrtErrorKind::ValueCountMismatch => {
+                "each row needs one value per header field; escape commas inside values as \\,"
+            }
+            OrtErrorKind::NestedValueCountMismatch => "a nested group needs one value per nested field",
+            OrtErrorKind::DuplicateKey => "rename one of them, or choose a `ConflictPolicy` in `ParseOptions`",
+            OrtErrorKind::UnterminatedQuote => "close the string with `\"`, or escape a quote inside it as `\\\"`",
+            OrtErrorKind::UnterminatedComment => "close the comment with `*/`",
+            OrtErrorKind::TypeMismatch => "fix the cell, or remove the type from the header to infer it",
+            OrtErrorKind::CircularInclude => "remove the include that leads back to an including file",
+            OrtErrorKind::IncludeOutsideDirectory => "move the file into the including file's directory",
+            OrtErrorKind::DepthLimit => "flatten the value, or raise `max_depth` in `ParseOptions`",
+            OrtErrorKind::InputLimit => "split the input, or raise the limit in `ParseOptions`",
+            OrtErrorKind::Encoding => "save the file as UTF-8",
+            _ => return None,
+        };
+        Some(Box::new(help))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.source)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let (start, end) = self.source_span()?;
+        let label = LabeledSpan::new(Some(self.error.message.clone()), start, end - start);
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
+/// Parse ORT string, keeping the source in the error for rich reports
+///
+/// Parses exactly like `parse_ort`.
+pub fn parse_ort_diagnostic(content: &str) -> Result<OrtValue, OrtDiagnostic> {
+    parse_ort(content).map_err(|e| OrtDiagnostic::new(content, e))
+}
@@ -0,0 +1,47 @@
+use crate::error::{OrtError, OrtResult};
+use crate::ort_value::OrtValue;
+
+impl OrtValue {
+    /// Encode value as MessagePack bytes
+    ///
+    /// # Example
+    /// ```
+    /// use ort_rs::OrtValue;
+    ///
+    /// let value = OrtValue::from(vec![OrtValue::from(1), OrtValue::from("a")]);
+    /// let bytes = value.to_msgpack();
+    /// assert_eq!(OrtValue::from_msgpack(&bytes).unwrap(), value);
+    /// ```
+    pub fn to_msgpack(&self) -> Vec<u8> {
+        rmp_serde::to_vec(self).expect("OrtValue is always representable as MessagePack")
+    }
+
+    /// Decode value from MessagePack bytes
+    pub fn from_msgpack(bytes: &[u8]) -> OrtResult<OrtValue> {
+        rmp_serde::from_slice(bytes).map_err(|e| {
+            OrtError::new(0, String::new(), format!("Failed to decode MessagePack: {}", e)).with_source(e)
+        })
+    }
+}
+
+/// Encode an OrtValue as MessagePack bytes
+///
+/// Variants map directly to MessagePack types: nil, bool, int/float, str, array and map.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_str("users:id,name:\n1,John\n2,Jane")?;
+/// let bytes = ort_rs::to_msgpack(&value);
+/// assert_eq!(ort_rs::from_msgpack(&bytes)?, value);
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_msgpack(v: &OrtValue) -> Vec<u8> {
+    v.to_msgpack()
+}
+
+/// Decode an OrtValue from MessagePack bytes
+pub fn from_msgpack(bytes: &[u8]) -> OrtResult<OrtValue> {
+    OrtValue::from_msgpack(bytes)
+}
@@ -1,19 +1,130 @@
+use crate::error::{OrtError, OrtErrorKind, OrtResult};
+use crate::generator::{generate_ort, generate_value, write_ort, GenerateOptions};
+use std::cmp::Ordering;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::ops::Index;
 use std::fmt;
 
+/// Map holding the entries of an object
+///
+/// This is an `IndexMap`, which keeps keys in the order they were inserted or
+/// parsed.
+pub type Map<K, V> = indexmap::IndexMap<K, V>;
+
+/// Remove a key from an object, keeping the order of the other keys
+pub(crate) fn remove_key<V>(obj: &mut Map<String, V>, key: &str) -> Option<V> {
+    obj.shift_remove(key)
+}
+
+/// Rename the keys of an object that `rename` maps, each keeping its position
+///
+/// A key that isn't renamed itself but is the new name of another is dropped,
+/// as the renamed value replaces it.
+fn rename_keys<'a, V>(obj: &mut Map<String, V>, rename: impl Fn(&str) -> Option<&'a str>) {
+    let targets: Vec<&str> = obj.keys().filter_map(|key| rename(key)).collect();
+    for (key, value) in std::mem::take(obj) {
+        match rename(&key) {
+            Some(new) => {
+                obj.insert(new.to_string(), value);
+            }
+            None if targets.contains(&key.as_str()) => {}
+            None => {
+                obj.insert(key, value);
+            }
+        }
+    }
+}
+
 /// ORT native value type
-#[derive(Debug, Clone, PartialEq)]
+///
+/// Values can be compared, ordered and hashed. Unlike plain `f64`, a NaN
+/// number equals another NaN, so equality is an `Eq` equivalence and sorting
+/// uses a total order: null < bool < number < string < array < object, with
+/// NaN after all other numbers. The default value is `Null`.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use std::collections::HashSet;
+/// use ort_rs::OrtValue;
+///
+/// let rows = ort_rs::from_str(":id,name:\n1,John\n2,Jane\n1,John")?;
+/// let unique: HashSet<_> = rows.as_array().unwrap().iter().collect();
+/// assert_eq!(unique.len(), 2);
+///
+/// let mut values = vec![OrtValue::from("a"), OrtValue::from(f64::NAN), OrtValue::Null, OrtValue::from(2)];
+/// values.sort();
+/// assert_eq!(values[..2], [OrtValue::Null, OrtValue::from(2)]);
+/// assert_eq!(values[3], OrtValue::from("a"));
+///
+/// let mut counts = ort_rs::Map::new();
+/// counts.entry("missing".to_string()).or_insert_with(OrtValue::default);
+/// assert_eq!(counts["missing"], OrtValue::Null);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
 pub enum OrtValue {
+    #[default]
     Null,
     Bool(bool),
     Number(f64),
     String(String),
     Array(Vec<OrtValue>),
-    Object(HashMap<String, OrtValue>),
+    Object(Map<String, OrtValue>),
+}
+
+/// Type of an OrtValue, without its contents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrtType {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl OrtType {
+    /// Get the type name
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrtType::Null => "null",
+            OrtType::Bool => "bool",
+            OrtType::Number => "number",
+            OrtType::String => "string",
+            OrtType::Array => "array",
+            OrtType::Object => "object",
+        }
+    }
+}
+
+impl fmt::Display for OrtType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
 }
 
 impl OrtValue {
+    /// Get the value type
+    pub fn value_type(&self) -> OrtType {
+        match self {
+            OrtValue::Null => OrtType::Null,
+            OrtValue::Bool(_) => OrtType::Bool,
+            OrtValue::Number(_) => OrtType::Number,
+            OrtValue::String(_) => OrtType::String,
+            OrtValue::Array(_) => OrtType::Array,
+            OrtValue::Object(_) => OrtType::Object,
+        }
+    }
+
+    /// Get the type name ("null", "bool", "number", "string", "array" or "object")
+    pub fn type_name(&self) -> &'static str {
+        self.value_type().name()
+    }
+
     /// Check if value is null
     pub fn is_null(&self) -> bool {
         matches!(self, OrtValue::Null)
@@ -76,6 +187,29 @@ impl OrtValue {
         }
     }
 
+    /// Get as f64 if the number is finite
+    ///
+    /// Unlike `as_f64`, NaN and the infinities, which ORT writes as `nan`,
+    /// `inf` and `-inf` and JSON can't hold, give `None`.
+    ///
+    /// # Example
+    /// ```
+    /// use ort_rs::OrtValue;
+    ///
+    /// assert_eq!(OrtValue::from(1.5).as_number_exact(), Some(1.5));
+    /// assert_eq!(OrtValue::from(f64::NAN).as_number_exact(), None);
+    /// assert_eq!(OrtValue::from(f64::INFINITY).as_number_exact(), None);
+    /// assert!(!OrtValue::from("1.5").is_finite_number());
+    /// ```
+    pub fn as_number_exact(&self) -> Option<f64> {
+        self.as_f64().filter(|n| n.is_finite())
+    }
+
+    /// Check if value is a number other than NaN or an infinity
+    pub fn is_finite_number(&self) -> bool {
+        self.as_number_exact().is_some()
+    }
+
     /// Get as string reference
     pub fn as_str(&self) -> Option<&str> {
         match self {
@@ -101,7 +235,7 @@ impl OrtValue {
     }
 
     /// Get as object reference
-    pub fn as_object(&self) -> Option<&HashMap<String, OrtValue>> {
+    pub fn as_object(&self) -> Option<&Map<String, OrtValue>> {
         match self {
             OrtValue::Object(obj) => Some(obj),
             _ => None,
@@ -109,7 +243,55 @@ impl OrtValue {
     }
 
     /// Get as mutable object reference
-    pub fn as_object_mut(&mut self) -> Option<&mut HashMap<String, OrtValue>> {
+    pub fn as_object_mut(&mut self) -> Option<&mut Map<String, OrtValue>> {
+        match self {
+            OrtValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Take as boolean
+    pub fn take_bool(self) -> Option<bool> {
+        match self {
+            OrtValue::Bool(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// Take as f64
+    pub fn take_f64(self) -> Option<f64> {
+        match self {
+            OrtValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// Take as i64
+    pub fn take_i64(self) -> Option<i64> {
+        match self {
+            OrtValue::Number(n) => Some(n as i64),
+            _ => None,
+        }
+    }
+
+    /// Take as owned string
+    pub fn take_string(self) -> Option<String> {
+        match self {
+            OrtValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Take as owned array
+    pub fn take_array(self) -> Option<Vec<OrtValue>> {
+        match self {
+            OrtValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    /// Take as owned object
+    pub fn take_object(self) -> Option<Map<String, OrtValue>> {
         match self {
             OrtValue::Object(obj) => Some(obj),
             _ => None,
@@ -131,6 +313,823 @@ impl OrtValue {
             _ => None,
         }
     }
+
+    /// Get value by key, or null when the key is missing or this isn't an
+    /// object, as indexing does
+    pub fn get_or_default(&self, key: &str) -> &OrtValue {
+        static NULL: OrtValue = OrtValue::Null;
+        self.get(key).unwrap_or(&NULL)
+    }
+
+    /// Get value by key, converted to `T`
+    ///
+    /// Fails when the key is missing, this isn't an object or the value has
+    /// another type, naming the field in the error.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let config = ort_rs::from_str("host:\nlocalhost\n\nport:\n5432\n\ntags:\n[db,primary]")?;
+    ///
+    /// let host: String = config.get_as("host")?;
+    /// let port: i64 = config.get_as("port")?;
+    /// let tags: Vec<ort_rs::OrtValue> = config.get_as("tags")?;
+    /// assert_eq!((host.as_str(), port, tags.len()), ("localhost", 5432, 2));
+    ///
+    /// let error = config.get_as::<String>("port").unwrap_err();
+    /// assert_eq!(error.message, "field 'port' has type 'number', expected 'string'");
+    /// assert_eq!(config.get_as::<bool>("debug").unwrap_err().message, "field 'debug' is missing");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_as<T: FromOrtValue>(&self, key: &str) -> OrtResult<T> {
+        let Some(value) = self.get(key) else {
+            return Err(OrtError::new(0, String::new(), format!("field '{}' is missing", key)));
+        };
+        T::from_ort_value(value).ok_or_else(|| {
+            let message = format!("field '{}' has type '{}', expected '{}'", key, value.type_name(), T::EXPECTED);
+            OrtError::new(0, String::new(), message).with_kind(OrtErrorKind::TypeMismatch)
+        })
+    }
+
+    /// Get mutable value by key, inserting `default` first when the key is
+    /// missing
+    ///
+    /// A null value becomes an empty object first.
+    ///
+    /// # Panics
+    /// Panics if the value is neither an object nor null.
+    ///
+    /// # Example
+    /// ```
+    /// use ort_rs::OrtValue;
+    ///
+    /// let mut config = OrtValue::Null;
+    /// config.get_or_insert("retries", 3);
+    /// config.get_or_insert("retries", 5);
+    /// if let Some(tags) = config.get_or_insert("tags", Vec::<OrtValue>::new()).as_array_mut() {
+    ///     tags.push(OrtValue::from("beta"));
+    /// }
+    ///
+    /// assert_eq!(config.get_or_default("retries").as_i64(), Some(3));
+    /// assert_eq!(config.get_or_default("tags").to_string(), ":\n[beta]");
+    /// assert!(config.get_or_default("timeout").is_null());
+    /// ```
+    pub fn get_or_insert(&mut self, key: impl Into<String>, default: impl Into<OrtValue>) -> &mut OrtValue {
+        if self.is_null() {
+            *self = OrtValue::Object(Map::new());
+        }
+        match self {
+            OrtValue::Object(obj) => obj.entry(key.into()).or_insert_with(|| default.into()),
+            other => panic!("get_or_insert called on {} value", other.type_name()),
+        }
+    }
+
+    /// Get the value at a dot-separated path of object keys and array indexes
+    ///
+    /// Paths use the same form as `to_flat_map`, and the empty path is the
+    /// value itself.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let value = ort_rs::from_str("users:id,name,tags:\n1,John,[admin,dev]\n2,Jane,[]")?;
+    ///
+    /// assert_eq!(value.get_path("users.1.name").and_then(|v| v.as_str()), Some("Jane"));
+    /// assert_eq!(value.get_path("users.0.tags.1").and_then(|v| v.as_str()), Some("dev"));
+    /// assert_eq!(value.get_path(""), Some(&value));
+    /// assert_eq!(value.get_path("users.2.name"), None);
+    /// assert_eq!(value.get_path("users.name"), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_path(&self, path: &str) -> Option<&OrtValue> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('.').try_fold(self, |value, segment| match value {
+            OrtValue::Object(obj) => obj.get(segment),
+            OrtValue::Array(arr) => arr.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        })
+    }
+
+    /// Check if a dot-separated path leads to a value, null included
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let value = ort_rs::from_str("users:id,email,tags:\n1,,[]")?;
+    ///
+    /// assert!(value.path_exists("users.0.email"));
+    /// assert!(!value.path_has_value("users.0.email"));
+    /// assert!(value.path_has_value("users.0.tags"));
+    /// assert!(!value.path_exists("users.0.phone"));
+    /// assert!(!value.path_exists("users.1"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn path_exists(&self, path: &str) -> bool {
+        self.get_path(path).is_some()
+    }
+
+    /// Check if a dot-separated path leads to a value other than null
+    ///
+    /// Empty arrays and objects count as values.
+    pub fn path_has_value(&self, path: &str) -> bool {
+        self.get_path(path).is_some_and(|value| !value.is_null())
+    }
+
+    /// Deep-merge another value into this one, with `other` taking priority
+    ///
+    /// Objects are merged key by key, recursively; any other value, arrays
+    /// included, is replaced by the one from `other`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let mut config = ort_rs::from_str("server:\n(host:localhost,port:80,tags:[a])\n\nname:\nbase\n")?;
+    /// let overrides = ort_rs::from_str("server:\n(port:8080,tags:[b],tls:(on:true))\n")?;
+    ///
+    /// let mut appended = config.clone();
+    /// config.merge(overrides.clone());
+    /// assert_eq!(config, ort_rs::from_str("server:\n(host:localhost,port:8080,tags:[b],tls:(on:true))\n\nname:\nbase\n")?);
+    ///
+    /// appended.merge_append(overrides);
+    /// assert_eq!(appended["server"]["tags"], ort_rs::from_str("tags:\n[a,b]")?["tags"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge(&mut self, other: OrtValue) {
+        merge_into(self, other, false);
+    }
+
+    /// Deep-merge like `merge`, but append arrays from `other` to the arrays
+    /// they meet instead of replacing them
+    pub fn merge_append(&mut self, other: OrtValue) {
+        merge_into(self, other, true);
+    }
+
+    /// Apply a JSON Merge Patch (RFC 7396)
+    ///
+    /// An object patch is applied key by key: a null removes the key, and any
+    /// other value is merge-patched into it, recursively, with a value that
+    /// isn't an object replaced by an empty one first. Any other patch, arrays
+    /// included, replaces the value. Unlike `merge`, a patch can delete keys.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let mut user = ort_rs::from_str(":\n(name:Alice,email:a@x.io,address:(city:Seoul,zip:04524),tags:[a,b])\n")?;
+    /// let patch = ort_rs::from_str(":\n(email:,address:(zip:,country:KR),tags:[c])\n")?;
+    ///
+    /// user.merge_patch(&patch);
+    /// assert_eq!(user, ort_rs::from_str(":\n(name:Alice,address:(city:Seoul,country:KR),tags:[c])\n")?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge_patch(&mut self, patch: &OrtValue) {
+        let OrtValue::Object(patch) = patch else {
+            *self = patch.clone();
+            return;
+        };
+        if !self.is_object() {
+            *self = OrtValue::Object(Map::new());
+        }
+        if let OrtValue::Object(obj) = self {
+            for (key, value) in patch {
+                if value.is_null() {
+                    remove_key(obj, key);
+                } else {
+                    obj.entry(key.clone()).or_insert(OrtValue::Null).merge_patch(value);
+                }
+            }
+        }
+    }
+
+    /// Check if value is truthy
+    ///
+    /// Null, `false`, zero, NaN, and empty strings, arrays and objects are falsy;
+    /// everything else is truthy.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            OrtValue::Null => false,
+            OrtValue::Bool(b) => *b,
+            OrtValue::Number(n) => *n != 0.0 && !n.is_nan(),
+            OrtValue::String(s) => !s.is_empty(),
+            OrtValue::Array(arr) => !arr.is_empty(),
+            OrtValue::Object(obj) => !obj.is_empty(),
+        }
+    }
+
+    /// Check if value is falsy (the opposite of `is_truthy`)
+    pub fn is_falsy(&self) -> bool {
+        !self.is_truthy()
+    }
+
+    /// Get a canonical copy of the value
+    ///
+    /// Whole numbers are normalized to their integer value (so `-0.0` becomes
+    /// `0`), strings are trimmed, and object keys are inserted in alphabetical
+    /// order, recursively.
+    pub fn normalize(&self) -> OrtValue {
+        match self {
+            OrtValue::Number(n) if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 => {
+                OrtValue::Number(*n as i64 as f64)
+            }
+            OrtValue::String(s) => OrtValue::String(s.trim().to_string()),
+            OrtValue::Array(arr) => OrtValue::Array(arr.iter().map(OrtValue::normalize).collect()),
+            OrtValue::Object(obj) => {
+                let mut entries: Vec<_> = obj.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                OrtValue::Object(
+                    entries
+                        .into_iter()
+                        .map(|(k, v)| (k.clone(), v.normalize()))
+                        .collect(),
+                )
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Flatten nested objects and arrays into dot-separated key paths
+    ///
+    /// `{user: {id: 1, tags: [a]}}` becomes `{"user.id": 1, "user.tags.0": "a"}`.
+    /// Empty objects and arrays are kept as leaf values.
+    pub fn to_flat_map(&self) -> HashMap<String, OrtValue> {
+        let mut result = HashMap::new();
+        flatten_into(self, String::new(), &mut result);
+        result
+    }
+
+    /// Rebuild a nested value from dot-separated key paths
+    ///
+    /// This is the inverse of `to_flat_map`: objects whose keys are exactly
+    /// `0..n` are turned back into arrays. A path that is also the prefix of
+    /// another, such as `a` and `a.b`, is an error.
+    ///
+    /// # Example
+    /// ```
+    /// use ort_rs::OrtValue;
+    /// use std::collections::HashMap;
+    ///
+    /// let flat = HashMap::from([("a".to_string(), OrtValue::from(1)), ("a.b".to_string(), OrtValue::from(2))]);
+    /// let error = OrtValue::from_flat_map(flat).unwrap_err();
+    /// assert_eq!(error.kind, ort_rs::OrtErrorKind::DuplicateKey);
+    /// ```
+    pub fn from_flat_map(map: HashMap<String, OrtValue>) -> OrtResult<OrtValue> {
+        // An empty path is the whole value, so it conflicts with any other
+        let mut conflicts: Vec<(&str, &str)> = vec![];
+        for path in map.keys() {
+            let prefixes = std::iter::once("").chain(path.match_indices('.').map(|(i, _)| &path[..i]));
+            conflicts.extend(
+                prefixes
+                    .filter(|prefix| prefix != path && map.contains_key(*prefix))
+                    .map(|prefix| (prefix, path.as_str())),
+            );
+        }
+        conflicts.sort_unstable();
+        if let Some((prefix, path)) = conflicts.first() {
+            let message = format!("key '{}' conflicts with '{}'", prefix, path);
+            return Err(OrtError::new(0, String::new(), message).with_kind(OrtErrorKind::DuplicateKey));
+        }
+
+        let mut root = OrtValue::Object(Map::new());
+
+        for (path, value) in map {
+            if path.is_empty() {
+                return Ok(value);
+            }
+
+            let mut current = &mut root;
+            let mut segments = path.split('.').peekable();
+            while let Some(segment) = segments.next() {
+                if !current.is_object() {
+                    *current = OrtValue::Object(Map::new());
+                }
+                let obj = current.as_object_mut().unwrap();
+                if segments.peek().is_none() {
+                    obj.insert(segment.to_string(), value);
+                    break;
+                }
+                current = obj
+                    .entry(segment.to_string())
+                    .or_insert_with(|| OrtValue::Object(Map::new()));
+            }
+        }
+
+        Ok(restore_arrays(root))
+    }
+
+    /// Keep only the given keys of an object, or of every object in an array
+    ///
+    /// Missing keys are omitted rather than inserted as null. Other values are
+    /// returned unchanged.
+    pub fn select_fields(&self, fields: &[&str]) -> OrtValue {
+        match self {
+            OrtValue::Object(obj) => OrtValue::Object(
+                fields
+                    .iter()
+                    .filter_map(|&k| obj.get(k).map(|v| (k.to_string(), v.clone())))
+                    .collect(),
+            ),
+            OrtValue::Array(arr) => {
+                OrtValue::Array(arr.iter().map(|item| item.select_fields(fields)).collect())
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Copy an object with a field added or replaced
+    ///
+    /// Other values are returned unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let record = ort_rs::from_str(":id,name,temp:\n1,John,x")?;
+    /// let updated = record.with_field("name", "Jane").with_field("active", true).without_field("temp");
+    ///
+    /// assert_eq!(updated, ort_rs::from_str(":active,id,name:\ntrue,1,Jane")?);
+    /// assert_eq!(record["name"].as_str(), Some("John"));
+    /// assert!(record.get("temp").is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_field(&self, key: impl Into<String>, value: impl Into<OrtValue>) -> OrtValue {
+        let mut copy = self.clone();
+        if let OrtValue::Object(obj) = &mut copy {
+            obj.insert(key.into(), value.into());
+        }
+        copy
+    }
+
+    /// Copy an object without a field, keeping the order of the others
+    ///
+    /// Other values are returned unchanged.
+    pub fn without_field(&self, key: &str) -> OrtValue {
+        let mut copy = self.clone();
+        if let OrtValue::Object(obj) = &mut copy {
+            remove_key(obj, key);
+        }
+        copy
+    }
+
+    /// Rename a key of an object in place, returning `true` if the key existed
+    ///
+    /// An existing value under `new` is replaced.
+    pub fn rename_key(&mut self, old: &str, new: &str) -> bool {
+        let obj = match self {
+            OrtValue::Object(obj) if obj.contains_key(old) => obj,
+            _ => return false,
+        };
+        rename_keys(obj, |key| (key == old).then_some(new));
+        true
+    }
+
+    /// Rename several keys of an object at once, each keeping its position
+    ///
+    /// Keys are renamed together, so mappings may swap or chain keys
+    /// (`a -> b`, `b -> a`).
+    ///
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use std::collections::HashMap;
+    ///
+    /// let mut record = ort_rs::from_str(":id,first,last,age:\n1,John,Doe,30")?;
+    /// let mapping = HashMap::from([("first".to_string(), "last".to_string()), ("last".to_string(), "first".to_string())]);
+    /// record.rename_fields(&mapping);
+    /// assert_eq!(record.as_object().unwrap().keys().collect::<Vec<_>>(), ["id", "last", "first", "age"]);
+    /// assert_eq!(record["first"].as_str(), Some("Doe"));
+    ///
+    /// record.rename_key("id", "age");
+    /// assert_eq!(record.as_object().unwrap().keys().collect::<Vec<_>>(), ["age", "last", "first"]);
+    /// assert_eq!(record["age"].as_i64(), Some(1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rename_fields(&mut self, mapping: &HashMap<String, String>) {
+        if let OrtValue::Object(obj) = self {
+            rename_keys(obj, |key| mapping.get(key).map(String::as_str));
+        }
+    }
+
+    /// Apply `rename_fields` to every object in an array
+    pub fn rename_fields_in_array(&mut self, mapping: &HashMap<String, String>) {
+        if let OrtValue::Array(arr) = self {
+            for item in arr {
+                item.rename_fields(mapping);
+            }
+        }
+    }
+
+    /// Rename a column of every row in an array of objects
+    ///
+    /// `column` may be a dot-separated path to a nested field, such as
+    /// `address.city`, and `new` replaces its last segment. It is an error if
+    /// a row lacks the column or already has the new one; nothing is changed
+    /// then.
+    ///
+    /// The column methods make up a migration:
+    ///
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::OrtValue;
+    ///
+    /// let mut value = ort_rs::from_str("users:id,legacy_id,user_name,address(city,zip):\n1,u-1,John,(seoul,04524)\n2,u-2,Jane,(busan,)")?;
+    /// let users = value.get_mut("users").unwrap();
+    /// users.rename_column("user_name", "name")?;
+    /// users.rename_column("address.zip", "postcode")?;
+    /// users.add_column("created_at", OrtValue::Null)?;
+    /// users.drop_column("legacy_id")?;
+    /// users.map_column("address.city", |city| OrtValue::from(city.coerce_to_string().to_uppercase()))?;
+    ///
+    /// assert_eq!(ort_rs::to_string(&value), "users:address(city,postcode),created_at,id,name:\n(SEOUL,4524),,1,John\n(BUSAN,),,2,Jane");
+    ///
+    /// let users = value.get_mut("users").unwrap();
+    /// assert_eq!(users.drop_column("legacy_id").unwrap_err().message, "Column 'legacy_id' not found in row 0");
+    /// assert_eq!(users.rename_column("id", "name").unwrap_err().message, "Column 'name' already exists in row 0");
+    /// assert_eq!(users.add_column("geo.lat", OrtValue::Null).unwrap_err().message, "Column 'geo' not found in row 0");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rename_column(&mut self, column: &str, new: &str) -> OrtResult<()> {
+        let renamed = match column.rsplit_once('.') {
+            Some((parent, _)) => format!("{}.{}", parent, new),
+            None => new.to_string(),
+        };
+        self.edit_column(&renamed, false, |_, _| {})?;
+        self.edit_column(column, true, |parent, key| {
+            if let Some(value) = remove_key(parent, key) {
+                parent.insert(new.to_string(), value);
+            }
+        })
+    }
+
+    /// Add a column holding `default` to every row in an array of objects
+    ///
+    /// `column` may be a dot-separated path into a nested field. It is an
+    /// error if a row already has the column.
+    pub fn add_column(&mut self, column: &str, default: OrtValue) -> OrtResult<()> {
+        self.edit_column(column, false, |parent, key| {
+            parent.insert(key.to_string(), default.clone());
+        })
+    }
+
+    /// Remove a column from every row in an array of objects
+    ///
+    /// `column` may be a dot-separated path into a nested field. It is an
+    /// error if a row lacks the column.
+    pub fn drop_column(&mut self, column: &str) -> OrtResult<()> {
+        self.edit_column(column, true, |parent, key| {
+            remove_key(parent, key);
+        })
+    }
+
+    /// Replace a column's value in every row of an array of objects with `f` of it
+    ///
+    /// `column` may be a dot-separated path into a nested field. It is an
+    /// error if a row lacks the column.
+    pub fn map_column<F: FnMut(OrtValue) -> OrtValue>(&mut self, column: &str, mut f: F) -> OrtResult<()> {
+        self.edit_column(column, true, |parent, key| {
+            if let Some(value) = parent.get_mut(key) {
+                *value = f(std::mem::replace(value, OrtValue::Null));
+            }
+        })
+    }
+
+    /// Call `edit` with the object holding `column` and its key, in every row
+    ///
+    /// Every row is checked to have the column, or with `exists` false to
+    /// lack it, before any is edited.
+    fn edit_column(&mut self, column: &str, exists: bool, mut edit: impl FnMut(&mut Map<String, OrtValue>, &str)) -> OrtResult<()> {
+        let rows = match self {
+            OrtValue::Array(rows) => rows,
+            other => {
+                let message = format!("Expected an array of objects but found {}", other.type_name());
+                return Err(OrtError::new(0, String::new(), message));
+            }
+        };
+        let (path, key) = match column.rsplit_once('.') {
+            Some((path, key)) => (Some(path), key),
+            None => (None, column),
+        };
+        for (i, row) in rows.iter().enumerate() {
+            let parent = match path {
+                Some(path) => path.split('.').try_fold(row, |value, segment| value.get(segment)),
+                None => Some(row),
+            };
+            let message = match parent.and_then(OrtValue::as_object) {
+                Some(obj) if obj.contains_key(key) == exists => continue,
+                Some(_) if exists => format!("Column '{}' not found in row {}", column, i),
+                Some(_) => format!("Column '{}' already exists in row {}", column, i),
+                None => format!("Column '{}' not found in row {}", path.unwrap_or(column), i),
+            };
+            return Err(OrtError::new(0, String::new(), message));
+        }
+
+        for row in rows {
+            let parent = match path {
+                Some(path) => path.split('.').try_fold(row, |value, segment| value.get_mut(segment)),
+                None => Some(row),
+            };
+            if let Some(obj) = parent.and_then(OrtValue::as_object_mut) {
+                edit(obj, key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check if any array element matches, stopping at the first match
+    ///
+    /// Returns `false` for values that are not arrays.
+    pub fn any<F: Fn(&OrtValue) -> bool>(&self, pred: F) -> bool {
+        match self {
+            OrtValue::Array(arr) => arr.iter().any(pred),
+            _ => false,
+        }
+    }
+
+    /// Check if all array elements match, stopping at the first mismatch
+    ///
+    /// An empty array matches; values that are not arrays do not.
+    pub fn all<F: Fn(&OrtValue) -> bool>(&self, pred: F) -> bool {
+        match self {
+            OrtValue::Array(arr) => arr.iter().all(pred),
+            _ => false,
+        }
+    }
+
+    /// Count the array elements that match (0 for values that are not arrays)
+    pub fn count<F: Fn(&OrtValue) -> bool>(&self, pred: F) -> usize {
+        match self {
+            OrtValue::Array(arr) => arr.iter().filter(|item| pred(item)).count(),
+            _ => 0,
+        }
+    }
+
+    /// Keep the array elements that match, as a new array
+    ///
+    /// Gives an empty array for values that are not arrays.
+    pub fn filter_rows<F: Fn(&OrtValue) -> bool>(&self, pred: F) -> OrtValue {
+        match self {
+            OrtValue::Array(arr) => OrtValue::Array(arr.iter().filter(|item| pred(item)).cloned().collect()),
+            _ => OrtValue::Array(vec![]),
+        }
+    }
+
+    /// Find the first array element that matches
+    pub fn find_row<F: Fn(&OrtValue) -> bool>(&self, pred: F) -> Option<&OrtValue> {
+        self.as_array()?.iter().find(|item| pred(item))
+    }
+
+    /// Keep the objects of an array whose `field` equals `expected`
+    ///
+    /// `field` may be a dot-separated path into nested objects, as for
+    /// `get_path`. Gives an empty array for values that are not arrays.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let value = ort_rs::from_str("users:id,active,profile(city,age):\n1,true,(Seoul,30)\n2,false,(Busan,25)\n3,true,(Busan,41)")?;
+    /// let users = &value["users"];
+    ///
+    /// let active = users.rows_where("active", true);
+    /// assert_eq!(active.select_fields(&["id"]).to_string(), ":id:\n1\n3");
+    ///
+    /// let busan = users.rows_where("profile.city", "Busan");
+    /// assert_eq!(busan.count(|_| true), 2);
+    ///
+    /// let older = users.filter_rows(|user| user.get_path("profile.age").and_then(|age| age.as_i64()) > Some(28));
+    /// assert_eq!(older.select_fields(&["id"]).to_string(), ":id:\n1\n3");
+    /// assert_eq!(users.find_row(|user| user["active"].as_bool() == Some(false)).unwrap()["id"].as_i64(), Some(2));
+    ///
+    /// assert_eq!(value.rows_where("active", true), ort_rs::OrtValue::Array(vec![]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rows_where(&self, field: &str, expected: impl Into<OrtValue>) -> OrtValue {
+        let expected = expected.into();
+        self.filter_rows(|row| row.is_object() && row.get_path(field) == Some(&expected))
+    }
+
+    /// Sum the numbers of an array, skipping other elements
+    ///
+    /// Returns `None` for values that are not arrays and `Some(0.0)` for an
+    /// array without numbers.
+    pub fn sum(&self) -> Option<f64> {
+        Some(self.numbers()?.sum())
+    }
+
+    /// Smallest number of an array, skipping other elements
+    pub fn min_value(&self) -> Option<f64> {
+        self.numbers()?.reduce(f64::min)
+    }
+
+    /// Largest number of an array, skipping other elements
+    pub fn max_value(&self) -> Option<f64> {
+        self.numbers()?.reduce(f64::max)
+    }
+
+    /// Average of the numbers of an array, skipping other elements
+    ///
+    /// Returns `None` if the array has no numbers.
+    pub fn mean(&self) -> Option<f64> {
+        let (sum, count) = self
+            .numbers()?
+            .fold((0.0, 0usize), |(sum, count), n| (sum + n, count + 1));
+        (count > 0).then(|| sum / count as f64)
+    }
+
+    /// Find the first object of an array whose `key` equals `value`
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::OrtValue;
+    ///
+    /// let users = ort_rs::from_str(":id,name,age:\n1,John,30\n2,Jane,25")?;
+    /// assert_eq!(users.mean(), None);
+    /// assert_eq!(users.find_by("id", &OrtValue::from(2)).unwrap()["name"].as_str(), Some("Jane"));
+    ///
+    /// let ages = OrtValue::from(vec![OrtValue::from(30), OrtValue::Null, OrtValue::from(25)]);
+    /// assert_eq!(ages.sum(), Some(55.0));
+    /// assert_eq!(ages.min_value(), Some(25.0));
+    /// assert_eq!(ages.max_value(), Some(30.0));
+    /// assert_eq!(ages.mean(), Some(27.5));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_by(&self, key: &str, value: &OrtValue) -> Option<&OrtValue> {
+        self.as_array()?
+            .iter()
+            .find(|item| item.get(key) == Some(value))
+    }
+
+    /// Collect every string in the value, depth first
+    ///
+    /// Arrays and objects are visited in document order, as parsed or inserted.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let value = ort_rs::from_str("users:id,name,tags:\n1,John,[admin,dev]\n2,Jane,[]")?;
+    /// assert_eq!(value.collect_strings(), ["John", "admin", "dev", "Jane"]);
+    /// assert_eq!(value.collect_strings_at_key("tags"), ["admin", "dev"]);
+    ///
+    /// let place = ort_rs::from_str(":zone,area:\nnorth,east")?;
+    /// assert_eq!(place.collect_strings(), ["north", "east"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn collect_strings(&self) -> Vec<&str> {
+        let mut strings = vec![];
+        self.walk_strings(None, &mut strings);
+        strings
+    }
+
+    /// Collect the strings in the values of fields named `key`, depth first,
+    /// as `collect_strings` does
+    pub fn collect_strings_at_key(&self, key: &str) -> Vec<&str> {
+        let mut strings = vec![];
+        self.walk_strings(Some(key), &mut strings);
+        strings
+    }
+
+    /// Collect strings, only within fields named `key` while it is given
+    fn walk_strings<'a>(&'a self, key: Option<&str>, strings: &mut Vec<&'a str>) {
+        match self {
+            OrtValue::String(s) if key.is_none() => strings.push(s),
+            OrtValue::Array(arr) => arr.iter().for_each(|item| item.walk_strings(key, strings)),
+            OrtValue::Object(obj) => {
+                for (k, v) in obj {
+                    v.walk_strings(key.filter(|key| k != key), strings);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn numbers(&self) -> Option<impl Iterator<Item = f64> + '_> {
+        Some(self.as_array()?.iter().filter_map(OrtValue::as_f64))
+    }
+
+    /// Convert to ORT string, same as `generate_ort`
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// let value = ort_rs::from_str("users:name:\nJohn\nJane")?;
+    /// assert_eq!(value.to_ort_string(), "users:name:\nJohn\nJane");
+    /// assert_eq!(value.to_string(), value.to_ort_string());
+    /// assert_eq!(ort_rs::from_str(&value.to_string())?, value);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_ort_string(&self) -> String {
+        generate_ort(self)
+    }
+
+    /// Loosely convert any value to a string
+    ///
+    /// Null becomes `""`, whole numbers have no trailing `.0`, and arrays and
+    /// objects use their inline ORT form.
+    pub fn coerce_to_string(&self) -> String {
+        match self {
+            OrtValue::Null => String::new(),
+            OrtValue::Bool(b) => b.to_string(),
+            OrtValue::Number(n) => n.to_string(),
+            OrtValue::String(s) => s.clone(),
+            OrtValue::Array(_) | OrtValue::Object(_) => generate_value(self, &GenerateOptions::default()),
+        }
+    }
+
+    /// Loosely convert to a number, parsing strings that look like numbers
+    ///
+    /// Strings that parse to NaN or an infinity, such as `"nan"` or `"1e400"`,
+    /// give `None`; numbers are returned as they are.
+    ///
+    /// # Example
+    /// ```
+    /// use ort_rs::OrtValue;
+    ///
+    /// assert_eq!(OrtValue::from(" 2.5 ").coerce_to_number(), Some(2.5));
+    /// assert_eq!(OrtValue::from("nan").coerce_to_number(), None);
+    /// assert_eq!(OrtValue::from("1e400").coerce_to_number(), None);
+    /// assert_eq!(OrtValue::from(f64::INFINITY).coerce_to_number(), Some(f64::INFINITY));
+    /// ```
+    pub fn coerce_to_number(&self) -> Option<f64> {
+        match self {
+            OrtValue::Number(n) => Some(*n),
+            OrtValue::String(s) => s.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+            _ => None,
+        }
+    }
+}
+
+fn merge_into(target: &mut OrtValue, value: OrtValue, append_arrays: bool) {
+    match (target, value) {
+        (OrtValue::Object(target), OrtValue::Object(obj)) => {
+            for (key, value) in obj {
+                match target.get_mut(&key) {
+                    Some(existing) => merge_into(existing, value, append_arrays),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (OrtValue::Array(target), OrtValue::Array(items)) if append_arrays => target.extend(items),
+        (target, value) => *target = value,
+    }
+}
+
+fn flatten_into(value: &OrtValue, prefix: String, result: &mut HashMap<String, OrtValue>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{}", prefix, key)
+        }
+    };
+
+    match value {
+        OrtValue::Object(obj) if !obj.is_empty() => {
+            for (k, v) in obj {
+                flatten_into(v, join(k), result);
+            }
+        }
+        OrtValue::Array(arr) if !arr.is_empty() => {
+            for (i, v) in arr.iter().enumerate() {
+                flatten_into(v, join(&i.to_string()), result);
+            }
+        }
+        _ => {
+            result.insert(prefix, value.clone());
+        }
+    }
+}
+
+fn restore_arrays(value: OrtValue) -> OrtValue {
+    match value {
+        OrtValue::Object(obj) => {
+            let is_array = !obj.is_empty()
+                && (0..obj.len()).all(|i| obj.contains_key(&i.to_string()));
+
+            if is_array {
+                let mut items: Vec<_> = obj
+                    .into_iter()
+                    .map(|(k, v)| (k.parse::<usize>().unwrap(), restore_arrays(v)))
+                    .collect();
+                items.sort_by_key(|(i, _)| *i);
+                OrtValue::Array(items.into_iter().map(|(_, v)| v).collect())
+            } else {
+                OrtValue::Object(obj.into_iter().map(|(k, v)| (k, restore_arrays(v))).collect())
+            }
+        }
+        other => other,
+    }
 }
 
 // Implement Index for string keys (object access)
@@ -159,38 +1158,104 @@ impl Index<usize> for OrtValue {
     }
 }
 
-// Display implementation
-impl fmt::Display for OrtValue {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl PartialEq for OrtValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (OrtValue::Null, OrtValue::Null) => true,
+            (OrtValue::Bool(a), OrtValue::Bool(b)) => a == b,
+            (OrtValue::Number(a), OrtValue::Number(b)) => a == b || (a.is_nan() && b.is_nan()),
+            (OrtValue::String(a), OrtValue::String(b)) => a == b,
+            (OrtValue::Array(a), OrtValue::Array(b)) => a == b,
+            (OrtValue::Object(a), OrtValue::Object(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for OrtValue {}
+
+impl PartialOrd for OrtValue {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Order by type (null < bool < number < string < array < object), then by
+// content. NaN sorts after every other number and objects compare their
+// entries sorted by key.
+impl Ord for OrtValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (OrtValue::Bool(a), OrtValue::Bool(b)) => a.cmp(b),
+            (OrtValue::Number(a), OrtValue::Number(b)) => match (a.is_nan(), b.is_nan()) {
+                (false, false) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+                (a_nan, b_nan) => a_nan.cmp(&b_nan),
+            },
+            (OrtValue::String(a), OrtValue::String(b)) => a.cmp(b),
+            (OrtValue::Array(a), OrtValue::Array(b)) => a.cmp(b),
+            (OrtValue::Object(a), OrtValue::Object(b)) => {
+                let mut a: Vec<_> = a.iter().collect();
+                let mut b: Vec<_> = b.iter().collect();
+                a.sort_unstable_by_key(|(k, _)| *k);
+                b.sort_unstable_by_key(|(k, _)| *k);
+                a.cmp(&b)
+            }
+            _ => type_rank(self).cmp(&type_rank(other)),
+        }
+    }
+}
+
+fn type_rank(value: &OrtValue) -> u8 {
+    match value {
+        OrtValue::Null => 0,
+        OrtValue::Bool(_) => 1,
+        OrtValue::Number(_) => 2,
+        OrtValue::String(_) => 3,
+        OrtValue::Array(_) => 4,
+        OrtValue::Object(_) => 5,
+    }
+}
+
+// Hash consistently with PartialEq: 0.0 and -0.0 hash alike, as do all NaNs,
+// and objects hash the same whatever their iteration order
+impl Hash for OrtValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
         match self {
-            OrtValue::Null => write!(f, "null"),
-            OrtValue::Bool(b) => write!(f, "{}", b),
-            OrtValue::Number(n) => write!(f, "{}", n),
-            OrtValue::String(s) => write!(f, "\"{}\"", s),
-            OrtValue::Array(arr) => {
-                write!(f, "[")?;
-                for (i, item) in arr.iter().enumerate() {
-                    if i > 0 {
-                        write!(f, ", ")?;
-                    }
-                    write!(f, "{}", item)?;
-                }
-                write!(f, "]")
+            OrtValue::Null => {}
+            OrtValue::Bool(b) => b.hash(state),
+            OrtValue::Number(n) => {
+                let n = if *n == 0.0 {
+                    0.0
+                } else if n.is_nan() {
+                    f64::NAN
+                } else {
+                    *n
+                };
+                n.to_bits().hash(state);
             }
+            OrtValue::String(s) => s.hash(state),
+            OrtValue::Array(arr) => arr.hash(state),
             OrtValue::Object(obj) => {
-                write!(f, "{{")?;
-                for (i, (k, v)) in obj.iter().enumerate() {
-                    if i > 0 {
-                        write!(f, ", ")?;
-                    }
-                    write!(f, "\"{}\": {}", k, v)?;
-                }
-                write!(f, "}}")
+                let combined = obj.iter().fold(0u64, |acc, entry| {
+                    let mut hasher = DefaultHasher::new();
+                    entry.hash(&mut hasher);
+                    acc.wrapping_add(hasher.finish())
+                });
+                obj.len().hash(state);
+                combined.hash(state);
             }
         }
     }
 }
 
+// Display as ORT, the same text `generate_ort` produces
+impl fmt::Display for OrtValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_ort(f, self, &GenerateOptions::default())
+    }
+}
+
 // Convenience constructors
 impl From<bool> for OrtValue {
     fn from(b: bool) -> Self {
@@ -278,6 +1343,171 @@ impl From<Vec<OrtValue>> for OrtValue {
 
 impl From<HashMap<String, OrtValue>> for OrtValue {
     fn from(obj: HashMap<String, OrtValue>) -> Self {
+        OrtValue::Object(obj.into_iter().collect())
+    }
+}
+
+impl From<Map<String, OrtValue>> for OrtValue {
+    fn from(obj: Map<String, OrtValue>) -> Self {
         OrtValue::Object(obj)
     }
 }
+
+/// `None` converts to null and `Some` to its value
+///
+/// # Example
+/// ```
+/// use ort_rs::OrtValue;
+///
+/// let nickname: Option<String> = None;
+/// assert_eq!(OrtValue::from(nickname), OrtValue::Null);
+/// assert_eq!(OrtValue::from(Some(42)), OrtValue::from(42));
+/// assert_eq!(OrtValue::from(Some(OrtValue::from("x"))), OrtValue::from("x"));
+/// ```
+impl<T: Into<OrtValue>> From<Option<T>> for OrtValue {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(OrtValue::Null, Into::into)
+    }
+}
+
+/// Types that `OrtValue::get_as` converts a value to
+///
+/// # Example
+/// ```
+/// use ort_rs::{FromOrtValue, OrtValue};
+///
+/// struct Port(u16);
+///
+/// impl FromOrtValue for Port {
+///     const EXPECTED: &'static str = "port";
+///
+///     fn from_ort_value(value: &OrtValue) -> Option<Self> {
+///         value.as_i64().and_then(|n| u16::try_from(n).ok()).map(Port)
+///     }
+/// }
+///
+/// let config = ort_rs::from_str("port:\n5432").unwrap();
+/// assert_eq!(config.get_as::<Port>("port").unwrap().0, 5432);
+/// ```
+pub trait FromOrtValue: Sized {
+    /// Type name used in the error for a value that doesn't convert
+    const EXPECTED: &'static str;
+
+    /// Convert a value, or `None` if it has another type
+    fn from_ort_value(value: &OrtValue) -> Option<Self>;
+}
+
+impl FromOrtValue for String {
+    const EXPECTED: &'static str = "string";
+
+    fn from_ort_value(value: &OrtValue) -> Option<Self> {
+        value.as_str().map(str::to_string)
+    }
+}
+
+impl FromOrtValue for f64 {
+    const EXPECTED: &'static str = "number";
+
+    fn from_ort_value(value: &OrtValue) -> Option<Self> {
+        value.as_f64()
+    }
+}
+
+// Only whole numbers within range convert, unlike `as_i64`
+impl FromOrtValue for i64 {
+    const EXPECTED: &'static str = "integer";
+
+    fn from_ort_value(value: &OrtValue) -> Option<Self> {
+        match *value {
+            OrtValue::Number(n) if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 => Some(n as i64),
+            _ => None,
+        }
+    }
+}
+
+impl FromOrtValue for bool {
+    const EXPECTED: &'static str = "bool";
+
+    fn from_ort_value(value: &OrtValue) -> Option<Self> {
+        value.as_bool()
+    }
+}
+
+impl FromOrtValue for Vec<OrtValue> {
+    const EXPECTED: &'static str = "array";
+
+    fn from_ort_value(value: &OrtValue) -> Option<Self> {
+        value.as_array().cloned()
+    }
+}
+
+impl FromOrtValue for HashMap<String, OrtValue> {
+    const EXPECTED: &'static str = "object";
+
+    fn from_ort_value(value: &OrtValue) -> Option<Self> {
+        value.as_object().map(|obj| obj.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+    }
+}
+
+/// Error for a value that doesn't convert to the `expected` type
+fn conversion_error(value: &OrtValue, expected: &str) -> OrtError {
+    let message = format!("value has type '{}', expected '{}'", value.type_name(), expected);
+    OrtError::new(0, String::new(), message).with_kind(OrtErrorKind::TypeMismatch)
+}
+
+impl TryFrom<OrtValue> for String {
+    type Error = OrtError;
+
+    fn try_from(value: OrtValue) -> OrtResult<Self> {
+        match value {
+            OrtValue::String(s) => Ok(s),
+            other => Err(conversion_error(&other, String::EXPECTED)),
+        }
+    }
+}
+
+impl TryFrom<OrtValue> for f64 {
+    type Error = OrtError;
+
+    fn try_from(value: OrtValue) -> OrtResult<Self> {
+        f64::from_ort_value(&value).ok_or_else(|| conversion_error(&value, f64::EXPECTED))
+    }
+}
+
+impl TryFrom<OrtValue> for i64 {
+    type Error = OrtError;
+
+    fn try_from(value: OrtValue) -> OrtResult<Self> {
+        i64::from_ort_value(&value).ok_or_else(|| conversion_error(&value, i64::EXPECTED))
+    }
+}
+
+impl TryFrom<OrtValue> for bool {
+    type Error = OrtError;
+
+    fn try_from(value: OrtValue) -> OrtResult<Self> {
+        bool::from_ort_value(&value).ok_or_else(|| conversion_error(&value, bool::EXPECTED))
+    }
+}
+
+impl TryFrom<OrtValue> for Vec<OrtValue> {
+    type Error = OrtError;
+
+    fn try_from(value: OrtValue) -> OrtResult<Self> {
+        match value {
+            OrtValue::Array(arr) => Ok(arr),
+            other => Err(conversion_error(&other, Vec::<OrtValue>::EXPECTED)),
+        }
+    }
+}
+
+impl TryFrom<OrtValue> for HashMap<String, OrtValue> {
+    type Error = OrtError;
+
+    fn try_from(value: OrtValue) -> OrtResult<Self> {
+        match value {
+            OrtValue::Object(obj) => Ok(obj.into_iter().collect()),
+            other => Err(conversion_error(&other, HashMap::<String, OrtValue>::EXPECTED)),
+        }
+    }
+}
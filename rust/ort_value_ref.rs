@@ -0,0 +1,167 @@
+use crate::ort_value::{Map, OrtValue};
+use std::borrow::Cow;
+use std::ops::Index;
+
+/// Borrowed ORT value type produced by `parse_ort_borrowed`
+///
+/// Strings and keys borrow from the parsed input unless they had to be unescaped.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrtValueRef<'a> {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(Cow<'a, str>),
+    Array(Vec<OrtValueRef<'a>>),
+    Object(Map<Cow<'a, str>, OrtValueRef<'a>>),
+}
+
+impl<'a> OrtValueRef<'a> {
+    /// Check if value is null
+    pub fn is_null(&self) -> bool {
+        matches!(self, OrtValueRef::Null)
+    }
+
+    /// Check if value is boolean
+    pub fn is_bool(&self) -> bool {
+        matches!(self, OrtValueRef::Bool(_))
+    }
+
+    /// Check if value is number
+    pub fn is_number(&self) -> bool {
+        matches!(self, OrtValueRef::Number(_))
+    }
+
+    /// Check if value is string
+    pub fn is_string(&self) -> bool {
+        matches!(self, OrtValueRef::String(_))
+    }
+
+    /// Check if value is array
+    pub fn is_array(&self) -> bool {
+        matches!(self, OrtValueRef::Array(_))
+    }
+
+    /// Check if value is object
+    pub fn is_object(&self) -> bool {
+        matches!(self, OrtValueRef::Object(_))
+    }
+
+    /// Get as boolean
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            OrtValueRef::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Get as f64
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            OrtValueRef::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Get as i64
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            OrtValueRef::Number(n) => Some(*n as i64),
+            _ => None,
+        }
+    }
+
+    /// Get as u64
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            OrtValueRef::Number(n) if *n >= 0.0 => Some(*n as u64),
+            _ => None,
+        }
+    }
+
+    /// Get as string reference
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            OrtValueRef::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Get as array reference
+    pub fn as_array(&self) -> Option<&Vec<OrtValueRef<'a>>> {
+        match self {
+            OrtValueRef::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    /// Get as object reference
+    pub fn as_object(&self) -> Option<&Map<Cow<'a, str>, OrtValueRef<'a>>> {
+        match self {
+            OrtValueRef::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    /// Get value by key (for objects)
+    pub fn get(&self, key: &str) -> Option<&OrtValueRef<'a>> {
+        match self {
+            OrtValueRef::Object(obj) => obj.get(key),
+            _ => None,
+        }
+    }
+
+    /// Convert into an owned OrtValue
+    pub fn to_owned(&self) -> OrtValue {
+        self.clone().into_owned()
+    }
+
+    /// Convert into an owned OrtValue, reusing already owned strings
+    pub fn into_owned(self) -> OrtValue {
+        match self {
+            OrtValueRef::Null => OrtValue::Null,
+            OrtValueRef::Bool(b) => OrtValue::Bool(b),
+            OrtValueRef::Number(n) => OrtValue::Number(n),
+            OrtValueRef::String(s) => OrtValue::String(s.into_owned()),
+            OrtValueRef::Array(arr) => {
+                OrtValue::Array(arr.into_iter().map(OrtValueRef::into_owned).collect())
+            }
+            OrtValueRef::Object(obj) => OrtValue::Object(
+                obj.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into_owned()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+// Implement Index for string keys (object access)
+impl<'a> Index<&str> for OrtValueRef<'a> {
+    type Output = OrtValueRef<'a>;
+
+    fn index(&self, key: &str) -> &Self::Output {
+        static NULL: OrtValueRef<'static> = OrtValueRef::Null;
+        match self {
+            OrtValueRef::Object(obj) => obj.get(key).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+// Implement Index for usize (array access)
+impl<'a> Index<usize> for OrtValueRef<'a> {
+    type Output = OrtValueRef<'a>;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        static NULL: OrtValueRef<'static> = OrtValueRef::Null;
+        match self {
+            OrtValueRef::Array(arr) => arr.get(index).unwrap_or(&NULL),
+            _ => &NULL,
+        }
+    }
+}
+
+impl<'a> From<OrtValueRef<'a>> for OrtValue {
+    fn from(value: OrtValueRef<'a>) -> Self {
+        value.into_owned()
+    }
+}
@@ -1,41 +1,747 @@
-use crate::error::{OrtError, OrtResult};
-use crate::ort_value::OrtValue;
-use std::collections::HashMap;
-
+use crate::error::{OrtError, OrtErrorKind, OrtResult, OrtWarning, ParseErrors, WarningKind};
+use crate::escape::{is_valid_delimiter, unescape, unescape_quoted};
+use crate::fields::{FieldSpec, FieldType};
+use crate::ort_value::{remove_key, Map, OrtValue};
+use crate::ort_value_ref::OrtValueRef;
+use crate::value::{resolve_conflict, ConflictPolicy};
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Parse ORT string into an OrtValue
+///
+/// A value in double quotes is always a string, and delimiters, parentheses
+/// and brackets inside the quotes are plain text. A quote that is never
+/// closed is an error.
+///
+/// Numbers may use `_` between digits, a leading `+`, and the `0x`, `0o` and
+/// `0b` prefixes for integers, in either case. Anything else with an `_` is a
+/// string, as is a number too large for `f64`, such as `1e400`.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::OrtErrorKind;
+///
+/// let value = ort_rs::parse_ort("users:id,name,tags:\n1,\"Doe, John\",[\"a]\",\"42\"]\n2,Jane,(note:\"x,y\")")?;
+/// assert_eq!(value["users"][0]["name"].as_str(), Some("Doe, John"));
+/// assert_eq!(value["users"][0]["tags"][0].as_str(), Some("a]"));
+/// assert_eq!(value["users"][0]["tags"][1].as_str(), Some("42"));
+/// assert_eq!(value["users"][1]["tags"]["note"].as_str(), Some("x,y"));
+///
+/// let value = ort_rs::parse_ort("row:a,b:\n\"abc\",123")?;
+/// assert_eq!(value["row"][0]["a"].as_str(), Some("abc"));
+/// assert_eq!(value["row"][0]["b"].as_f64(), Some(123.0));
+///
+/// let error = ort_rs::parse_ort("row:a,b:\n1,\"open, never \\\"closed").unwrap_err();
+/// assert_eq!(error.kind, OrtErrorKind::UnterminatedQuote);
+/// assert_eq!((error.line, error.span.as_deref()), (2, Some(&(2, 23))));
+///
+/// let accepted = [
+///     ("1_000_000", 1e6), ("0x10", 16.0), ("0xDEAD_BEEF", 3735928559.0), ("-0o17", -15.0),
+///     ("0b1010", 10.0), ("+42", 42.0), ("+1.5e3", 1500.0), ("1_000.25", 1000.25), ("2E-2", 0.02),
+///     ("0XFF", 255.0), ("-0B11", -3.0), ("0O7", 7.0),
+/// ];
+/// for (token, n) in accepted {
+///     assert_eq!(ort_rs::parse_ort(&format!("n:\n{}", token))?["n"].as_f64(), Some(n), "{}", token);
+/// }
+/// let rejected = ["_foo_", "_1", "1_", "1__0", "1_.5", "0x", "0x_1", "0xG", "0b2", "0o8", "++1", "1e", "1_e5", "1e400", "-1e400"];
+/// for token in rejected {
+///     assert_eq!(ort_rs::parse_ort(&format!("n:\n{}", token))?["n"].as_str(), Some(token), "{}", token);
+/// }
+/// # Ok(())
+/// # }
+/// ```
 pub fn parse_ort(content: &str) -> OrtResult<OrtValue> {
-    let lines: Vec<&str> = content.lines().collect();
+    parse_document(content, &mut Context::default())
+}
+
+/// Options for `parse_ort_with`
+///
+/// Options are built from the defaults, which `parse_ort` uses, by chaining
+/// the setters. New options may be added, so the struct cannot be written out
+/// field by field outside this crate.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::ParseOptions;
+///
+/// let options = ParseOptions::new().comment_char(';').multi_line_comment(true);
+/// let content = "; exported nightly\n/* columns:\n   id, name */\nusers:id,name:\n1,John\n#2,Jane";
+/// let value = ort_rs::parse_ort_with(content, &options)?;
+/// assert_eq!(value["users"][1]["id"].as_str(), Some("#2"));
+///
+/// let error = ort_rs::parse_ort_with("/* never closed\nusers:id:\n1", &options).unwrap_err();
+/// assert_eq!((error.line, error.message.as_str()), (1, "Unterminated comment"));
+/// assert_eq!(error.kind, ort_rs::OrtErrorKind::UnterminatedComment);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// A repeated section is an error unless `duplicate_sections` resolves it:
+///
+/// ```
+/// use ort_rs::{ConflictPolicy, OrtValue, ParseOptions};
+///
+/// let content = "users:id:\n1\n\nitems:id:\n9\n\nusers:id:\n2";
+/// let error = ort_rs::parse_ort(content).unwrap_err();
+/// assert_eq!(error.message, "Duplicate section 'users', first defined at line 1");
+/// assert_eq!((error.line, error.span.as_deref()), (7, Some(&(0, 5))));
+///
+/// let ids = |policy| {
+///     let options = ParseOptions::new().duplicate_sections(policy);
+///     let value = ort_rs::parse_ort_with(content, &options).unwrap();
+///     value["users"].as_array().unwrap().iter().map(|row| row["id"].as_i64().unwrap()).collect::<Vec<_>>()
+/// };
+/// assert_eq!(ids(ConflictPolicy::FirstWins), [1]);
+/// assert_eq!(ids(ConflictPolicy::LastWins), [2]);
+/// assert_eq!(ids(ConflictPolicy::Concatenate), [1, 2]);
+///
+/// let options = ParseOptions::new().duplicate_sections(ConflictPolicy::Concatenate);
+/// let error = ort_rs::parse_ort_with("name:\nJohn\n\nname:\nJane", &options).unwrap_err();
+/// assert_eq!(error.message, "Cannot concatenate section 'name' with the one at line 1, as only tables can be concatenated");
+///
+/// // Repeated inline object keys keep the last value unless `duplicate_keys` says otherwise
+/// assert_eq!(ort_rs::parse_ort("meta:\n(a:1,a:2)").unwrap()["meta"]["a"].as_i64(), Some(2));
+/// let options = ParseOptions::new().duplicate_keys(ConflictPolicy::Error);
+/// let error = ort_rs::parse_ort_with("meta:\n(a:1,a:2)", &options).unwrap_err();
+/// assert_eq!((error.message.as_str(), error.column), ("Key 'a' is repeated in the inline object", Some(6)));
+/// ```
+///
+/// Nesting is limited to `max_depth` levels, and `max_line_length` and
+/// `max_rows` bound the input, so untrusted files can be parsed safely:
+///
+/// ```
+/// use ort_rs::{OrtErrorKind, ParseOptions};
+///
+/// // Deeply nested input is an error, not a stack overflow
+/// for depth in [129, 50_000] {
+///     let inputs = [
+///         format!("a:\n{}1{}", "[".repeat(depth), "]".repeat(depth)),
+///         format!("a:\n{}{}", "(k:".repeat(depth), ")".repeat(depth)),
+///         format!("a:x:\n{}{}", "[".repeat(depth), "]".repeat(depth)),
+///         format!("a:{}x{}:\n1", "x(".repeat(depth), ")".repeat(depth)),
+///         format!("a:x {}int{}:\n1", "[".repeat(depth), "]".repeat(depth)),
+///         format!("a:x(y):\n({}{})", "[".repeat(depth), "]".repeat(depth)),
+///     ];
+///     for input in &inputs {
+///         assert_eq!(ort_rs::parse_ort(input).unwrap_err().kind, OrtErrorKind::DepthLimit);
+///         assert_eq!(ort_rs::parse_ort_borrowed(input).unwrap_err().kind, OrtErrorKind::DepthLimit);
+///         assert_eq!(ort_rs::parse_ort_all(input).1[0].kind, OrtErrorKind::DepthLimit);
+///     }
+/// }
+/// assert!(ort_rs::parse_ort(&format!("a:\n{}1{}", "[".repeat(128), "]".repeat(128))).is_ok());
+///
+/// let options = ParseOptions::new().max_depth(2);
+/// assert!(ort_rs::parse_ort_with("a:\n[(k:1)]", &options).is_ok());
+/// let error = ort_rs::parse_ort_with("a:\n[[[1]]]", &options).unwrap_err();
+/// assert_eq!((error.message.as_str(), error.span.as_deref()), ("Nesting is deeper than the limit of 2 levels", Some(&(2, 3))));
+///
+/// let options = ParseOptions::new().max_rows(2).max_line_length(16);
+/// let error = ort_rs::parse_ort_with("a:id:\n1\n2\n\nb:id:\n3", &options).unwrap_err();
+/// assert_eq!((error.kind, error.line, error.message.as_str()), (OrtErrorKind::InputLimit, 6, "More than 2 rows"));
+/// let error = ort_rs::parse_ort_with("users:id,name:\n1,John\n2,Jane Doe-Smithson", &options).unwrap_err();
+/// assert_eq!((error.line, error.message.as_str()), (3, "Line is 19 bytes long, over the limit of 16"));
+/// ```
+///
+/// Without type inference every scalar stays the string it was written as,
+/// so nothing is lost on the way through. The defaults are exactly what
+/// `parse_ort` does:
+///
+/// ```
+/// use ort_rs::{OrtValue, ParseOptions};
+///
+/// let content = "users:id,zip,active,tags:\n1,007,true,[1_000,x]\n2,,false,()";
+/// let value = ort_rs::parse_ort_with(content, &ParseOptions::new().infer_types(false)).unwrap();
+/// assert_eq!(value["users"][0]["id"], OrtValue::from("1"));
+/// assert_eq!(value["users"][0]["zip"], OrtValue::from("007"));
+/// assert_eq!(value["users"][0]["active"], OrtValue::from("true"));
+/// assert_eq!(value["users"][0]["tags"][0], OrtValue::from("1_000"));
+/// assert!(value["users"][1]["zip"].is_null());
+///
+/// // Typed columns still read their declared type
+/// let value = ort_rs::parse_ort_with("row:n int,m:\n123,123", &ParseOptions::new().infer_types(false)).unwrap();
+/// assert_eq!((value["row"][0]["n"].as_f64(), value["row"][0]["m"].as_str()), (Some(123.0), Some("123")));
+///
+/// assert_eq!(ParseOptions::new(), ParseOptions::default());
+/// assert_eq!(ort_rs::parse_ort_with(content, &ParseOptions::new()).unwrap(), ort_rs::parse_ort(content).unwrap());
+/// assert_eq!(ort_rs::parse_ort(content).unwrap()["users"][0]["zip"].as_f64(), Some(7.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ParseOptions {
+    /// Lines starting with this character are comments
+    pub comment_char: char,
+    /// Also skip `/* ... */` comments, which start a line and may span several
+    pub multi_line_comment: bool,
+    /// How to treat rows with fewer or more values than their header has fields
+    pub row_arity: RowArity,
+    /// Separates values in rows, arrays and inline objects, and fields in headers
+    pub delimiter: char,
+    /// How a section key defined more than once is resolved
+    pub duplicate_sections: ConflictPolicy,
+    /// How a key repeated in an inline object is resolved
+    pub duplicate_keys: ConflictPolicy,
+    /// Read `#include` and `@include` lines, which are comments otherwise
+    pub allow_includes: bool,
+    /// Directory that include lines are resolved against
+    ///
+    /// Include lines are only read when this is set, as `from_file_with` does
+    /// for the file it parses, and may not lead outside it.
+    pub base_dir: Option<PathBuf>,
+    // Canonical paths of the files being parsed, to detect circular includes
+    pub(crate) include_guard: BTreeSet<PathBuf>,
+    /// Deepest nesting of arrays, inline objects and nested fields allowed
+    pub max_depth: usize,
+    /// Longest line allowed, in bytes
+    pub max_line_length: Option<usize>,
+    /// Most data rows allowed in the whole document
+    pub max_rows: Option<usize>,
+    /// Read bare scalars as numbers and booleans where they look like one,
+    /// rather than keeping every scalar a string
+    pub infer_types: bool,
+}
+
+static DEFAULT_OPTIONS: ParseOptions = ParseOptions {
+    comment_char: '#',
+    multi_line_comment: false,
+    row_arity: RowArity::Strict,
+    delimiter: ',',
+    duplicate_sections: ConflictPolicy::Error,
+    duplicate_keys: ConflictPolicy::LastWins,
+    allow_includes: false,
+    base_dir: None,
+    include_guard: BTreeSet::new(),
+    max_depth: 128,
+    max_line_length: None,
+    max_rows: None,
+    infer_types: true,
+};
+
+/// How rows whose value count differs from the header are treated
+///
+/// In the lenient modes, a lone trailing comma is ignored.
+///
+/// # Example
+/// ```
+/// use ort_rs::{ParseOptions, RowArity};
+///
+/// let content = "users:id,name,age:\n1,John,30,\n2,Jane\n3,Bob,25,extra";
+/// let parse = |row_arity| ort_rs::parse_ort_with(content, &ParseOptions::new().row_arity(row_arity));
+///
+/// assert_eq!(parse(RowArity::Strict).unwrap_err().line, 2);
+///
+/// let error = parse(RowArity::PadMissing).unwrap_err();
+/// assert_eq!((error.line, error.message.as_str()), (4, "Expected 3 values but got 4"));
+///
+/// let error = parse(RowArity::IgnoreExtra).unwrap_err();
+/// assert_eq!((error.line, error.message.as_str()), (3, "Expected 3 values but got 2"));
+///
+/// let options = ParseOptions::new().row_arity(RowArity::PadMissing);
+/// let value = ort_rs::parse_ort_with("users:id,name,age:\n1,John,30,\n2,Jane\n3,Bob,25", &options).unwrap();
+/// assert_eq!(value["users"][0]["age"].as_i64(), Some(30));
+/// assert!(value["users"][1]["age"].is_null());
+///
+/// let options = ParseOptions::new().row_arity(RowArity::IgnoreExtra);
+/// let value = ort_rs::parse_ort_with("users:id,name,age:\n1,John,30,\n3,Bob,25,extra", &options).unwrap();
+/// assert_eq!(value["users"][1]["age"].as_i64(), Some(25));
+/// assert_eq!(value["users"][1].as_object().unwrap().len(), 3);
+///
+/// // Ragged rows allow both
+/// let value = parse(RowArity::Ragged).unwrap();
+/// assert!(value["users"][1]["age"].is_null());
+/// assert_eq!(value["users"][2]["age"].as_i64(), Some(25));
+/// assert_eq!(ParseOptions::new().allow_ragged_rows(true).row_arity, RowArity::Ragged);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RowArity {
+    /// Any difference is an error
+    #[default]
+    Strict,
+    /// Missing trailing values are null; extra values are an error
+    PadMissing,
+    /// Extra trailing values are dropped; missing values are an error
+    IgnoreExtra,
+    /// Missing trailing values are null and extra trailing values are dropped
+    Ragged,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        DEFAULT_OPTIONS.clone()
+    }
+}
+
+impl ParseOptions {
+    /// Create the default options, as used by `parse_ort`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the character that starts a comment line
+    pub fn comment_char(mut self, comment_char: char) -> Self {
+        self.comment_char = comment_char;
+        self
+    }
+
+    /// Also skip `/* ... */` comments
+    pub fn multi_line_comment(mut self, multi_line_comment: bool) -> Self {
+        self.multi_line_comment = multi_line_comment;
+        self
+    }
+
+    /// Set how rows with too few or too many values are treated
+    pub fn row_arity(mut self, row_arity: RowArity) -> Self {
+        self.row_arity = row_arity;
+        self
+    }
+
+    /// Accept rows with missing or extra trailing values, as `RowArity::Ragged`
+    /// does, or require exact rows with `false`
+    pub fn allow_ragged_rows(mut self, allow: bool) -> Self {
+        self.row_arity = if allow { RowArity::Ragged } else { RowArity::Strict };
+        self
+    }
+
+    /// Set the value delimiter; parsing fails unless `escape::is_valid_delimiter` accepts it
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set how a repeated section is resolved
+    pub fn duplicate_sections(mut self, policy: ConflictPolicy) -> Self {
+        self.duplicate_sections = policy;
+        self
+    }
+
+    /// Set how a key repeated in an inline object is resolved
+    pub fn duplicate_keys(mut self, policy: ConflictPolicy) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Read include lines, or leave them as comments with `false`
+    pub fn allow_includes(mut self, allow: bool) -> Self {
+        self.allow_includes = allow;
+        self
+    }
+
+    /// Resolve include lines against this directory
+    pub fn base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(dir.into());
+        self
+    }
+
+    /// Set the deepest nesting allowed
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Set the longest line allowed, in bytes
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = Some(max_line_length);
+        self
+    }
+
+    /// Set the most data rows allowed
+    pub fn max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = Some(max_rows);
+        self
+    }
+
+    /// Turn inference of numbers and booleans on or off
+    pub fn infer_types(mut self, infer_types: bool) -> Self {
+        self.infer_types = infer_types;
+        self
+    }
+
+    /// Parse ORT string with these options, also returning warnings like `parse_ort_with_warnings`
+    pub fn parse_with_warnings(&self, content: &str) -> OrtResult<(OrtValue, Vec<OrtWarning>)> {
+        let mut warnings = vec![];
+        let mut ctx = Context {
+            warnings: Some(&mut warnings),
+            options: self,
+            ..Context::default()
+        };
+        let value = parse_document(content, &mut ctx)?;
+        Ok((value, warnings))
+    }
+
+    /// Parse ORT string with these options, collecting every error like `parse_ort_all`
+    ///
+    /// # Example
+    /// ```
+    /// use ort_rs::ParseOptions;
+    ///
+    /// let content = "users:id,name:\n1,John\n2\n\ntree:a(b(c)):\n(((1)))\n\nitems:id:\n1\n2";
+    /// let (value, errors) = ParseOptions::new().max_depth(2).parse_all(content);
+    ///
+    /// let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+    /// assert_eq!(lines, vec![3, 6]);
+    /// assert_eq!(value["users"].as_array().unwrap().len(), 1);
+    /// assert_eq!(value["items"].as_array().unwrap().len(), 2);
+    /// ```
+    pub fn parse_all(&self, content: &str) -> (OrtValue, Vec<OrtError>) {
+        let mut errors = vec![];
+        let mut ctx = Context {
+            errors: Some(&mut errors),
+            options: self,
+            ..Context::default()
+        };
+        let value = match parse_document(content, &mut ctx) {
+            Ok(value) => value,
+            Err(e) => {
+                errors.push(e);
+                OrtValue::Null
+            }
+        };
+
+        errors.sort_by_key(|e| e.line);
+        errors.dedup_by(|a, b| a.line == b.line && a.message == b.message);
+        (value, errors)
+    }
+
+    /// Parse only the section named `key` with these options, like `parse_section`
+    pub fn parse_section(&self, content: &str, key: &str) -> OrtResult<Option<OrtValue>> {
+        find_section(content.lines().map(Ok), key, self)
+    }
+}
+
+/// Parse ORT string with the given options
+pub fn parse_ort_with(content: &str, options: &ParseOptions) -> OrtResult<OrtValue> {
+    let mut ctx = Context {
+        options,
+        ..Context::default()
+    };
+    parse_document(content, &mut ctx)
+}
+
+/// Parse ORT string, collecting every error instead of stopping at the first
+///
+/// Rows that fail to parse are skipped, and a section with a bad header is
+/// skipped as a whole. Everything else is returned as a partial value along
+/// with the errors, ordered by line.
+///
+/// # Example
+/// ```
+/// let content = "users:id,name:\n1,John\n2,Jane,extra\n\nitems:id),name:\n1,Pen\n\nplaces:id,pos(lat,lng):\n1,(1.5)\n2,(3.5,4.5)";
+/// let (value, errors) = ort_rs::parse_ort_all(content);
+///
+/// let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+/// assert_eq!(lines, vec![3, 5, 9]);
+/// assert_eq!(value["users"].as_array().unwrap().len(), 1);
+/// assert!(value.get("items").is_none());
+/// assert_eq!(value["places"][0]["id"].as_i64(), Some(2));
+/// ```
+pub fn parse_ort_all(content: &str) -> (OrtValue, Vec<OrtError>) {
+    DEFAULT_OPTIONS.parse_all(content)
+}
+
+/// Parse ORT string leniently, like `parse_ort_all`, returning a `ParseErrors`
+///
+/// # Example
+/// ```
+/// let (value, errors) = ort_rs::parse_ort_lenient("users:id,name:\n1,John\n2\n3,Bob,extra");
+/// assert_eq!(value["users"].as_array().unwrap().len(), 1);
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors.to_string(), "\
+/// line 3: Expected 2 values but got 1 — \"2\"
+/// line 4: Expected 2 values but got 3 — \"3,Bob,extra\"");
+/// ```
+pub fn parse_ort_lenient(content: &str) -> (OrtValue, ParseErrors) {
+    let (value, errors) = parse_ort_all(content);
+    (value, errors.into())
+}
+
+/// Parse ORT string, also returning warnings about suspicious input
+///
+/// Warnings are raised for duplicate keys in an inline object, repeated field
+/// names in a header, and integers too large to be stored exactly. The parsed
+/// value is the same as `parse_ort` returns.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::WarningKind;
+///
+/// let (value, warnings) = ort_rs::parse_ort_with_warnings("users:id,meta:\n9007199254740993,(a:1,a:2)")?;
+/// let kinds: Vec<_> = warnings.iter().map(|w| w.kind).collect();
+/// assert_eq!(kinds, vec![WarningKind::PrecisionLoss, WarningKind::DuplicateKey]);
+/// assert_eq!((warnings[1].line, warnings[1].column), (2, 23));
+/// assert_eq!(value["users"][0]["meta"]["a"].as_i64(), Some(2));
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_ort_with_warnings(content: &str) -> OrtResult<(OrtValue, Vec<OrtWarning>)> {
+    DEFAULT_OPTIONS.parse_with_warnings(content)
+}
+
+/// Parse only the section named `key`, or `None` if the document has none
+///
+/// Headers are read the way `parse_ort` reads them, but the data lines of
+/// other sections are skipped without parsing their values, so a small
+/// section of a large document is quick to get. Errors in other sections'
+/// rows are not reported. Included files are parsed as a whole, and a
+/// document with a `:fields:` section has no named sections, as for
+/// `parse_ort`.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{ConflictPolicy, OrtValue, ParseOptions};
+///
+/// let content = "meta:version,rows:\n3,2\n\n## exported nightly\nusers:id,name:\n1,John\n2,Jane,extra\n\nport:\n80";
+/// let meta = ort_rs::parse_section(content, "meta")?.unwrap();
+/// assert_eq!(meta[0]["version"].as_i64(), Some(3));
+/// assert_eq!(ort_rs::parse_section(content, "port")?, Some(OrtValue::from(80)));
+/// assert_eq!(ort_rs::parse_section(content, "missing")?, None);
+///
+/// // The section itself is parsed in full, with lines numbered as in the document
+/// let error = ort_rs::parse_section(content, "users").unwrap_err();
+/// assert_eq!(error.line, 7);
+///
+/// let content = "meta:\n1\n\nusers:id:\n1\n\nitems:id:\n9\n\nusers:id:\n2";
+/// let error = ort_rs::parse_section(content, "users").unwrap_err();
+/// assert_eq!((error.line, error.message.as_str()), (10, "Duplicate section 'users', first defined at line 4"));
+/// let options = ParseOptions::new().duplicate_sections(ConflictPolicy::Concatenate);
+/// let users = options.parse_section(content, "users")?.unwrap();
+/// assert_eq!(users, ort_rs::parse_ort_with(content, &options)?["users"]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_section(content: &str, key: &str) -> OrtResult<Option<OrtValue>> {
+    DEFAULT_OPTIONS.parse_section(content, key)
+}
+
+/// Section of a document as listed by `outline`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionInfo {
+    /// Section key, or `None` for a `:fields:` section
+    pub key: Option<String>,
+    /// Line number of the header
+    pub line: usize,
+    /// Fields declared in the header, with nested fields inside theirs
+    pub fields: Vec<FieldSpec>,
+    /// Number of data lines
+    pub rows: usize,
+}
+
+/// List the sections of a document with their header line, fields and row count
+///
+/// Only headers are parsed; data lines are counted without reading their
+/// values, so this is quick even for large documents. Every section is
+/// listed in the order written, including any after a `:fields:` section.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::FieldSpec;
+///
+/// let content = "## users\nusers:id,name,address(city,geo(lat,lng)):\n1,John,(Seoul,(37.5,127.0))\n\n## totals\ncount:\n1\n\n:id,total:\n1,10\n## 2 not exported yet\n3,30";
+/// let outline = ort_rs::outline(content)?;
+///
+/// let sections: Vec<_> = outline.iter().map(|s| (s.key.as_deref(), s.line, s.rows)).collect();
+/// assert_eq!(sections, vec![(Some("users"), 2, 1), (Some("count"), 6, 1), (None, 9, 2)]);
+/// assert!(outline[1].fields.is_empty());
+/// let FieldSpec::Nested(name, nested) = &outline[0].fields[2] else { panic!() };
+/// assert_eq!(name, "address");
+/// assert_eq!(nested[1], FieldSpec::Nested("geo".to_string(), vec![FieldSpec::Simple("lat".to_string()), FieldSpec::Simple("lng".to_string())]));
+/// # Ok(())
+/// # }
+/// ```
+pub fn outline(content: &str) -> OrtResult<Vec<SectionInfo>> {
+    let options = &DEFAULT_OPTIONS;
+    let mut sections: Vec<SectionInfo> = vec![];
+    for (idx, line) in content.lines().enumerate() {
+        let row = trim_row(line, options.delimiter);
+        if is_blank_or_comment(row, options) {
+            continue;
+        }
+        if !starts_section(row, !sections.is_empty()) {
+            if let Some(section) = sections.last_mut() {
+                section.rows += 1;
+            }
+            continue;
+        }
+
+        let line = line.trim();
+        let line_num = idx + 1;
+        let (key, fields_str) = parse_header(line, line_num)?;
+        let fields = parse_fields(fields_str, line, line_num, options)?;
+        sections.push(SectionInfo {
+            key: key.map(|key| unescape(key, options.delimiter).into_owned()),
+            line: line_num,
+            fields: to_field_specs(&fields, options.delimiter),
+            rows: 0,
+        });
+    }
+    Ok(sections)
+}
+
+/// Where a parse reports errors and warnings
+///
+/// Errors are returned right away unless `errors` collects them. Warnings are
+/// dropped unless `warnings` collects them.
+pub(crate) struct Context<'a> {
+    errors: Option<&'a mut Vec<OrtError>>,
+    warnings: Option<&'a mut Vec<OrtWarning>>,
+    options: &'a ParseOptions,
+    rows: usize,
+}
+
+impl Default for Context<'_> {
+    fn default() -> Self {
+        Self {
+            errors: None,
+            warnings: None,
+            options: &DEFAULT_OPTIONS,
+            rows: 0,
+        }
+    }
+}
+
+impl<'a> Context<'a> {
+    /// Create a context that stops at the first error and ignores warnings
+    pub(crate) fn with_options(options: &'a ParseOptions) -> Self {
+        Self {
+            options,
+            ..Context::default()
+        }
+    }
+
+    /// Record the error and carry on when collecting errors, otherwise fail
+    fn recover<T>(&mut self, result: OrtResult<T>) -> OrtResult<Option<T>> {
+        match (result, &mut self.errors) {
+            (Ok(value), _) => Ok(Some(value)),
+            (Err(e), Some(errors)) => {
+                errors.push(e);
+                Ok(None)
+            }
+            (Err(e), None) => Err(e),
+        }
+    }
+
+    /// Count a data row, failing once there are more than `max_rows`
+    fn count_row(&mut self, line: &str, line_num: usize) -> OrtResult<()> {
+        self.rows += 1;
+        match self.options.max_rows {
+            Some(max) if self.rows > max => Err(OrtError::new(line_num, line.to_string(), format!("More than {} rows", max))
+                .with_kind(OrtErrorKind::InputLimit)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record a warning about `part` of `line`
+    fn warn(&mut self, line: &str, line_num: usize, part: &str, kind: WarningKind, message: String) {
+        if let Some(warnings) = &mut self.warnings {
+            let column = line[..offset_in(line, part)].chars().count() + 1;
+            warnings.push(OrtWarning {
+                line: line_num,
+                column,
+                length: part.chars().count(),
+                kind,
+                message,
+            });
+        }
+    }
+}
+
+fn parse_document(content: &str, ctx: &mut Context) -> OrtResult<OrtValue> {
+    check_delimiter(ctx.options.delimiter)?;
+    let mut lines: Vec<&str> = content.lines().collect();
+    if ctx.options.multi_line_comment {
+        strip_block_comments(&mut lines)?;
+    }
+    if let Some(max) = ctx.options.max_line_length {
+        if let Some(i) = lines.iter().position(|line| line.len() > max) {
+            return Err(OrtError::new(i + 1, String::new(), format!("Line is {} bytes long, over the limit of {}", lines[i].len(), max))
+                .with_kind(OrtErrorKind::InputLimit));
+        }
+    }
     let mut line_idx = 0;
 
-    let mut result = HashMap::new();
+    let mut result = Map::new();
+    let mut section_lines = HashMap::new();
 
     while line_idx < lines.len() {
         let line = lines[line_idx].trim();
 
+        if let Some(target) = include_target(line, ctx.options) {
+            let line_num = line_idx + 1;
+            if let Some(sections) = ctx.recover(include(target, line, line_num, ctx.options))? {
+                for (name, values) in sections {
+                    match result.get_mut(&name) {
+                        None => {
+                            section_lines.insert(name.clone(), line_num);
+                            result.insert(name, values);
+                        }
+                        Some(existing) => {
+                            let policy = ctx.options.duplicate_sections;
+                            if !resolve_conflict(existing, values, policy) {
+                                let error = duplicate_section_error(line, line_num, target, &name, section_lines[&name], policy);
+                                ctx.recover::<()>(Err(error))?;
+                            }
+                        }
+                    }
+                }
+            }
+            line_idx += 1;
+            continue;
+        }
+
         // Skip empty lines and comments
-        if line.is_empty() || line.starts_with('#') {
+        if is_blank_or_comment(line, ctx.options) {
             line_idx += 1;
             continue;
         }
 
         // Parse header
         if line.contains(':') {
-            let (key, fields, data_lines) = parse_section(&lines, line_idx)?;
+            let (key, fields, data_lines) = match ctx.recover(section_at(&lines, line_idx, ctx.options))? {
+                Some(section) => section,
+                None => {
+                    // A bad header skips its whole section
+                    line_idx += count_data_lines(&lines, line_idx, ctx.options) + 1;
+                    continue;
+                }
+            };
+            warn_duplicate_fields(&fields, line, line_idx + 1, ctx);
+            let fields = to_field_specs(&fields, ctx.options.delimiter);
 
             if let Some(key) = key {
                 // keyName:fields: format
-                let values = parse_data_lines(&lines, line_idx + 1, &fields, data_lines)?;
-                result.insert(key, values);
+                let values = parse_data_lines(&lines, line_idx + 1, &fields, data_lines, ctx)?;
+                let line_num = line_idx + 1;
+                let name = unescape(key, ctx.options.delimiter).into_owned();
+                match result.get_mut(&name) {
+                    None => {
+                        section_lines.insert(name.clone(), line_num);
+                        result.insert(name, values);
+                    }
+                    Some(existing) => {
+                        let policy = ctx.options.duplicate_sections;
+                        if !resolve_conflict(existing, values, policy) {
+                            let error = duplicate_section_error(line, line_num, key, &name, section_lines[&name], policy);
+                            ctx.recover::<()>(Err(error))?;
+                        }
+                    }
+                }
                 line_idx += data_lines + 1;
             } else {
                 // :fields: format (top-level)
-                let values = parse_data_lines(&lines, line_idx + 1, &fields, data_lines)?;
+                let values = parse_data_lines(&lines, line_idx + 1, &fields, data_lines, ctx)?;
 
                 // If single object, return as object
                 if !fields.is_empty() && data_lines == 1 {
-                    if let OrtValue::Array(ref arr) = values {
+                    if let OrtValue::Array(mut arr) = values {
                         if arr.len() == 1 {
-                            return Ok(arr[0].clone());
+                            return Ok(arr.remove(0));
                         }
+                        return Ok(OrtValue::Array(arr));
                     }
                 }
                 return Ok(values);
@@ -48,15 +754,339 @@ pub fn parse_ort(content: &str) -> OrtResult<OrtValue> {
     Ok(OrtValue::Object(result))
 }
 
-fn parse_section(lines: &[&str], start_idx: usize) -> OrtResult<(Option<String>, Vec<Field>, usize)> {
+/// Scan `lines` for the sections named `key`, parsing only their headers and rows
+///
+/// Every line is still checked against `max_line_length` and every row is
+/// counted for `max_rows`, so the limits hold as they do for `parse_ort`.
+pub(crate) fn find_section<L: AsRef<str>>(
+    lines: impl Iterator<Item = OrtResult<L>>,
+    key: &str,
+    options: &ParseOptions,
+) -> OrtResult<Option<OrtValue>> {
+    check_delimiter(options.delimiter)?;
+    // The section found so far, with the line it was first defined at
+    let mut found: Option<(usize, OrtValue)> = None;
+    // Lines of the matching section being read, from its header on, with
+    // the byte offset of the text left after block comments
+    let mut current: Option<(usize, Vec<(L, usize)>)> = None;
+    let mut in_section = false;
+    let mut in_comment = false;
+    let mut rows = 0;
+
+    for (idx, line) in lines.enumerate() {
+        let line = line?;
+        let line_num = idx + 1;
+        let full = line.as_ref();
+        if let Some(max) = options.max_line_length.filter(|&max| full.len() > max) {
+            return Err(OrtError::new(line_num, String::new(), format!("Line is {} bytes long, over the limit of {}", full.len(), max))
+                .with_kind(OrtErrorKind::InputLimit));
+        }
+        let text = if options.multi_line_comment { uncommented(full, &mut in_comment) } else { full };
+        let offset = full.len() - text.len();
+        let trimmed = text.trim();
+
+        if let Some(target) = include_target(trimmed, options) {
+            if let Some(values) = remove_key(&mut include(target, trimmed, line_num, options)?, key) {
+                add_section(&mut found, values, trimmed, line_num, target, key, options)?;
+            }
+            continue;
+        }
+
+        let row = trim_row(text, options.delimiter);
+        if is_blank_or_comment(row, options) {
+            if let Some((_, section)) = &mut current {
+                section.push((line, offset));
+            }
+            continue;
+        }
+
+        if !starts_section(row, in_section) {
+            if in_section {
+                rows += 1;
+                if let Some(max) = options.max_rows.filter(|&max| rows > max) {
+                    return Err(OrtError::new(line_num, row.to_string(), format!("More than {} rows", max))
+                        .with_kind(OrtErrorKind::InputLimit));
+                }
+            }
+            if let Some((_, section)) = &mut current {
+                section.push((line, offset));
+            }
+            continue;
+        }
+
+        if let Some((start, section)) = current.take() {
+            let (values, name) = read_found_section(start, &section, options)?;
+            let header = section[0].0.as_ref()[section[0].1..].trim();
+            add_section(&mut found, values, header, start + 1, name, key, options)?;
+            if options.duplicate_sections == ConflictPolicy::FirstWins {
+                break;
+            }
+        }
+
+        in_section = true;
+        let (name, _) = parse_header(trimmed, line_num)?;
+        let Some(name) = name else {
+            // A `:fields:` section is the whole document
+            return Ok(None);
+        };
+        if unescape(name, options.delimiter) == key {
+            current = Some((idx, vec![(line, offset)]));
+        }
+    }
+
+    if let Some((start, section)) = current {
+        let (values, name) = read_found_section(start, &section, options)?;
+        let header = section[0].0.as_ref()[section[0].1..].trim();
+        add_section(&mut found, values, header, start + 1, name, key, options)?;
+    }
+    Ok(found.map(|(_, values)| values))
+}
+
+/// Check if a trimmed, non-comment line is a header
+///
+/// Before the first section any line with a colon starts one, as in `parse_ort`.
+pub(crate) fn starts_section(row: &str, in_section: bool) -> bool {
+    row.contains(':') && (!in_section || is_header(row))
+}
+
+/// Parse the lines of a section that starts at line index `start`, giving its
+/// values and its key as written
+fn read_found_section<'a, L: AsRef<str>>(start: usize, section: &'a [(L, usize)], options: &ParseOptions) -> OrtResult<(OrtValue, &'a str)> {
+    let lines: Vec<&str> = section.iter().map(|(line, offset)| &line.as_ref()[*offset..]).collect();
+    let mut ctx = Context::with_options(options);
+    let parsed = section_at(&lines, 0, options).and_then(|(name, fields, data_lines)| {
+        let fields = to_field_specs(&fields, options.delimiter);
+        let values = parse_data_lines(&lines, 1, &fields, data_lines, &mut ctx)?;
+        Ok((values, name.unwrap_or_default()))
+    });
+    parsed.map_err(|mut e| {
+        e.line += start;
+        e
+    })
+}
+
+/// Keep the values of a section named `key`, resolving a repeat of it by `duplicate_sections`
+fn add_section(
+    found: &mut Option<(usize, OrtValue)>,
+    values: OrtValue,
+    line: &str,
+    line_num: usize,
+    name: &str,
+    key: &str,
+    options: &ParseOptions,
+) -> OrtResult<()> {
+    match found {
+        None => *found = Some((line_num, values)),
+        Some((first_line, existing)) => {
+            let policy = options.duplicate_sections;
+            if !resolve_conflict(existing, values, policy) {
+                return Err(duplicate_section_error(line, line_num, name, key, *first_line, policy));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Get the path of an `#include <path>` or `@include path` line, when includes are read
+fn include_target<'a>(line: &'a str, options: &ParseOptions) -> Option<&'a str> {
+    if !options.allow_includes {
+        return None;
+    }
+    options.base_dir.as_ref()?;
+    let rest = line.strip_prefix("#include").or_else(|| line.strip_prefix("@include"))?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+
+    let target = rest.trim();
+    let target = target
+        .strip_prefix('<').and_then(|t| t.strip_suffix('>'))
+        .or_else(|| target.strip_prefix('"').and_then(|t| t.strip_suffix('"')))
+        .unwrap_or(target);
+    (!target.is_empty()).then_some(target)
+}
+
+/// Parse an included file into its sections
+fn include(target: &str, line: &str, line_num: usize, options: &ParseOptions) -> OrtResult<Map<String, OrtValue>> {
+    let base_dir = options.base_dir.clone().unwrap_or_default();
+    let path = base_dir.join(target);
+    let start = offset_in(line, target);
+
+    if is_outside(&path, &base_dir) {
+        return Err(OrtError::new(line_num, line.to_string(), format!("Include '{}' is outside the directory of the including file", target))
+            .with_kind(OrtErrorKind::IncludeOutsideDirectory)
+            .with_span(start, start + target.len()));
+    }
+
+    let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+    if options.include_guard.contains(&canonical) {
+        return Err(OrtError::new(line_num, line.to_string(), format!("Circular include of '{}'", target))
+            .with_kind(OrtErrorKind::CircularInclude)
+            .with_span(start, start + target.len()));
+    }
+
+    match crate::value::from_file_with(&path, options)? {
+        OrtValue::Object(sections) => Ok(sections),
+        _ => Err(OrtError::new(line_num, line.to_string(), format!("Included file '{}' has no named sections", target))
+            .with_span(start, start + target.len())),
+    }
+}
+
+/// Fail if a delimiter from the options can't separate values
+pub(crate) fn check_delimiter(delimiter: char) -> OrtResult<()> {
+    match is_valid_delimiter(delimiter) {
+        true => Ok(()),
+        false => Err(OrtError::new(0, String::new(), format!("Invalid delimiter {:?}", delimiter))),
+    }
+}
+
+/// Check if `path` lies outside `dir`, leaving files that don't exist for reading to report
+fn is_outside(path: &Path, dir: &Path) -> bool {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    match (fs::canonicalize(path), fs::canonicalize(dir)) {
+        (Ok(path), Ok(dir)) => !path.starts_with(dir),
+        _ => false,
+    }
+}
+
+fn duplicate_section_error(line: &str, line_num: usize, key: &str, name: &str, first_line: usize, policy: ConflictPolicy) -> OrtError {
+    let message = if policy == ConflictPolicy::Concatenate {
+        format!("Cannot concatenate section '{}' with the one at line {}, as only tables can be concatenated", name, first_line)
+    } else {
+        format!("Duplicate section '{}', first defined at line {}", name, first_line)
+    };
+    let start = offset_in(line, key);
+    OrtError::new(line_num, line.to_string(), message)
+        .with_kind(OrtErrorKind::DuplicateKey)
+        .with_span(start, start + key.len())
+}
+
+/// Blank out `/* ... */` comments, keeping any text after the closing `*/`
+pub(crate) fn strip_block_comments(lines: &mut [&str]) -> OrtResult<()> {
+    let mut start = 0;
+    let mut in_comment = false;
+    for (i, line) in lines.iter_mut().enumerate() {
+        if !in_comment {
+            start = i;
+        }
+        *line = uncommented(line, &mut in_comment);
+    }
+
+    match in_comment {
+        true => Err(OrtError::new(start + 1, String::new(), "Unterminated comment".to_string()).with_kind(OrtErrorKind::UnterminatedComment)),
+        false => Ok(()),
+    }
+}
+
+/// Get the end of `line` left after block comments, where `in_comment` says
+/// whether one is open, giving an empty end when the whole line is comment
+fn uncommented<'a>(line: &'a str, in_comment: &mut bool) -> &'a str {
+    let rest = match *in_comment {
+        true => line,
+        false => match line.trim_start().strip_prefix("/*") {
+            Some(rest) => rest,
+            None => return line,
+        },
+    };
+
+    match rest.find("*/") {
+        Some(end) => {
+            *in_comment = false;
+            &rest[end + 2..]
+        }
+        None => {
+            *in_comment = true;
+            &line[line.len()..]
+        }
+    }
+}
+
+/// Parse ORT string into an OrtValueRef that borrows from `content`
+///
+/// Strings and keys only allocate when unescaping is needed.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::parse_ort_borrowed("users:id,name:\n1,John\n2,Jane")?;
+/// assert_eq!(value["users"][1]["name"].as_str(), Some("Jane"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn parse_ort_borrowed(content: &str) -> OrtResult<OrtValueRef<'_>> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut line_idx = 0;
+
+    let mut result = Map::new();
+    let mut section_lines = HashMap::new();
+
+    while line_idx < lines.len() {
+        let line = lines[line_idx].trim();
+
+        // Skip empty lines and comments
+        if is_blank_or_comment(line, &DEFAULT_OPTIONS) {
+            line_idx += 1;
+            continue;
+        }
+
+        // Parse header
+        if line.contains(':') {
+            let (key, fields, data_lines) = section_at(&lines, line_idx, &DEFAULT_OPTIONS)?;
+            let values = parse_data_lines_borrowed(&lines, line_idx + 1, &fields, data_lines)?;
+
+            if let Some(key) = key {
+                // keyName:fields: format
+                let name = unescape(key, DEFAULT_OPTIONS.delimiter);
+                if let Some(&first_line) = section_lines.get(&name) {
+                    return Err(duplicate_section_error(line, line_idx + 1, key, &name, first_line, ConflictPolicy::Error));
+                }
+                section_lines.insert(name.clone(), line_idx + 1);
+                result.insert(name, values);
+                line_idx += data_lines + 1;
+            } else {
+                // :fields: format (top-level), a single row is returned as object
+                if !fields.is_empty() && data_lines == 1 {
+                    if let OrtValueRef::Array(mut arr) = values {
+                        if arr.len() == 1 {
+                            return Ok(arr.remove(0));
+                        }
+                        return Ok(OrtValueRef::Array(arr));
+                    }
+                }
+                return Ok(values);
+            }
+        } else {
+            line_idx += 1;
+        }
+    }
+
+    Ok(OrtValueRef::Object(result))
+}
+
+/// Parse the header at `start_idx`, counting the data lines that follow it
+pub(crate) fn section_at<'a>(
+    lines: &[&'a str],
+    start_idx: usize,
+    options: &ParseOptions,
+) -> OrtResult<(Option<&'a str>, Vec<Field<'a>>, usize)> {
     let line = lines[start_idx].trim();
     let line_num = start_idx + 1;
 
-    // Count data lines (non-empty, non-comment lines until next header or end)
+    let data_lines = count_data_lines(lines, start_idx, options);
+
+    // Parse header
+    let (key, fields_str) = parse_header(line, line_num)?;
+    let fields = parse_fields(fields_str, line, line_num, options)?;
+
+    Ok((key, fields, data_lines))
+}
+
+/// Count data lines (non-empty, non-comment lines until next header or end)
+fn count_data_lines(lines: &[&str], start_idx: usize, options: &ParseOptions) -> usize {
     let mut data_lines = 0;
-    for i in (start_idx + 1)..lines.len() {
-        let l = lines[i].trim();
-        if l.is_empty() || l.starts_with('#') {
+    for l in &lines[(start_idx + 1)..] {
+        let l = trim_row(l, options.delimiter);
+        if is_blank_or_comment(l, options) {
             continue;
         }
         if l.contains(':') && is_header(l) {
@@ -64,153 +1094,281 @@ fn parse_section(lines: &[&str], start_idx: usize) -> OrtResult<(Option<String>,
         }
         data_lines += 1;
     }
+    data_lines
+}
 
-    // Parse header
-    let (key, fields_str) = parse_header(line, line_num)?;
-    let fields = parse_fields(&fields_str, line, line_num)?;
+fn warn_duplicate_fields(fields: &[Field], line: &str, line_num: usize, ctx: &mut Context) {
+    for (i, field) in fields.iter().enumerate() {
+        if fields[..i].iter().any(|f| f.name() == field.name()) {
+            ctx.warn(line, line_num, field.name(), WarningKind::DuplicateField,
+                format!("Field '{}' is repeated in the header", field.name()));
+        }
+        if let Field::Nested(_, nested) = field {
+            warn_duplicate_fields(nested, line, line_num, ctx);
+        }
+    }
+}
 
-    Ok((key, fields, data_lines))
+/// Check if a trimmed line is empty or a comment, where an escaped `\#` starts data
+pub(crate) fn is_blank_or_comment(line: &str, options: &ParseOptions) -> bool {
+    line.is_empty() || line.starts_with(options.comment_char)
 }
 
-fn is_header(line: &str) -> bool {
+pub(crate) fn is_header(line: &str) -> bool {
     // Check if line looks like a header (ends with : or has : at start)
     let trimmed = line.trim();
     if trimmed.starts_with(':') {
         return true;
     }
 
-    // Check if it's keyName:fields: format
-    let parts: Vec<&str> = trimmed.split(':').collect();
-    if parts.len() >= 2 && parts[parts.len() - 1].is_empty() {
-        return true;
-    }
-
-    false
+    // Check if it's keyName:fields: format, where an escaped \: doesn't count
+    trimmed.ends_with(':') && !is_escaped(trimmed, trimmed.len() - 1)
 }
 
-fn parse_header(line: &str, line_num: usize) -> OrtResult<(Option<String>, String)> {
+/// Split a header into its key and fields (raw, still escaped)
+pub(crate) fn parse_header(line: &str, line_num: usize) -> OrtResult<(Option<&str>, &str)> {
     if line.starts_with(':') {
         // :fields: format
-        let content = line.trim_start_matches(':').trim_end_matches(':');
-        Ok((None, content.to_string()))
+        let content = trim_header_end(line.trim_start_matches(':'));
+        Ok((None, content))
     } else {
         // keyName:fields: format
-        let parts: Vec<&str> = line.splitn(2, ':').collect();
-        if parts.len() < 2 {
-            return Err(OrtError::new(
-                line_num,
-                line.to_string(),
-                "Invalid header format".to_string(),
-            ));
-        }
+        let pos = match find_unescaped(line, ':') {
+            Some(pos) => pos,
+            None => {
+                return Err(OrtError::new(
+                    line_num,
+                    line.to_string(),
+                    "Invalid header format".to_string(),
+                ).with_kind(OrtErrorKind::InvalidHeader));
+            }
+        };
 
-        let key = parts[0].trim().to_string();
-        let fields = parts[1].trim_end_matches(':').trim().to_string();
+        let key = line[..pos].trim();
+        let fields = trim_header_end(&line[pos + 1..]).trim();
 
         Ok((Some(key), fields))
     }
 }
 
+/// Strip the closing colons of a header, keeping escaped ones
+fn trim_header_end(mut s: &str) -> &str {
+    while s.ends_with(':') && !is_escaped(s, s.len() - 1) {
+        s = &s[..s.len() - 1];
+    }
+    s
+}
+
+/// Check if the character at byte `pos` is preceded by an odd number of backslashes
+fn is_escaped(s: &str, pos: usize) -> bool {
+    s[..pos].bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 1
+}
+
+/// Find the first occurrence of `target` that is not escaped
+fn find_unescaped(s: &str, target: char) -> Option<usize> {
+    s.match_indices(target)
+        .map(|(pos, _)| pos)
+        .find(|&pos| !is_escaped(s, pos))
+}
+
+/// Header field borrowing its name from the header line
 #[derive(Debug, Clone)]
-enum Field {
-    Simple(String),
-    Nested(String, Vec<Field>),
+pub(crate) enum Field<'a> {
+    Simple(&'a str),
+    Nested(&'a str, Vec<Field<'a>>),
+    Typed(&'a str, FieldType),
+}
+
+impl<'a> Field<'a> {
+    fn name(&self) -> &'a str {
+        match self {
+            Field::Simple(name) => name,
+            Field::Nested(name, _) => name,
+            Field::Typed(name, _) => name,
+        }
+    }
 }
 
-fn parse_fields(fields_str: &str, line: &str, line_num: usize) -> OrtResult<Vec<Field>> {
+/// Read a field declaration, which may end with a type such as `id int`
+///
+/// A last word that is not a type name, or follows an escaped space, is part
+/// of the field name.
+fn simple_field<'a>(field: &'a str, line: &str, line_num: usize, max_depth: usize) -> OrtResult<Field<'a>> {
+    let space = field.char_indices().rev().find(|&(_, ch)| ch.is_whitespace());
+    if let Some((i, ch)) = space.filter(|&(i, _)| !is_escaped(field, i)) {
+        let (name, ty) = (&field[..i], &field[i + ch.len_utf8()..]);
+        let levels = ty.bytes().take_while(|&b| b == b'[').count();
+        if levels > 0 && FieldType::parse(ty.trim_start_matches('[').trim_end_matches(']')).is_some() {
+            check_depth(levels - 1, max_depth, ty, line, line_num)?;
+        }
+        if let Some(ty) = FieldType::parse(ty) {
+            return Ok(Field::Typed(name.trim_end(), ty));
+        }
+    }
+    Ok(Field::Simple(field))
+}
+
+pub(crate) fn parse_fields<'a>(fields_str: &'a str, line: &str, line_num: usize, options: &ParseOptions) -> OrtResult<Vec<Field<'a>>> {
+    parse_fields_at(fields_str, line, line_num, options, 0)
+}
+
+fn parse_fields_at<'a>(fields_str: &'a str, line: &str, line_num: usize, options: &ParseOptions, depth: usize) -> OrtResult<Vec<Field<'a>>> {
     if fields_str.is_empty() {
         return Ok(vec![]);
     }
 
-    let mut result = vec![];
-    let mut current = String::new();
-    let mut depth = 0;
-    let chars: Vec<char> = fields_str.chars().collect();
-    let mut i = 0;
+    let delimiter = options.delimiter;
 
-    while i < chars.len() {
-        let ch = chars[i];
+    let mut result = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    let mut chars = fields_str.char_indices();
 
+    while let Some((i, ch)) = chars.next() {
+        if std::mem::take(&mut escaped) {
+            continue;
+        }
         match ch {
+            '\\' => escaped = true,
             '(' => {
-                if depth == 0 {
-                    // Start of nested fields
-                    let field_name = current.trim().to_string();
-                    current.clear();
-                    i += 1;
-
-                    // Find matching closing paren
-                    let mut nested_str = String::new();
-                    let mut nested_depth = 1;
-
-                    while i < chars.len() && nested_depth > 0 {
-                        match chars[i] {
-                            '(' => nested_depth += 1,
-                            ')' => nested_depth -= 1,
-                            _ => {}
-                        }
+                // Start of nested fields
+                let field_name = fields_str[start..i].trim();
 
-                        if nested_depth > 0 {
-                            nested_str.push(chars[i]);
-                        }
-                        i += 1;
+                // Find matching closing paren
+                let nested_start = i + 1;
+                let mut nested_end = fields_str.len();
+                let mut nested_depth = 1;
+
+                for (j, ch) in chars.by_ref() {
+                    if std::mem::take(&mut escaped) {
+                        continue;
+                    }
+                    match ch {
+                        '\\' => escaped = true,
+                        '(' => nested_depth += 1,
+                        ')' => nested_depth -= 1,
+                        _ => {}
                     }
 
-                    let nested_fields = parse_fields(&nested_str, line, line_num)?;
-                    result.push(Field::Nested(field_name, nested_fields));
-                    continue;
-                } else {
-                    depth += 1;
-                    current.push(ch);
+                    if nested_depth == 0 {
+                        nested_end = j;
+                        break;
+                    }
                 }
+
+                check_depth(depth, options.max_depth, &fields_str[i..], line, line_num)?;
+                let nested_fields = parse_fields_at(&fields_str[nested_start..nested_end], line, line_num, options, depth + 1)?;
+                result.push(Field::Nested(field_name, nested_fields));
+                start = (nested_end + 1).min(fields_str.len());
             }
             ')' => {
-                depth -= 1;
-                if depth < 0 {
-                    return Err(OrtError::new(
-                        line_num,
-                        line.to_string(),
-                        "Unmatched closing parenthesis".to_string(),
-                    ));
-                }
-                current.push(ch);
+                let offset = offset_in(line, fields_str) + i;
+                return Err(OrtError::new(
+                    line_num,
+                    line.to_string(),
+                    "Unmatched closing parenthesis".to_string(),
+                ).with_kind(OrtErrorKind::UnmatchedParenthesis).with_span(offset, offset + 1));
             }
-            ',' => {
-                if depth == 0 {
-                    let field = current.trim().to_string();
-                    if !field.is_empty() {
-                        result.push(Field::Simple(field));
-                    }
-                    current.clear();
-                } else {
-                    current.push(ch);
+            _ if ch == delimiter => {
+                let field = fields_str[start..i].trim();
+                if !field.is_empty() {
+                    result.push(simple_field(field, line, line_num, options.max_depth.saturating_sub(depth))?);
                 }
+                start = i + ch.len_utf8();
             }
-            _ => current.push(ch),
+            _ => {}
+        }
+    }
+
+    let field = fields_str[start..].trim();
+    if !field.is_empty() {
+        result.push(simple_field(field, line, line_num, options.max_depth.saturating_sub(depth))?);
+    }
+
+    Ok(result)
+}
+
+pub(crate) fn to_field_specs(fields: &[Field], delimiter: char) -> Vec<FieldSpec> {
+    fields
+        .iter()
+        .map(|field| match field {
+            Field::Simple(name) => FieldSpec::Simple(unescape(name, delimiter).into_owned()),
+            Field::Nested(name, nested) => FieldSpec::Nested(unescape(name, delimiter).into_owned(), to_field_specs(nested, delimiter)),
+            Field::Typed(name, ty) => FieldSpec::Typed(unescape(name, delimiter).into_owned(), ty.clone()),
+        })
+        .collect()
+}
+
+fn parse_data_lines(
+    lines: &[&str],
+    start_idx: usize,
+    fields: &[FieldSpec],
+    count: usize,
+    ctx: &mut Context,
+) -> OrtResult<OrtValue> {
+    let mut result = vec![];
+    let mut processed = 0;
+
+    for (i, line) in lines.iter().enumerate().skip(start_idx) {
+        if processed >= count {
+            break;
+        }
+
+        let line = trim_row(line, ctx.options.delimiter);
+        if is_blank_or_comment(line, ctx.options) {
+            continue;
+        }
+
+        let line_num = i + 1;
+
+        ctx.count_row(line, line_num)?;
+
+        // Special case: array value without fields
+        if fields.is_empty() {
+            let value = parse_value(line, line, line_num, 0, ctx)?;
+            return Ok(value);
         }
 
-        i += 1;
+        let row = parse_row(line, line_num, fields, ctx);
+        if let Some(row) = ctx.recover(row)? {
+            result.push(row);
+        }
+        processed += 1;
     }
 
-    let field = current.trim().to_string();
-    if !field.is_empty() {
-        result.push(Field::Simple(field));
+    Ok(OrtValue::Array(result))
+}
+
+/// Parse a data line into an object keyed by the header fields
+pub(crate) fn parse_row(line: &str, line_num: usize, fields: &[FieldSpec], ctx: &mut Context) -> OrtResult<OrtValue> {
+    // Parse data values
+    let values = parse_data_values(line, fields.len(), line_num, ctx.options)?;
+
+    let mut obj = Map::new();
+    for (field, value_str) in fields.iter().zip(values) {
+        let value = parse_field_value(field, value_str, line, line_num, 0, ctx)?;
+        obj.insert(field.name().to_string(), value);
     }
 
-    Ok(result)
+    Ok(OrtValue::Object(obj))
 }
 
-fn parse_data_lines(lines: &[&str], start_idx: usize, fields: &[Field], count: usize) -> OrtResult<OrtValue> {
+fn parse_data_lines_borrowed<'a>(
+    lines: &[&'a str],
+    start_idx: usize,
+    fields: &[Field<'a>],
+    count: usize,
+) -> OrtResult<OrtValueRef<'a>> {
     let mut result = vec![];
     let mut processed = 0;
 
-    for i in start_idx..lines.len() {
+    for (i, line) in lines.iter().enumerate().skip(start_idx) {
         if processed >= count {
             break;
         }
 
-        let line = lines[i].trim();
-        if line.is_empty() || line.starts_with('#') {
+        let line = line.trim();
+        if is_blank_or_comment(line, &DEFAULT_OPTIONS) {
             continue;
         }
 
@@ -218,93 +1376,172 @@ fn parse_data_lines(lines: &[&str], start_idx: usize, fields: &[Field], count: u
 
         // Special case: array value without fields
         if fields.is_empty() {
-            let value = parse_value(line, line, line_num)?;
-            return Ok(value);
+            return parse_value_borrowed(line, line, line_num, 0);
         }
 
-        // Parse data values
-        let values = parse_data_values(line, line_num)?;
+        result.push(parse_row_borrowed(line, line_num, fields)?);
+        processed += 1;
+    }
 
-        if values.len() != fields.len() {
-            return Err(OrtError::new(
-                line_num,
-                line.to_string(),
-                format!("Expected {} values but got {}", fields.len(), values.len()),
-            ));
-        }
+    Ok(OrtValueRef::Array(result))
+}
 
-        let mut obj = HashMap::new();
-        for (field, value_str) in fields.iter().zip(values.iter()) {
-            let value = parse_field_value(field, value_str, line, line_num)?;
-            let key = match field {
-                Field::Simple(name) => name.clone(),
-                Field::Nested(name, _) => name.clone(),
-            };
-            obj.insert(key, value);
-        }
+/// Parse a data line into an object borrowing from the line
+pub(crate) fn parse_row_borrowed<'a>(line: &'a str, line_num: usize, fields: &[Field<'a>]) -> OrtResult<OrtValueRef<'a>> {
+    let values = parse_data_values(line, fields.len(), line_num, &DEFAULT_OPTIONS)?;
 
-        result.push(OrtValue::Object(obj));
-        processed += 1;
+    let mut obj = Map::new();
+    for (field, value_str) in fields.iter().zip(values) {
+        let value = parse_field_value_borrowed(field, value_str, line, line_num, 0)?;
+        obj.insert(unescape(field.name(), DEFAULT_OPTIONS.delimiter), value);
     }
 
-    Ok(OrtValue::Array(result))
+    Ok(OrtValueRef::Object(obj))
+}
+
+/// Split a row into exactly `expected` values, as far as `row_arity` allows
+fn parse_data_values<'a>(line: &'a str, expected: usize, line_num: usize, options: &ParseOptions) -> OrtResult<Vec<&'a str>> {
+    let mut values = split_values(line, options.delimiter)
+        .map_err(|rest| unterminated_quote(rest, line, line_num))?;
+    let arity = options.row_arity;
+
+    if arity != RowArity::Strict && values.len() == expected + 1 && values[expected].trim().is_empty() {
+        // A lone trailing comma
+        values.pop();
+    }
+    match arity {
+        RowArity::PadMissing | RowArity::Ragged if values.len() < expected => values.resize(expected, ""),
+        RowArity::IgnoreExtra | RowArity::Ragged if values.len() > expected => values.truncate(expected),
+        _ => {}
+    }
+
+    if values.len() != expected {
+        // Point at the extra values, or at the end of the line if values are missing
+        let start = values.get(expected).map_or(line.len(), |extra| offset_in(line, extra));
+        return Err(OrtError::new(
+            line_num,
+            line.to_string(),
+            format!("Expected {} values but got {}", expected, values.len()),
+        ).with_kind(OrtErrorKind::ValueCountMismatch).with_span(start, line.len()));
+    }
+
+    Ok(values)
 }
 
-fn parse_data_values(line: &str, _line_num: usize) -> OrtResult<Vec<String>> {
+/// Split on delimiters that are not escaped, quoted or enclosed in parentheses/brackets
+///
+/// A value is quoted when it starts with `"`, and the quote runs to the next
+/// unescaped `"`. A quote that is never closed fails with the rest of `s` from it.
+pub(crate) fn split_values(s: &str, delimiter: char) -> Result<Vec<&str>, &str> {
     let mut values = vec![];
-    let mut current = String::new();
+    let mut start = 0;
     let mut escaped = false;
+    let mut quoted = false;
+    let mut quote_start = 0;
+    let mut value_start = true;
     let mut depth = 0;
     let mut bracket_depth = 0;
 
-    for ch in line.chars() {
+    for (i, ch) in s.char_indices() {
         if escaped {
-            current.push(ch);
             escaped = false;
             continue;
         }
 
+        if quoted {
+            match ch {
+                '\\' => escaped = true,
+                '"' => quoted = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        if ch.is_whitespace() && ch != delimiter {
+            continue;
+        }
+        let at_value_start = std::mem::replace(&mut value_start, false);
+
         match ch {
-            '\\' => {
-                escaped = true;
-                current.push('\\');
+            '\\' => escaped = true,
+            '"' if at_value_start => {
+                quoted = true;
+                quote_start = i;
             }
             '(' => {
                 depth += 1;
-                current.push(ch);
-            }
-            ')' => {
-                depth -= 1;
-                current.push(ch);
+                value_start = true;
             }
+            ')' => depth -= 1,
             '[' => {
                 bracket_depth += 1;
-                current.push(ch);
-            }
-            ']' => {
-                bracket_depth -= 1;
-                current.push(ch);
+                value_start = true;
             }
-            ',' => {
+            ']' => bracket_depth -= 1,
+            ':' => value_start = true,
+            _ if ch == delimiter => {
+                value_start = true;
                 if depth == 0 && bracket_depth == 0 {
-                    values.push(current.clone());
-                    current.clear();
-                } else {
-                    current.push(ch);
+                    values.push(&s[start..i]);
+                    start = i + ch.len_utf8();
                 }
             }
-            _ => current.push(ch),
+            _ => {}
         }
     }
 
-    values.push(current);
+    if quoted {
+        return Err(&s[quote_start..]);
+    }
+    values.push(&s[start..]);
     Ok(values)
 }
 
-fn parse_field_value(field: &Field, value_str: &str, line: &str, line_num: usize) -> OrtResult<OrtValue> {
+/// Error for a quoted string that is never closed, spanning from its opening quote
+pub(crate) fn unterminated_quote(rest: &str, line: &str, line_num: usize) -> OrtError {
+    let start = offset_in(line, rest);
+    OrtError::new(line_num, line.to_string(), "Unterminated quoted string".to_string())
+        .with_kind(OrtErrorKind::UnterminatedQuote)
+        .with_span(start, start + rest.len())
+}
+
+/// Byte offset of `part` within `line`, which it must be a slice of
+pub(crate) fn offset_in(line: &str, part: &str) -> usize {
+    let offset = (part.as_ptr() as usize).wrapping_sub(line.as_ptr() as usize);
+    debug_assert!(offset + part.len() <= line.len(), "part is not a slice of line");
+    offset.min(line.len())
+}
+
+/// Trim a row, keeping a leading or trailing whitespace delimiter such as a tab
+pub(crate) fn trim_row(line: &str, delimiter: char) -> &str {
+    line.trim_matches(|ch: char| ch.is_whitespace() && ch != delimiter)
+}
+
+/// Split list items, dropping a trailing empty item
+fn split_items<'a>(s: &'a str, delimiter: char, line: &str, line_num: usize) -> OrtResult<Vec<&'a str>> {
+    let mut items = split_values(s, delimiter).map_err(|rest| unterminated_quote(rest, line, line_num))?;
+    if items.last().is_some_and(|item| item.trim().is_empty()) {
+        items.pop();
+    }
+    Ok(items)
+}
+
+pub(crate) fn parse_value_line(line: &str, line_num: usize, ctx: &mut Context) -> OrtResult<OrtValue> {
+    parse_value(line, line, line_num, 0, ctx)
+}
+
+fn parse_field_value(
+    field: &FieldSpec,
+    value_str: &str,
+    line: &str,
+    line_num: usize,
+    depth: usize,
+    ctx: &mut Context,
+) -> OrtResult<OrtValue> {
     match field {
-        Field::Simple(_) => parse_value(value_str, line, line_num),
-        Field::Nested(_, nested_fields) => {
+        FieldSpec::Simple(_) => parse_value(value_str, line, line_num, depth, ctx),
+        FieldSpec::Typed(name, ty) => parse_typed_value(name, ty, value_str, line, line_num, ctx.options.delimiter),
+        FieldSpec::Nested(_, nested_fields) => {
             let trimmed = value_str.trim();
 
             // Check for empty value
@@ -314,47 +1551,228 @@ fn parse_field_value(field: &Field, value_str: &str, line: &str, line_num: usize
 
             // Check for empty object
             if trimmed == "()" {
-                return Ok(OrtValue::Object(HashMap::new()));
+                check_depth(depth, ctx.options.max_depth, trimmed, line, line_num)?;
+                return Ok(OrtValue::Object(Map::new()));
             }
 
-            // Handle array value dynamically (when field is defined as nested but value is array)
+            // An array of rows holding the nested values, or any other array
             if trimmed.starts_with('[') && trimmed.ends_with(']') {
-                return parse_value(trimmed, line, line_num);
+                let Some(rows) = nested_rows(trimmed, line, line_num, ctx.options.delimiter)? else {
+                    return parse_value(trimmed, line, line_num, depth, ctx);
+                };
+                check_depth(depth, ctx.options.max_depth, trimmed, line, line_num)?;
+                return rows
+                    .into_iter()
+                    .map(|row| parse_field_value(field, row, line, line_num, depth + 1, ctx))
+                    .collect::<OrtResult<_>>()
+                    .map(OrtValue::Array);
             }
 
             // Parse nested object
             if !trimmed.starts_with('(') || !trimmed.ends_with(')') {
                 // Fallback: parse as regular value if not in expected format
-                return parse_value(trimmed, line, line_num);
+                return parse_value(trimmed, line, line_num, depth, ctx);
             }
 
-            let inner = &trimmed[1..trimmed.len()-1];
-            let values = parse_data_values(inner, line_num)?;
+            check_depth(depth, ctx.options.max_depth, trimmed, line, line_num)?;
+            let values = parse_nested_values(trimmed, nested_fields.len(), line, line_num, ctx.options.delimiter)?;
 
-            if values.len() != nested_fields.len() {
-                return Err(OrtError::new(
-                    line_num,
-                    line.to_string(),
-                    format!("Expected {} nested values but got {}", nested_fields.len(), values.len()),
-                ));
+            let mut obj = Map::new();
+            for (field, value_str) in nested_fields.iter().zip(values) {
+                let value = parse_field_value(field, value_str, line, line_num, depth + 1, ctx)?;
+                obj.insert(field.name().to_string(), value);
             }
 
-            let mut obj = HashMap::new();
-            for (field, value_str) in nested_fields.iter().zip(values.iter()) {
-                let value = parse_field_value(field, value_str, line, line_num)?;
-                let key = match field {
-                    Field::Simple(name) => name.clone(),
-                    Field::Nested(name, _) => name.clone(),
-                };
-                obj.insert(key, value);
+            Ok(OrtValue::Object(obj))
+        }
+    }
+}
+
+fn parse_field_value_borrowed<'a>(
+    field: &Field<'a>,
+    value_str: &'a str,
+    line: &str,
+    line_num: usize,
+    depth: usize,
+) -> OrtResult<OrtValueRef<'a>> {
+    match field {
+        Field::Simple(_) => parse_value_borrowed(value_str, line, line_num, depth),
+        Field::Typed(name, ty) => parse_typed_value_borrowed(name, ty, value_str, line, line_num),
+        Field::Nested(_, nested_fields) => {
+            let trimmed = value_str.trim();
+
+            if trimmed.is_empty() {
+                return Ok(OrtValueRef::Null);
             }
 
-            Ok(OrtValue::Object(obj))
+            if trimmed == "()" {
+                check_depth(depth, DEFAULT_OPTIONS.max_depth, trimmed, line, line_num)?;
+                return Ok(OrtValueRef::Object(Map::new()));
+            }
+
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                if let Some(rows) = nested_rows(trimmed, line, line_num, DEFAULT_OPTIONS.delimiter)? {
+                    check_depth(depth, DEFAULT_OPTIONS.max_depth, trimmed, line, line_num)?;
+                    return rows
+                        .into_iter()
+                        .map(|row| parse_field_value_borrowed(field, row, line, line_num, depth + 1))
+                        .collect::<OrtResult<_>>()
+                        .map(OrtValueRef::Array);
+                }
+            }
+
+            // Other arrays and values not in nested object form are parsed as regular values
+            if !trimmed.starts_with('(') || !trimmed.ends_with(')') {
+                return parse_value_borrowed(trimmed, line, line_num, depth);
+            }
+
+            check_depth(depth, DEFAULT_OPTIONS.max_depth, trimmed, line, line_num)?;
+            let values = parse_nested_values(trimmed, nested_fields.len(), line, line_num, DEFAULT_OPTIONS.delimiter)?;
+
+            let mut obj = Map::new();
+            for (field, value_str) in nested_fields.iter().zip(values) {
+                let value = parse_field_value_borrowed(field, value_str, line, line_num, depth + 1)?;
+                obj.insert(unescape(field.name(), DEFAULT_OPTIONS.delimiter), value);
+            }
+
+            Ok(OrtValueRef::Object(obj))
+        }
+    }
+}
+
+/// Parse a cell under a typed column, checking that it holds the declared type
+fn parse_typed_value(name: &str, ty: &FieldType, value_str: &str, line: &str, line_num: usize, delimiter: char) -> OrtResult<OrtValue> {
+    let trimmed = value_str.trim();
+    if trimmed.is_empty() {
+        return Ok(OrtValue::Null);
+    }
+
+    if let FieldType::Array(item) = ty {
+        return typed_items(name, ty, trimmed, line, line_num, delimiter)?
+            .into_iter()
+            .map(|value_str| parse_typed_value(name, item, value_str, line, line_num, delimiter))
+            .collect::<OrtResult<_>>()
+            .map(OrtValue::Array);
+    }
+    typed_scalar(name, ty, trimmed, line, line_num, delimiter).map(Into::into)
+}
+
+fn parse_typed_value_borrowed<'a>(name: &str, ty: &FieldType, value_str: &'a str, line: &str, line_num: usize) -> OrtResult<OrtValueRef<'a>> {
+    let trimmed = value_str.trim();
+    if trimmed.is_empty() {
+        return Ok(OrtValueRef::Null);
+    }
+
+    let delimiter = DEFAULT_OPTIONS.delimiter;
+    if let FieldType::Array(item) = ty {
+        return typed_items(name, ty, trimmed, line, line_num, delimiter)?
+            .into_iter()
+            .map(|value_str| parse_typed_value_borrowed(name, item, value_str, line, line_num))
+            .collect::<OrtResult<_>>()
+            .map(OrtValueRef::Array);
+    }
+    typed_scalar(name, ty, trimmed, line, line_num, delimiter).map(Into::into)
+}
+
+/// Split the items of a cell under an array column
+fn typed_items<'a>(name: &str, ty: &FieldType, token: &'a str, line: &str, line_num: usize, delimiter: char) -> OrtResult<Vec<&'a str>> {
+    match token.strip_prefix('[').and_then(|s| s.strip_suffix(']')) {
+        Some(inner) => split_items(inner, delimiter, line, line_num),
+        None => Err(type_mismatch(name, ty, token, line, line_num)),
+    }
+}
+
+/// Read a scalar cell as the type of its column, without inferring another
+fn typed_scalar<'a>(name: &str, ty: &FieldType, token: &'a str, line: &str, line_num: usize, delimiter: char) -> OrtResult<Scalar<'a>> {
+    if let Some(inner) = strip_quotes(token) {
+        if *ty == FieldType::Str {
+            return Ok(Scalar::String(unescape_quoted(inner)));
+        }
+        return Err(type_mismatch(name, ty, token, line, line_num));
+    }
+    if token.starts_with('"') && closing_quote(token).is_none() {
+        return Err(unterminated_quote(token, line, line_num));
+    }
+
+    let text = unescape(token, delimiter);
+    let scalar = match ty {
+        FieldType::Str => Some(Scalar::String(text)),
+        FieldType::Int => parse_integer(&text).and_then(|n| i64::try_from(n).ok()).map(|n| Scalar::Number(n as f64)),
+        FieldType::Float => parse_number(&text).map(Scalar::Number),
+        FieldType::Bool => match &*text {
+            "true" => Some(Scalar::Bool(true)),
+            "false" => Some(Scalar::Bool(false)),
+            _ => None,
+        },
+        FieldType::Array(_) => None,
+    };
+    scalar.ok_or_else(|| type_mismatch(name, ty, token, line, line_num))
+}
+
+fn type_mismatch(name: &str, ty: &FieldType, token: &str, line: &str, line_num: usize) -> OrtError {
+    let start = offset_in(line, token);
+    OrtError::new(line_num, line.to_string(), format!("Column '{}' expects {} but found '{}'", name, ty, token))
+        .with_kind(OrtErrorKind::TypeMismatch)
+        .with_span(start, start + token.len())
+}
+
+/// Split an array cell under a nested field into rows of nested values, as in
+/// `[(1,9.99),(2,4.50)]`
+///
+/// Gives `None` for arrays holding anything else, including inline objects
+/// such as `[(id:1,total:9.99)]`, which are read as regular values.
+fn nested_rows<'a>(trimmed: &'a str, line: &str, line_num: usize, delimiter: char) -> OrtResult<Option<Vec<&'a str>>> {
+    let items = split_items(&trimmed[1..trimmed.len()-1], delimiter, line, line_num)?;
+    for item in &items {
+        let item = item.trim();
+        if !item.starts_with('(') || !item.ends_with(')') {
+            return Ok(None);
         }
+        let values = split_values(&item[1..item.len()-1], delimiter).map_err(|rest| unterminated_quote(rest, line, line_num))?;
+        if values.into_iter().any(is_pair) {
+            return Ok(None);
+        }
+    }
+    Ok((!items.is_empty()).then_some(items))
+}
+
+/// Check if a value is a `key:value` pair of an inline object, with a colon
+/// before any bracket or quote
+fn is_pair(value: &str) -> bool {
+    value
+        .match_indices([':', '(', '[', '"'])
+        .find(|&(pos, _)| !is_escaped(value, pos))
+        .is_some_and(|(_, found)| found == ":")
+}
+
+fn parse_nested_values<'a>(
+    trimmed: &'a str,
+    expected: usize,
+    line: &str,
+    line_num: usize,
+    delimiter: char,
+) -> OrtResult<Vec<&'a str>> {
+    let inner = &trimmed[1..trimmed.len()-1];
+    let values = split_values(inner, delimiter).map_err(|rest| unterminated_quote(rest, line, line_num))?;
+
+    if values.len() != expected {
+        // Point at the extra values, or at the closing parenthesis if values are missing
+        let end = offset_in(line, trimmed) + trimmed.len() - 1;
+        let (start, end) = match values.get(expected) {
+            Some(extra) => (offset_in(line, extra), end),
+            None => (end, end + 1),
+        };
+        return Err(OrtError::new(
+            line_num,
+            line.to_string(),
+            format!("Expected {} nested values but got {}", expected, values.len()),
+        ).with_kind(OrtErrorKind::NestedValueCountMismatch).with_span(start, end));
     }
+
+    Ok(values)
 }
 
-fn parse_value(s: &str, line: &str, line_num: usize) -> OrtResult<OrtValue> {
+fn parse_value(s: &str, line: &str, line_num: usize, depth: usize, ctx: &mut Context) -> OrtResult<OrtValue> {
     let trimmed = s.trim();
 
     // Empty value -> null
@@ -362,6 +1780,10 @@ fn parse_value(s: &str, line: &str, line_num: usize) -> OrtResult<OrtValue> {
         return Ok(OrtValue::Null);
     }
 
+    if is_enclosed(trimmed) {
+        check_depth(depth, ctx.options.max_depth, trimmed, line, line_num)?;
+    }
+
     // Empty array
     if trimmed == "[]" {
         return Ok(OrtValue::Array(vec![]));
@@ -369,194 +1791,292 @@ fn parse_value(s: &str, line: &str, line_num: usize) -> OrtResult<OrtValue> {
 
     // Empty object
     if trimmed == "()" {
-        return Ok(OrtValue::Object(HashMap::new()));
+        return Ok(OrtValue::Object(Map::new()));
     }
 
     // Array
     if trimmed.starts_with('[') && trimmed.ends_with(']') {
-        return parse_array(&trimmed[1..trimmed.len()-1], line, line_num);
+        return parse_array(&trimmed[1..trimmed.len()-1], line, line_num, depth + 1, ctx);
     }
 
     // Inline object
     if trimmed.starts_with('(') && trimmed.ends_with(')') {
-        return parse_inline_object(&trimmed[1..trimmed.len()-1], line, line_num);
+        return parse_inline_object(&trimmed[1..trimmed.len()-1], line, line_num, depth + 1, ctx);
     }
 
-    // Unescape string
-    let unescaped = unescape(trimmed);
+    // Quoted string, never inferred as another type
+    if let Some(inner) = strip_quotes(trimmed) {
+        return Ok(OrtValue::String(unescape_quoted(inner).into_owned()));
+    }
+    if trimmed.starts_with('"') && closing_quote(trimmed).is_none() {
+        return Err(unterminated_quote(trimmed, line, line_num));
+    }
 
-    // Try parse as number
-    if let Ok(num) = unescaped.parse::<i64>() {
-        return Ok(OrtValue::Number(num as f64));
+    if !ctx.options.infer_types {
+        return Ok(OrtValue::String(unescape(trimmed, ctx.options.delimiter).into_owned()));
+    }
+    let scalar = parse_scalar(trimmed, ctx.options.delimiter);
+    if let Scalar::Number(n) = scalar {
+        if loses_precision(trimmed, n) {
+            ctx.warn(line, line_num, trimmed, WarningKind::PrecisionLoss,
+                format!("Integer {} cannot be stored exactly and becomes {}", trimmed, n));
+        }
     }
+    Ok(scalar.into())
+}
+
+pub(crate) fn parse_value_borrowed<'a>(s: &'a str, line: &str, line_num: usize, depth: usize) -> OrtResult<OrtValueRef<'a>> {
+    let trimmed = s.trim();
 
-    if let Ok(num) = unescaped.parse::<f64>() {
-        return Ok(OrtValue::Number(num));
+    if trimmed.is_empty() {
+        return Ok(OrtValueRef::Null);
     }
 
-    // Boolean
-    if unescaped == "true" {
-        return Ok(OrtValue::Bool(true));
+    if is_enclosed(trimmed) {
+        check_depth(depth, DEFAULT_OPTIONS.max_depth, trimmed, line, line_num)?;
     }
-    if unescaped == "false" {
-        return Ok(OrtValue::Bool(false));
+
+    if trimmed == "[]" {
+        return Ok(OrtValueRef::Array(vec![]));
     }
 
-    // String
-    Ok(OrtValue::String(unescaped))
+    if trimmed == "()" {
+        return Ok(OrtValueRef::Object(Map::new()));
+    }
+
+    if trimmed.starts_with('[') && trimmed.ends_with(']') {
+        return parse_array_borrowed(&trimmed[1..trimmed.len()-1], line, line_num, depth + 1);
+    }
+
+    if trimmed.starts_with('(') && trimmed.ends_with(')') {
+        return parse_inline_object_borrowed(&trimmed[1..trimmed.len()-1], line, line_num, depth + 1);
+    }
+
+    if let Some(inner) = strip_quotes(trimmed) {
+        return Ok(OrtValueRef::String(unescape_quoted(inner)));
+    }
+    if trimmed.starts_with('"') && closing_quote(trimmed).is_none() {
+        return Err(unterminated_quote(trimmed, line, line_num));
+    }
+
+    Ok(parse_scalar(trimmed, DEFAULT_OPTIONS.delimiter).into())
 }
 
-fn parse_array(s: &str, line: &str, line_num: usize) -> OrtResult<OrtValue> {
-    if s.trim().is_empty() {
-        return Ok(OrtValue::Array(vec![]));
+/// Check if a token is an array or inline object
+fn is_enclosed(token: &str) -> bool {
+    (token.starts_with('[') && token.ends_with(']')) || (token.starts_with('(') && token.ends_with(')'))
+}
+
+/// Fail if a bracket or parenthesis at `depth` would nest deeper than `max_depth`
+fn check_depth(depth: usize, max_depth: usize, token: &str, line: &str, line_num: usize) -> OrtResult<()> {
+    if depth < max_depth {
+        return Ok(());
     }
+    let start = offset_in(line, token);
+    Err(OrtError::new(line_num, line.to_string(), format!("Nesting is deeper than the limit of {} levels", max_depth))
+        .with_kind(OrtErrorKind::DepthLimit)
+        .with_span(start, start + 1))
+}
 
-    let mut result = vec![];
-    let mut current = String::new();
-    let mut escaped = false;
-    let mut depth = 0;
-    let mut bracket_depth = 0;
+/// Get the content of a `"..."` token
+fn strip_quotes(token: &str) -> Option<&str> {
+    let end = closing_quote(token)?;
+    (end == token.len() - 1).then(|| &token[1..end])
+}
 
-    for ch in s.chars() {
-        if escaped {
-            current.push(ch);
-            escaped = false;
-            continue;
+/// Find the byte offset of the unescaped `"` closing a token that starts with one
+fn closing_quote(token: &str) -> Option<usize> {
+    if !token.starts_with('"') {
+        return None;
+    }
+    let mut escaped = false;
+    for (i, ch) in token.char_indices().skip(1) {
+        match ch {
+            _ if std::mem::take(&mut escaped) => {}
+            '\\' => escaped = true,
+            '"' => return Some(i),
+            _ => {}
         }
+    }
+    None
+}
 
-        match ch {
-            '\\' => {
-                escaped = true;
-                current.push('\\');
-            }
-            '(' => {
-                depth += 1;
-                current.push(ch);
-            }
-            ')' => {
-                depth -= 1;
-                current.push(ch);
-            }
-            '[' => {
-                bracket_depth += 1;
-                current.push(ch);
-            }
-            ']' => {
-                bracket_depth -= 1;
-                current.push(ch);
-            }
-            ',' => {
-                if depth == 0 && bracket_depth == 0 {
-                    result.push(parse_value(&current, line, line_num)?);
-                    current.clear();
-                } else {
-                    current.push(ch);
-                }
-            }
-            _ => current.push(ch),
+/// Scalar token with its inferred type
+pub(crate) enum Scalar<'a> {
+    Bool(bool),
+    Number(f64),
+    String(Cow<'a, str>),
+}
+
+impl From<Scalar<'_>> for OrtValue {
+    fn from(scalar: Scalar<'_>) -> Self {
+        match scalar {
+            Scalar::Bool(b) => OrtValue::Bool(b),
+            Scalar::Number(n) => OrtValue::Number(n),
+            Scalar::String(s) => OrtValue::String(s.into_owned()),
         }
     }
+}
 
-    if !current.trim().is_empty() {
-        result.push(parse_value(&current, line, line_num)?);
+impl<'a> From<Scalar<'a>> for OrtValueRef<'a> {
+    fn from(scalar: Scalar<'a>) -> Self {
+        match scalar {
+            Scalar::Bool(b) => OrtValueRef::Bool(b),
+            Scalar::Number(n) => OrtValueRef::Number(n),
+            Scalar::String(s) => OrtValueRef::String(s),
+        }
     }
+}
 
-    Ok(OrtValue::Array(result))
+/// Unescape a scalar token and infer its type
+fn parse_scalar(token: &str, delimiter: char) -> Scalar<'_> {
+    infer_scalar(unescape(token, delimiter))
 }
 
-fn parse_inline_object(s: &str, line: &str, line_num: usize) -> OrtResult<OrtValue> {
-    if s.trim().is_empty() {
-        return Ok(OrtValue::Object(HashMap::new()));
+/// Infer the type of an unescaped scalar token (number, boolean or string)
+pub(crate) fn infer_scalar(unescaped: Cow<'_, str>) -> Scalar<'_> {
+    // Try parse as number
+    if let Some(num) = parse_number(&unescaped) {
+        return Scalar::Number(num);
     }
 
-    let mut obj = HashMap::new();
-    let pairs = split_pairs(s)?;
+    // Boolean
+    if unescaped == "true" {
+        return Scalar::Bool(true);
+    }
+    if unescaped == "false" {
+        return Scalar::Bool(false);
+    }
 
-    for pair in pairs {
-        if let Some(pos) = pair.find(':') {
-            let key = pair[..pos].trim().to_string();
-            let value_str = pair[pos+1..].trim();
-            let value = parse_value(value_str, line, line_num)?;
-            obj.insert(key, value);
+    // String
+    Scalar::String(unescaped)
+}
+
+/// Parse a number token
+///
+/// Besides what `f64` parses, this accepts `_` between digits, as in
+/// `1_000_000`, and the integer prefixes `0x`, `0o` and `0b` in either case.
+/// Digits too large for `f64` give `None` rather than an infinity.
+pub(crate) fn parse_number(token: &str) -> Option<f64> {
+    if let Some(num) = parse_integer(token) {
+        // `-0` keeps its sign, as it does when parsed as a float
+        if num == 0 && token.starts_with('-') {
+            return Some(-0.0);
         }
+        return Some(num as f64);
+    }
+    let n = strip_underscores(token, 10)?.parse::<f64>().ok()?;
+    if n.is_infinite() && token.bytes().any(|b| b.is_ascii_digit()) {
+        return None;
     }
+    Some(n)
+}
 
-    Ok(OrtValue::Object(obj))
+/// Parse an integer token with an optional sign, radix prefix and `_` separators
+pub(crate) fn parse_integer(token: &str) -> Option<i128> {
+    let (negative, rest) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token.strip_prefix('+').unwrap_or(token)),
+    };
+    let (radix, digits) = match rest.as_bytes() {
+        [b'0', b'x' | b'X', ..] => (16, &rest[2..]),
+        [b'0', b'o' | b'O', ..] => (8, &rest[2..]),
+        [b'0', b'b' | b'B', ..] => (2, &rest[2..]),
+        _ => (10, rest),
+    };
+
+    let digits = strip_underscores(digits, radix)?;
+    if digits.is_empty() || !digits.chars().all(|ch| ch.is_digit(radix)) {
+        return None;
+    }
+    let num = i128::from_str_radix(&digits, radix).ok()?;
+    Some(if negative { -num } else { num })
 }
 
-fn split_pairs(s: &str) -> OrtResult<Vec<String>> {
-    let mut pairs = vec![];
-    let mut current = String::new();
-    let mut escaped = false;
-    let mut depth = 0;
-    let mut bracket_depth = 0;
+/// Remove `_` digit separators, failing if one is not between two digits
+fn strip_underscores(token: &str, radix: u32) -> Option<Cow<'_, str>> {
+    if !token.contains('_') {
+        return Some(Cow::Borrowed(token));
+    }
 
-    for ch in s.chars() {
-        if escaped {
-            current.push(ch);
-            escaped = false;
-            continue;
+    let bytes = token.as_bytes();
+    let is_digit = |i: Option<usize>| i.and_then(|i| bytes.get(i)).is_some_and(|&b| (b as char).is_digit(radix));
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'_' && !(is_digit(i.checked_sub(1)) && is_digit(Some(i + 1))) {
+            return None;
         }
+    }
+    Some(Cow::Owned(token.replace('_', "")))
+}
 
-        match ch {
-            '\\' => {
-                escaped = true;
-                current.push('\\');
-            }
-            '(' => {
-                depth += 1;
-                current.push(ch);
-            }
-            ')' => {
-                depth -= 1;
-                current.push(ch);
-            }
-            '[' => {
-                bracket_depth += 1;
-                current.push(ch);
-            }
-            ']' => {
-                bracket_depth -= 1;
-                current.push(ch);
-            }
-            ',' => {
-                if depth == 0 && bracket_depth == 0 {
-                    pairs.push(current.clone());
-                    current.clear();
-                } else {
-                    current.push(ch);
-                }
-            }
-            _ => current.push(ch),
-        }
+/// Check if an integer token has no exact f64 representation
+fn loses_precision(token: &str, n: f64) -> bool {
+    let digits = token.strip_prefix('-').unwrap_or(token);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return false;
+    }
+    match token.parse::<i128>() {
+        Ok(exact) => n as i128 != exact,
+        Err(_) => true,
     }
+}
 
-    if !current.trim().is_empty() {
-        pairs.push(current);
+fn parse_array(s: &str, line: &str, line_num: usize, depth: usize, ctx: &mut Context) -> OrtResult<OrtValue> {
+    let mut result = vec![];
+    for item in split_items(s, ctx.options.delimiter, line, line_num)? {
+        result.push(parse_value(item, line, line_num, depth, ctx)?);
     }
 
-    Ok(pairs)
+    Ok(OrtValue::Array(result))
 }
 
-fn unescape(s: &str) -> String {
-    let mut result = String::new();
-    let mut escaped = false;
+fn parse_array_borrowed<'a>(s: &'a str, line: &str, line_num: usize, depth: usize) -> OrtResult<OrtValueRef<'a>> {
+    let mut result = vec![];
+    for item in split_items(s, DEFAULT_OPTIONS.delimiter, line, line_num)? {
+        result.push(parse_value_borrowed(item, line, line_num, depth)?);
+    }
 
-    for ch in s.chars() {
-        if escaped {
-            match ch {
-                'n' => result.push('\n'),
-                't' => result.push('\t'),
-                'r' => result.push('\r'),
-                _ => result.push(ch),
+    Ok(OrtValueRef::Array(result))
+}
+
+fn parse_inline_object(s: &str, line: &str, line_num: usize, depth: usize, ctx: &mut Context) -> OrtResult<OrtValue> {
+    let mut obj = Map::new();
+
+    for pair in split_items(s, ctx.options.delimiter, line, line_num)? {
+        if let Some(pos) = find_unescaped(pair, ':') {
+            let key = pair[..pos].trim();
+            let value_str = pair[pos+1..].trim();
+            let value = parse_value(value_str, line, line_num, depth, ctx)?;
+            let name = unescape(key, ctx.options.delimiter).into_owned();
+            let Some(existing) = obj.get_mut(&name) else {
+                obj.insert(name, value);
+                continue;
+            };
+
+            let message = format!("Key '{}' is repeated in the inline object", key);
+            if !resolve_conflict(existing, value, ctx.options.duplicate_keys) {
+                let start = offset_in(line, key);
+                return Err(OrtError::new(line_num, line.to_string(), message)
+                    .with_kind(OrtErrorKind::DuplicateKey)
+                    .with_span(start, start + key.len()));
             }
-            escaped = false;
-        } else if ch == '\\' {
-            escaped = true;
-        } else {
-            result.push(ch);
+            ctx.warn(line, line_num, key, WarningKind::DuplicateKey, message);
+        }
+    }
+
+    Ok(OrtValue::Object(obj))
+}
+
+fn parse_inline_object_borrowed<'a>(s: &'a str, line: &str, line_num: usize, depth: usize) -> OrtResult<OrtValueRef<'a>> {
+    let mut obj = Map::new();
+
+    for pair in split_items(s, DEFAULT_OPTIONS.delimiter, line, line_num)? {
+        if let Some(pos) = find_unescaped(pair, ':') {
+            let key = unescape(pair[..pos].trim(), DEFAULT_OPTIONS.delimiter);
+            let value = parse_value_borrowed(&pair[pos+1..], line, line_num, depth)?;
+            obj.insert(key, value);
         }
     }
 
-    result
+    Ok(OrtValueRef::Object(obj))
 }
@@ -0,0 +1,341 @@
+use crate::error::{OrtError, OrtResult};
+use crate::escape::unescape_quoted;
+use crate::ort_value::OrtValue;
+use crate::parser::parse_number;
+
+/// Evaluate a query expression against a value
+///
+/// An expression is a chain of steps:
+///
+/// - `name` or `.name`: the value of a key, or of an array index when the
+///   name is a number, as for `get_path`
+/// - `[2]`: an array item, counted from the end when negative
+/// - `["name"]`: the value of a key that has `.` or `[` in it
+/// - `[*]`: every item of an array, or every value of an object in key order
+/// - `[?field == literal]`: the items of an array whose `field`, a dotted
+///   path or `@` for the item itself, compares to the literal with `==`,
+///   `!=`, `<`, `<=`, `>` or `>=`; `[?field]` keeps the items where it is
+///   truthy
+///
+/// Literals are numbers, quoted strings, `true`, `false` and `null`, and `<`
+/// and `>` only hold between two numbers or two strings.
+///
+/// After `[*]` or a filter, the following steps apply to each result, the
+/// ones they don't match are dropped, and the results are returned as one
+/// flat array. A filter there keeps the results that match. Otherwise the
+/// single value is returned, or null if the path doesn't exist. Errors point
+/// at the failing part of the expression, and parentheses in a key must be
+/// balanced, so a key such as `((` needs the `["(("]` form.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let users = ort_rs::from_str(include_str!("../example/04_nested_objects.ort"))?;
+/// assert_eq!(ort_rs::query(&users, "users[*].profile.address.city")?.to_string(), ":\n[New York,London]");
+/// assert_eq!(ort_rs::query(&users, "users[?profile.age > 26].id")?, ort_rs::OrtValue::from(vec![ort_rs::OrtValue::from(1)]));
+/// assert_eq!(ort_rs::query(&users, "users[-1].profile.name")?.as_str(), Some("Jane Smith"));
+///
+/// let settings = ort_rs::from_str(include_str!("../example/11_boolean.ort"))?;
+/// assert_eq!(ort_rs::query(&settings, "settings[?enabled == true].feature")?.to_string(), ":\n[notifications,auto_save]");
+/// assert_eq!(ort_rs::query(&settings, "settings[?feature != 'dark_mode'][?verified].id")?.to_string(), ":\n[3]");
+///
+/// let matrix = ort_rs::from_str(include_str!("../example/05_nested_array.ort"))?;
+/// assert_eq!(ort_rs::query(&matrix, "matrix[*][*][?@ >= 5]")?.to_string(), ":\n[5,6,7,8,9]");
+/// assert_eq!(ort_rs::query(&matrix, "matrix[1][2]")?.as_f64(), Some(6.0));
+///
+/// let error = ort_rs::query(&users, "users[?id = 1]").unwrap_err();
+/// assert_eq!(error.message, "Expected a comparison operator such as '==' or '<'");
+/// assert_eq!((error.column, error.span.as_deref()), (Some(11), Some(&(10, 11))));
+///
+/// let error = ort_rs::query(&users, "users.((((").unwrap_err();
+/// assert_eq!(error.message, "Unclosed '(' in key");
+/// assert_eq!(ort_rs::query(&ort_rs::from_str("((((:\n1")?, "[\"((((\"]")?.as_f64(), Some(1.0));
+/// # Ok(())
+/// # }
+/// ```
+pub fn query(value: &OrtValue, expr: &str) -> OrtResult<OrtValue> {
+    let steps = QueryParser { expr, pos: 0 }.parse()?;
+
+    let mut current = vec![value];
+    let mut projected = false;
+    for step in &steps {
+        current = match step {
+            Step::Key(key) => current.into_iter().filter_map(|value| child(value, key)).collect(),
+            Step::Index(index) => current.into_iter().filter_map(|value| item(value, *index)).collect(),
+            Step::Wildcard => {
+                projected = true;
+                current.into_iter().flat_map(children).collect()
+            }
+            // Following a projection, a filter narrows its results
+            Step::Filter(filter) if projected => current.into_iter().filter(|item| filter.matches(item)).collect(),
+            Step::Filter(filter) => {
+                projected = true;
+                current
+                    .into_iter()
+                    .filter_map(OrtValue::as_array)
+                    .flatten()
+                    .filter(|item| filter.matches(item))
+                    .collect()
+            }
+        };
+    }
+
+    Ok(match projected {
+        true => OrtValue::Array(current.into_iter().cloned().collect()),
+        false => current.first().map_or(OrtValue::Null, |value| (*value).clone()),
+    })
+}
+
+enum Step {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Filter(Filter),
+}
+
+struct Filter {
+    /// Dotted path within the item, empty for `@`
+    path: Vec<String>,
+    /// Comparison, or `None` to test if the value is truthy
+    test: Option<(Op, OrtValue)>,
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Filter {
+    fn matches(&self, item: &OrtValue) -> bool {
+        let found = self.path.iter().try_fold(item, |value, key| child(value, key));
+        let value = found.unwrap_or(&OrtValue::Null);
+        let Some((op, literal)) = &self.test else {
+            return value.is_truthy();
+        };
+
+        let ordering = match (value, literal) {
+            (OrtValue::Number(a), OrtValue::Number(b)) => a.partial_cmp(b),
+            (OrtValue::String(a), OrtValue::String(b)) => Some(a.cmp(b)),
+            _ => None,
+        };
+        match op {
+            Op::Eq => value == literal,
+            Op::Ne => value != literal,
+            Op::Lt => ordering.is_some_and(|o| o.is_lt()),
+            Op::Le => ordering.is_some_and(|o| o.is_le()),
+            Op::Gt => ordering.is_some_and(|o| o.is_gt()),
+            Op::Ge => ordering.is_some_and(|o| o.is_ge()),
+        }
+    }
+}
+
+fn child<'a>(value: &'a OrtValue, key: &str) -> Option<&'a OrtValue> {
+    match value {
+        OrtValue::Object(obj) => obj.get(key),
+        OrtValue::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+fn item(value: &OrtValue, index: i64) -> Option<&OrtValue> {
+    let items = value.as_array()?;
+    let index = if index < 0 { items.len().checked_sub(index.unsigned_abs() as usize)? } else { index as usize };
+    items.get(index)
+}
+
+fn children(value: &OrtValue) -> Vec<&OrtValue> {
+    match value {
+        OrtValue::Array(items) => items.iter().collect(),
+        OrtValue::Object(obj) => {
+            let mut entries: Vec<_> = obj.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            entries.into_iter().map(|(_, value)| value).collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// Reads an expression into steps, keeping the byte position for errors
+struct QueryParser<'a> {
+    expr: &'a str,
+    pos: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn parse(mut self) -> OrtResult<Vec<Step>> {
+        let mut steps = vec![];
+        while let Some(ch) = self.peek() {
+            match ch {
+                '[' => steps.push(self.bracket()?),
+                '.' if !steps.is_empty() => {
+                    self.pos += 1;
+                    steps.push(Step::Key(self.name()?));
+                }
+                _ if steps.is_empty() => steps.push(Step::Key(self.name()?)),
+                _ => return Err(self.error(self.pos, self.pos + ch.len_utf8(), format!("Unexpected '{}', expected '.' or '['", ch))),
+            }
+        }
+        Ok(steps)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.expr[self.pos..].chars().next()
+    }
+
+    fn rest(&self) -> &str {
+        &self.expr[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.pos = self.expr.len() - self.rest().trim_start().len();
+    }
+
+    /// Take characters up to one of `stops`, or the end
+    fn take_until(&mut self, stops: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        self.pos = self.rest().find(stops).map_or(self.expr.len(), |i| start + i);
+        &self.expr[start..self.pos]
+    }
+
+    fn name(&mut self) -> OrtResult<String> {
+        let start = self.pos;
+        let name = self.take_until(|ch| matches!(ch, '.' | '[' | ']'));
+        if name.is_empty() {
+            return Err(self.error(start, start + 1, "Expected a key".to_string()));
+        }
+        self.check_parentheses(start, name)?;
+        Ok(name.to_string())
+    }
+
+    /// Reject a key or path starting at `start` whose parentheses don't pair up
+    fn check_parentheses(&self, start: usize, text: &str) -> OrtResult<()> {
+        let mut open = vec![];
+        for (i, ch) in text.char_indices() {
+            match ch {
+                '(' => open.push(start + i),
+                ')' if open.pop().is_none() => return Err(self.error(start + i, start + i + 1, "Unmatched ')' in key".to_string())),
+                _ => {}
+            }
+        }
+        match open.first() {
+            Some(&at) => Err(self.error(at, at + 1, "Unclosed '(' in key".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> OrtResult<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(ch) if ch == expected => {
+                self.pos += ch.len_utf8();
+                Ok(())
+            }
+            Some(ch) => Err(self.error(self.pos, self.pos + ch.len_utf8(), format!("Expected '{}' but found '{}'", expected, ch))),
+            None => Err(self.error(self.pos, self.pos, format!("Expected '{}' but the expression ended", expected))),
+        }
+    }
+
+    fn bracket(&mut self) -> OrtResult<Step> {
+        let open = self.pos;
+        self.pos += 1;
+        self.skip_whitespace();
+        let step = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Step::Wildcard
+            }
+            Some('?') => {
+                self.pos += 1;
+                Step::Filter(self.filter()?)
+            }
+            Some('"' | '\'') => match self.literal()? {
+                OrtValue::String(key) => Step::Key(key),
+                _ => unreachable!("quoted literals are strings"),
+            },
+            Some(ch) if ch == '-' || ch.is_ascii_digit() => {
+                let start = self.pos;
+                let token = self.take_until(|ch| ch == ']' || ch.is_whitespace());
+                match token.parse::<i64>() {
+                    Ok(index) => Step::Index(index),
+                    Err(_) => return Err(self.error(start, self.pos, format!("Invalid index '{}'", token))),
+                }
+            }
+            _ => {
+                let end = self.pos + self.peek().map_or(0, char::len_utf8);
+                return Err(self.error(open, end, "Expected an index, '*', '?' or a quoted key after '['".to_string()));
+            }
+        };
+        self.expect(']')?;
+        Ok(step)
+    }
+
+    fn filter(&mut self) -> OrtResult<Filter> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let operand = self.take_until(|ch| matches!(ch, '=' | '!' | '<' | '>' | ']') || ch.is_whitespace());
+        let path = match operand {
+            "" => return Err(self.error(start, start + 1, "Expected a field or '@' to filter on".to_string())),
+            "@" => vec![],
+            path => {
+                self.check_parentheses(start, path)?;
+                path.split('.').map(str::to_string).collect()
+            }
+        };
+
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            return Ok(Filter { path, test: None });
+        }
+        let ops = [("==", Op::Eq), ("!=", Op::Ne), ("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)];
+        let Some(&(token, op)) = ops.iter().find(|(token, _)| self.rest().starts_with(token)) else {
+            let end = self.pos + self.peek().map_or(0, char::len_utf8);
+            return Err(self.error(self.pos, end, "Expected a comparison operator such as '==' or '<'".to_string()));
+        };
+        self.pos += token.len();
+
+        self.skip_whitespace();
+        Ok(Filter { path, test: Some((op, self.literal()?)) })
+    }
+
+    fn literal(&mut self) -> OrtResult<OrtValue> {
+        let start = self.pos;
+        if let Some(quote @ ('"' | '\'')) = self.peek() {
+            // Find the closing quote, skipping escaped characters
+            let mut escaped = false;
+            let close = self.expr[start + 1..].char_indices().find(|&(_, ch)| {
+                let found = !escaped && ch == quote;
+                escaped = !escaped && ch == '\\';
+                found
+            });
+            let Some((close, _)) = close else {
+                return Err(self.error(start, self.expr.len(), "Unterminated string".to_string()));
+            };
+            self.pos = start + 1 + close + 1;
+            return Ok(OrtValue::String(unescape_quoted(&self.expr[start + 1..self.pos - 1]).into_owned()));
+        }
+
+        let token = self.take_until(|ch| ch == ']' || ch.is_whitespace());
+        match token {
+            "true" => Ok(OrtValue::Bool(true)),
+            "false" => Ok(OrtValue::Bool(false)),
+            "null" => Ok(OrtValue::Null),
+            _ => match parse_number(token) {
+                Some(n) => Ok(OrtValue::Number(n)),
+                None => Err(self.error(
+                    start,
+                    self.pos.max(start + 1),
+                    "Expected a number, a quoted string, true, false or null".to_string(),
+                )),
+            },
+        }
+    }
+
+    fn error(&self, start: usize, end: usize, message: String) -> OrtError {
+        OrtError::new(1, self.expr.to_string(), message).with_span(start, end)
+    }
+}
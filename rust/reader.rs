@@ -0,0 +1,189 @@
+use crate::error::{OrtError, OrtErrorKind, OrtResult};
+use crate::escape::unescape;
+use crate::fields::FieldSpec;
+use crate::ort_value::OrtValue;
+use crate::parser::{is_blank_or_comment, is_header, parse_fields, parse_header, parse_row, parse_value_line, to_field_specs, Context, ParseOptions};
+use std::io::BufRead;
+
+/// Streaming ORT reader that parses one row at a time
+///
+/// Sections are visited in document order and their rows are parsed lazily, so
+/// only the current row is held in memory. Unlike `parse_ort`, a `:fields:`
+/// section does not end the document, and each data line of a section without
+/// fields is yielded as its own value.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use std::io::Cursor;
+/// use ort_rs::OrtReader;
+///
+/// let input = Cursor::new("users:id,name:\n1,John\n2,Jane\n\nitems:id:\n1\n");
+/// let mut reader = OrtReader::from_reader(input);
+///
+/// while let Some(section) = reader.next_section() {
+///     let section = section?;
+///     let key = section.key().map(str::to_string);
+///     for row in section {
+///         println!("{:?}: {}", key, row?["id"]);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct OrtReader<R: BufRead> {
+    reader: R,
+    line_num: usize,
+    peeked: Option<String>,
+    in_section: bool,
+}
+
+impl<R: BufRead> OrtReader<R> {
+    /// Create a reader over buffered ORT input
+    pub fn from_reader(reader: R) -> Self {
+        Self {
+            reader,
+            line_num: 0,
+            peeked: None,
+            in_section: false,
+        }
+    }
+
+    /// Advance to the next section header
+    ///
+    /// Any rows left unread in the previous section are skipped.
+    pub fn next_section(&mut self) -> Option<OrtResult<Section<'_, R>>> {
+        loop {
+            let line = match self.next_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => return None,
+                Err(e) => return Some(Err(e)),
+            };
+            let trimmed = line.trim();
+
+            // Skip empty lines and comments
+            if is_blank_or_comment(trimmed, &ParseOptions::default()) {
+                continue;
+            }
+
+            // Skip the rest of an abandoned section
+            if self.in_section && !is_header(trimmed) {
+                continue;
+            }
+
+            if !trimmed.contains(':') {
+                continue;
+            }
+
+            // Rows following a malformed header belong to no section
+            self.in_section = true;
+
+            let line_num = self.line_num;
+            let (key, fields) = match parse_header(trimmed, line_num)
+                .and_then(|(key, fields_str)| {
+                    let fields = parse_fields(fields_str, trimmed, line_num, &ParseOptions::default())?;
+                    Ok((key.map(|key| unescape(key, ',').into_owned()), to_field_specs(&fields, ',')))
+                }) {
+                Ok(header) => header,
+                Err(e) => return Some(Err(e)),
+            };
+
+            return Some(Ok(Section {
+                reader: self,
+                key,
+                fields,
+                line: line_num,
+                finished: false,
+            }));
+        }
+    }
+
+    fn next_line(&mut self) -> OrtResult<Option<String>> {
+        if let Some(line) = self.peeked.take() {
+            return Ok(Some(line));
+        }
+
+        let mut line = String::new();
+        let read = self.reader.read_line(&mut line).map_err(|e| {
+            OrtError::new(self.line_num + 1, String::new(), format!("Failed to read input: {}", e))
+                .with_kind(OrtErrorKind::Io)
+                .with_source(e)
+        })?;
+
+        if read == 0 {
+            return Ok(None);
+        }
+
+        self.line_num += 1;
+        Ok(Some(line))
+    }
+}
+
+/// Section of a streamed ORT document, iterating over its rows
+pub struct Section<'r, R: BufRead> {
+    reader: &'r mut OrtReader<R>,
+    key: Option<String>,
+    fields: Vec<FieldSpec>,
+    line: usize,
+    finished: bool,
+}
+
+impl<R: BufRead> Section<'_, R> {
+    /// Get the section key (`None` for `:fields:` sections)
+    pub fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
+
+    /// Get the fields declared in the section header
+    pub fn fields(&self) -> &[FieldSpec] {
+        &self.fields
+    }
+
+    /// Get the line number of the section header
+    pub fn line(&self) -> usize {
+        self.line
+    }
+}
+
+impl<R: BufRead> Iterator for Section<'_, R> {
+    type Item = OrtResult<OrtValue>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            let line = match self.reader.next_line() {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            };
+            let trimmed = line.trim();
+
+            // Skip empty lines and comments
+            if is_blank_or_comment(trimmed, &ParseOptions::default()) {
+                continue;
+            }
+
+            // Next header ends this section
+            if trimmed.contains(':') && is_header(trimmed) {
+                self.reader.peeked = Some(line);
+                break;
+            }
+
+            let line_num = self.reader.line_num;
+            if self.fields.is_empty() {
+                return Some(parse_value_line(trimmed, line_num, &mut Context::default()));
+            }
+            return Some(parse_row(trimmed, line_num, &self.fields, &mut Context::default()));
+        }
+
+        self.finished = true;
+        self.reader.in_section = false;
+        None
+    }
+}
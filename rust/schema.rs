@@ -0,0 +1,400 @@
+use crate::error::{OrtError, OrtResult};
+use crate::fields::FieldType;
+use crate::ort_value::{remove_key, Map, OrtValue};
+use crate::parser::parse_ort;
+use std::fmt;
+
+/// Expected type of a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaType {
+    /// Any value
+    #[default]
+    Any,
+    Null,
+    Bool,
+    /// A whole number
+    Int,
+    /// Any number
+    Float,
+    Str,
+    Array,
+    Object,
+}
+
+impl SchemaType {
+    /// Get the type name, as written in a schema
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchemaType::Any => "any",
+            SchemaType::Null => "null",
+            SchemaType::Bool => "bool",
+            SchemaType::Int => "int",
+            SchemaType::Float => "float",
+            SchemaType::Str => "str",
+            SchemaType::Array => "array",
+            SchemaType::Object => "object",
+        }
+    }
+
+    /// Check if a value has this type
+    pub fn matches(&self, value: &OrtValue) -> bool {
+        match (self, value) {
+            (SchemaType::Any, _)
+            | (SchemaType::Null, OrtValue::Null)
+            | (SchemaType::Bool, OrtValue::Bool(_))
+            | (SchemaType::Float, OrtValue::Number(_))
+            | (SchemaType::Str, OrtValue::String(_))
+            | (SchemaType::Array, OrtValue::Array(_))
+            | (SchemaType::Object, OrtValue::Object(_)) => true,
+            (SchemaType::Int, OrtValue::Number(n)) => n.fract() == 0.0,
+            _ => false,
+        }
+    }
+
+    fn parse(name: &str) -> Option<SchemaType> {
+        Some(match name {
+            "any" => SchemaType::Any,
+            "null" => SchemaType::Null,
+            "bool" => SchemaType::Bool,
+            "int" => SchemaType::Int,
+            "float" => SchemaType::Float,
+            "str" => SchemaType::Str,
+            "array" => SchemaType::Array,
+            "object" => SchemaType::Object,
+            _ => return None,
+        })
+    }
+}
+
+impl fmt::Display for SchemaType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Expected structure of a value
+///
+/// A schema is written as a value itself, so it can be kept in ORT or JSON.
+/// It is either a type name (`any`, `null`, `bool`, `int`, `float`, `str`,
+/// `array`, `object`, or an array type such as `[int]`) or an object with
+/// these keys, all optional:
+///
+/// - `type`: the type name, `any` if missing
+/// - `required`: `false` if a field may be missing from its object
+/// - `nullable`: `true` if the value may also be null
+/// - `min` and `max`: the allowed range of a number
+/// - `items`: the schema of every item of an array
+/// - `fields`: the schemas of the fields of an object, by name
+///
+/// Objects may have fields the schema doesn't list.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::schema::{validate_against_schema, Schema};
+///
+/// let schema = Schema::parse("type:\nobject\n\nfields:\n(users:(type:array,items:(fields:(id:(type:int,min:1),name:str,email:(type:str,required:false)))))")?;
+///
+/// let value = ort_rs::from_str("users:id,name:\n1,John\n0,Jane\n3,")?;
+/// let errors = validate_against_schema(&value, &schema);
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(errors[0].to_string(), "users.1.id: expected int at least 1 but found 0");
+/// assert_eq!((errors[1].path.as_str(), errors[1].expected.as_str(), errors[1].actual.as_str()), ("users.2.name", "str", "null"));
+///
+/// assert!(validate_against_schema(&ort_rs::from_str("users:id,name:\n1,John")?, &schema).is_empty());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schema {
+    pub ty: SchemaType,
+    /// The value may be missing from its object
+    pub optional: bool,
+    /// The value may be null as well as of its type
+    pub nullable: bool,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Schema of every item of an array
+    pub items: Option<Box<Schema>>,
+    /// Schemas of the fields of an object
+    pub fields: Map<String, Schema>,
+}
+
+impl Schema {
+    /// Create a schema of the given type
+    pub fn new(ty: SchemaType) -> Self {
+        Schema { ty, ..Default::default() }
+    }
+
+    /// Parse a schema written in ORT
+    pub fn parse(s: &str) -> OrtResult<Schema> {
+        Schema::from_value(&parse_ort(s)?)
+    }
+
+    /// Read a schema from its value form
+    ///
+    /// A schema kept in JSON is read by converting it first, with
+    /// `Schema::from_value(&OrtValue::from(json))`.
+    pub fn from_value(value: &OrtValue) -> OrtResult<Schema> {
+        read_schema(value, "")
+    }
+
+    /// Write the schema in its value form, which `from_value` reads back
+    ///
+    /// A schema that is just a type is written as its type name.
+    pub fn to_value(&self) -> OrtValue {
+        let mut obj = Map::new();
+        if self.ty != SchemaType::Any {
+            obj.insert("type".to_string(), OrtValue::from(self.ty.name()));
+        }
+        if self.optional {
+            obj.insert("required".to_string(), OrtValue::Bool(false));
+        }
+        if self.nullable {
+            obj.insert("nullable".to_string(), OrtValue::Bool(true));
+        }
+        if let Some(min) = self.min {
+            obj.insert("min".to_string(), OrtValue::Number(min));
+        }
+        if let Some(max) = self.max {
+            obj.insert("max".to_string(), OrtValue::Number(max));
+        }
+        if let Some(items) = &self.items {
+            obj.insert("items".to_string(), items.to_value());
+        }
+        if !self.fields.is_empty() {
+            let fields = self.fields.iter().map(|(name, field)| (name.clone(), field.to_value())).collect();
+            obj.insert("fields".to_string(), OrtValue::Object(fields));
+        }
+
+        match obj.len() {
+            0 => OrtValue::from("any"),
+            1 if obj.contains_key("type") => OrtValue::from(self.ty.name()),
+            _ => OrtValue::Object(obj),
+        }
+    }
+
+    /// Infer a permissive schema that the example value passes
+    ///
+    /// Types are taken from the example without ranges. The items of an
+    /// array share one schema: fields missing from some objects are not
+    /// required, a type seen with null is nullable, `int` widens to `float`,
+    /// and other mixed types become `any`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::schema::{validate_against_schema, Schema};
+    ///
+    /// let example = ort_rs::from_str("users:id,name,score:\n1,John,9.5\n2,,7")?;
+    /// let schema = Schema::infer_from(&example);
+    /// assert!(validate_against_schema(&example, &schema).is_empty());
+    ///
+    /// let written = ort_rs::generate_ort(&schema.to_value());
+    /// assert_eq!(written, "fields:\n(users:(items:(fields:(id:int,name:(nullable:true,type:str),score:float),type:object),type:array))\n\ntype:\nobject\n");
+    /// assert_eq!(Schema::parse(&written)?, schema);
+    ///
+    /// let other = ort_rs::from_str("users:id,name,score:\n1.5,John,high")?;
+    /// assert_eq!(validate_against_schema(&other, &schema).len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn infer_from(value: &OrtValue) -> Schema {
+        match value {
+            OrtValue::Null => Schema::new(SchemaType::Null),
+            OrtValue::Bool(_) => Schema::new(SchemaType::Bool),
+            OrtValue::Number(n) if n.fract() == 0.0 => Schema::new(SchemaType::Int),
+            OrtValue::Number(_) => Schema::new(SchemaType::Float),
+            OrtValue::String(_) => Schema::new(SchemaType::Str),
+            OrtValue::Array(items) => Schema {
+                items: items.iter().map(Schema::infer_from).reduce(unify).map(Box::new),
+                ..Schema::new(SchemaType::Array)
+            },
+            OrtValue::Object(obj) => Schema {
+                fields: obj.iter().map(|(key, value)| (key.clone(), Schema::infer_from(value))).collect(),
+                ..Schema::new(SchemaType::Object)
+            },
+        }
+    }
+}
+
+/// A value that doesn't match its schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// Dot-separated path of the value, as for `get_path`, empty for the root
+    pub path: String,
+    /// What the schema expects, such as `int` or `int at least 1`
+    pub expected: String,
+    /// What was found instead: the type name, the value if it is out of
+    /// range, or `missing`
+    pub actual: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "{}: ", self.path)?;
+        }
+        write!(f, "expected {} but found {}", self.expected, self.actual)
+    }
+}
+
+/// Check a value against a schema, listing every mismatch
+///
+/// Fields of an object are checked in sorted order and array items by index.
+pub fn validate_against_schema(value: &OrtValue, schema: &Schema) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    validate_into(value, schema, String::new(), &mut errors);
+    errors
+}
+
+fn validate_into(value: &OrtValue, schema: &Schema, path: String, errors: &mut Vec<ValidationError>) {
+    if value.is_null() && schema.nullable {
+        return;
+    }
+    if !schema.ty.matches(value) {
+        let expected = if schema.nullable { format!("{} or null", schema.ty) } else { schema.ty.to_string() };
+        errors.push(ValidationError { path, expected, actual: value.type_name().to_string() });
+        return;
+    }
+
+    if let Some(n) = value.as_f64() {
+        let range = match (schema.min, schema.max) {
+            (Some(min), Some(max)) if !(min..=max).contains(&n) => Some(format!("between {} and {}", min, max)),
+            (Some(min), None) if n < min => Some(format!("at least {}", min)),
+            (None, Some(max)) if n > max => Some(format!("at most {}", max)),
+            _ => None,
+        };
+        if let Some(range) = range {
+            let ty = if schema.ty == SchemaType::Any { "number" } else { schema.ty.name() };
+            errors.push(ValidationError { path, expected: format!("{} {}", ty, range), actual: value.coerce_to_string() });
+            return;
+        }
+    }
+
+    match value {
+        OrtValue::Array(items) => {
+            if let Some(item_schema) = &schema.items {
+                for (i, item) in items.iter().enumerate() {
+                    validate_into(item, item_schema, child_path(&path, &i.to_string()), errors);
+                }
+            }
+        }
+        OrtValue::Object(obj) => {
+            let mut names: Vec<&String> = schema.fields.keys().collect();
+            names.sort();
+            for name in names {
+                let field = &schema.fields[name];
+                match obj.get(name) {
+                    Some(value) => validate_into(value, field, child_path(&path, name), errors),
+                    None if !field.optional => errors.push(ValidationError {
+                        path: child_path(&path, name),
+                        expected: field.ty.to_string(),
+                        actual: "missing".to_string(),
+                    }),
+                    None => {}
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    match path {
+        "" => key.to_string(),
+        _ => format!("{}.{}", path, key),
+    }
+}
+
+/// Combine the schemas inferred from two items of one array
+fn unify(a: Schema, b: Schema) -> Schema {
+    match (a.ty, b.ty) {
+        (SchemaType::Null, _) => return Schema { nullable: true, ..b },
+        (_, SchemaType::Null) => return Schema { nullable: true, ..a },
+        _ => {}
+    }
+    let ty = match (a.ty, b.ty) {
+        (a, b) if a == b => a,
+        (SchemaType::Int, SchemaType::Float) | (SchemaType::Float, SchemaType::Int) => SchemaType::Float,
+        _ => return Schema { nullable: a.nullable || b.nullable, ..Schema::default() },
+    };
+    let items = match (a.items, b.items) {
+        (Some(a), Some(b)) => Some(Box::new(unify(*a, *b))),
+        (a, b) => a.or(b),
+    };
+
+    // Fields missing from either object are not required
+    let mut others = b.fields;
+    let mut fields: Map<String, Schema> = a
+        .fields
+        .into_iter()
+        .map(|(name, field)| {
+            let field = match remove_key(&mut others, &name) {
+                Some(other) => Schema { optional: field.optional || other.optional, ..unify(field, other) },
+                None => Schema { optional: true, ..field },
+            };
+            (name, field)
+        })
+        .collect();
+    fields.extend(others.into_iter().map(|(name, field)| (name, Schema { optional: true, ..field })));
+
+    Schema { ty, nullable: a.nullable || b.nullable, items, fields, ..Default::default() }
+}
+
+fn read_schema(value: &OrtValue, path: &str) -> OrtResult<Schema> {
+    let at = |message: String| {
+        let message = if path.is_empty() { message } else { format!("{} at '{}'", message, path) };
+        OrtError::new(0, String::new(), message)
+    };
+
+    let obj = match value {
+        OrtValue::String(name) => return type_schema(name).ok_or_else(|| at(format!("Unknown schema type '{}'", name))),
+        OrtValue::Object(obj) => obj,
+        other => return Err(at(format!("Expected a type name or schema object but found {}", other.type_name()))),
+    };
+
+    let mut schema = match obj.get("type") {
+        None => Schema::default(),
+        Some(OrtValue::String(name)) => type_schema(name).ok_or_else(|| at(format!("Unknown schema type '{}'", name)))?,
+        Some(other) => return Err(at(format!("Expected 'type' to be a type name but found {}", other.type_name()))),
+    };
+    for (key, value) in obj {
+        match (key.as_str(), value) {
+            ("type", _) => {}
+            ("required", OrtValue::Bool(required)) => schema.optional = !required,
+            ("nullable", OrtValue::Bool(nullable)) => schema.nullable = *nullable,
+            ("min", OrtValue::Number(min)) => schema.min = Some(*min),
+            ("max", OrtValue::Number(max)) => schema.max = Some(*max),
+            ("items", items) => schema.items = Some(Box::new(read_schema(items, &child_path(path, "items"))?)),
+            ("fields", OrtValue::Object(fields)) => {
+                for (name, field) in fields {
+                    schema.fields.insert(name.clone(), read_schema(field, &child_path(path, name))?);
+                }
+            }
+            ("required" | "nullable", other) => return Err(at(format!("Expected '{}' to be a bool but found {}", key, other.type_name()))),
+            ("min" | "max", other) => return Err(at(format!("Expected '{}' to be a number but found {}", key, other.type_name()))),
+            ("fields", other) => return Err(at(format!("Expected 'fields' to be an object but found {}", other.type_name()))),
+            _ => return Err(at(format!("Unknown schema key '{}'", key))),
+        }
+    }
+    Ok(schema)
+}
+
+/// Read a type name, where an array type such as `[int]` also gives the
+/// schema of the items
+fn type_schema(name: &str) -> Option<Schema> {
+    if let Some(ty) = SchemaType::parse(name.trim()) {
+        return Some(Schema::new(ty));
+    }
+    fn from_field_type(ty: FieldType) -> Schema {
+        match ty {
+            FieldType::Int => Schema::new(SchemaType::Int),
+            FieldType::Float => Schema::new(SchemaType::Float),
+            FieldType::Str => Schema::new(SchemaType::Str),
+            FieldType::Bool => Schema::new(SchemaType::Bool),
+            FieldType::Array(item) => Schema { items: Some(Box::new(from_field_type(*item))), ..Schema::new(SchemaType::Array) },
+        }
+    }
+    FieldType::parse(name.trim()).map(from_field_type)
+}
@@ -1,8 +1,172 @@
-use crate::ort_value::OrtValue;
+use crate::error::{Diagnostic, OrtError, OrtErrorKind, OrtResult};
+use crate::generator::non_finite_token;
+use crate::ort_value::{Map, OrtValue};
 use serde_json::Value as JsonValue;
-use std::collections::HashMap;
+use std::io::{BufRead, Write};
 
-// Convert OrtValue to serde_json::Value
+/// What `OrtValue::to_json_with` writes for numbers JSON cannot hold: NaN and the infinities
+///
+/// # Example
+/// ```
+/// use ort_rs::{NonFinite, OrtValue};
+/// use serde_json::json;
+///
+/// let value = OrtValue::Array(vec![OrtValue::from(1.5), OrtValue::from(f64::NAN), OrtValue::from(f64::NEG_INFINITY)]);
+///
+/// let error = value.to_json_with(&NonFinite::Error).unwrap_err();
+/// assert_eq!(error.message, "Cannot write nan at [1] as JSON");
+/// assert_eq!(value.to_json_with(&NonFinite::Null).unwrap(), json!([1.5, null, null]));
+/// assert_eq!(value.to_json_with(&NonFinite::String).unwrap(), json!([1.5, "nan", "-inf"]));
+/// assert_eq!(value.to_json_with(&NonFinite::Replace(json!(0))).unwrap(), json!([1.5, 0, 0]));
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum NonFinite {
+    /// Fail, naming where the number is
+    #[default]
+    Error,
+    /// Write null, as the `From` conversion does
+    Null,
+    /// Write the ORT token as a string: `"nan"`, `"inf"` or `"-inf"`
+    String,
+    /// Write the given value
+    Replace(JsonValue),
+}
+
+impl OrtValue {
+    /// Convert to JSON, choosing what NaN and infinite numbers become
+    ///
+    /// Converting with `From` writes them as null.
+    pub fn to_json_with(&self, non_finite: &NonFinite) -> OrtResult<JsonValue> {
+        to_json(self, non_finite, &mut String::new())
+    }
+}
+
+/// Read JSON Lines (NDJSON), one JSON value per line, into an array
+///
+/// Lines of objects make a table. Blank lines are skipped, and a line that
+/// isn't valid JSON fails with its line number. With a `key`, the array is
+/// the only section of the result, under that name.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::serde_compat::ndjson_to_ort;
+///
+/// let lines = "{\"id\":1,\"name\":\"John\"}\n\n{\"id\":2,\"name\":\"Jane\"}\n";
+/// let value = ndjson_to_ort(lines.as_bytes(), Some("users"))?;
+/// assert_eq!(value, ort_rs::from_str("users:id,name:\n1,John\n2,Jane")?);
+///
+/// let error = ndjson_to_ort("{\"id\":1}\n{\"id\":".as_bytes(), None).unwrap_err();
+/// assert_eq!(error.line, 2);
+/// # Ok(())
+/// # }
+/// ```
+pub fn ndjson_to_ort(reader: impl BufRead, key: Option<&str>) -> OrtResult<OrtValue> {
+    let mut rows = vec![];
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| {
+            OrtError::new(i + 1, String::new(), format!("Failed to read input: {}", e))
+                .with_source(e)
+                .with_kind(OrtErrorKind::Io)
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let json: JsonValue = serde_json::from_str(&line)
+            .map_err(|e| OrtError::new(i + 1, line.clone(), format!("Invalid JSON: {}", e)).with_kind(OrtErrorKind::Other))?;
+        rows.push(OrtValue::from(json));
+    }
+
+    let table = OrtValue::Array(rows);
+    Ok(match key {
+        Some(key) => OrtValue::Object(Map::from_iter([(key.to_string(), table)])),
+        None => table,
+    })
+}
+
+/// Write a table as JSON Lines (NDJSON), each item of an array on its own line
+///
+/// Any other value is written as a single line. NaN and infinite numbers are
+/// written as `non_finite` says.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::NonFinite;
+/// use ort_rs::serde_compat::ort_to_ndjson;
+///
+/// let value = ort_rs::from_str("users:id,name:\n1,John\n2,Jane")?;
+/// let mut lines = vec![];
+/// ort_to_ndjson(&value["users"], &mut lines, &NonFinite::Error)?;
+/// assert_eq!(String::from_utf8_lossy(&lines), "{\"id\":1.0,\"name\":\"John\"}\n{\"id\":2.0,\"name\":\"Jane\"}\n");
+/// # Ok(())
+/// # }
+/// ```
+pub fn ort_to_ndjson<W: Write>(value: &OrtValue, mut writer: W, non_finite: &NonFinite) -> OrtResult<()> {
+    let write_error = |e: std::io::Error| {
+        OrtError::new(0, String::new(), format!("Failed to write output: {}", e))
+            .with_source(e)
+            .with_kind(OrtErrorKind::Io)
+    };
+
+    match value {
+        OrtValue::Array(rows) => {
+            for (i, row) in rows.iter().enumerate() {
+                let json = to_json(row, non_finite, &mut format!("[{}]", i))?;
+                writeln!(writer, "{}", json).map_err(write_error)?;
+            }
+        }
+        other => writeln!(writer, "{}", other.to_json_with(non_finite)?).map_err(write_error)?,
+    }
+    writer.flush().map_err(write_error)
+}
+
+fn to_json(value: &OrtValue, non_finite: &NonFinite, path: &mut String) -> OrtResult<JsonValue> {
+    let json = match value {
+        OrtValue::Number(n) => match (serde_json::Number::from_f64(*n), non_finite) {
+            (Some(num), _) => JsonValue::Number(num),
+            (None, NonFinite::Error) => {
+                let at = if path.is_empty() { String::new() } else { format!(" at {}", path) };
+                let token = non_finite_token(*n).unwrap_or_default();
+                return Err(OrtError::new(0, String::new(), format!("Cannot write {}{} as JSON", token, at))
+                    .with_kind(OrtErrorKind::Other));
+            }
+            (None, NonFinite::Null) => JsonValue::Null,
+            (None, NonFinite::String) => JsonValue::String(non_finite_token(*n).unwrap_or_default().to_string()),
+            (None, NonFinite::Replace(replacement)) => replacement.clone(),
+        },
+        OrtValue::Array(arr) => {
+            let mut items = Vec::with_capacity(arr.len());
+            for (i, item) in arr.iter().enumerate() {
+                let len = path.len();
+                path.push_str(&format!("[{}]", i));
+                items.push(to_json(item, non_finite, path)?);
+                path.truncate(len);
+            }
+            JsonValue::Array(items)
+        }
+        OrtValue::Object(obj) => {
+            let mut entries = serde_json::Map::new();
+            for (key, item) in obj {
+                let len = path.len();
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(key);
+                entries.insert(key.clone(), to_json(item, non_finite, path)?);
+                path.truncate(len);
+            }
+            JsonValue::Object(entries)
+        }
+        other => other.clone().into(),
+    };
+    Ok(json)
+}
+
+/// Convert to JSON, writing NaN and infinite numbers as null
+///
+/// Use `TryFrom<&OrtValue>` to fail on them instead, or
+/// `OrtValue::to_json_with` to choose what they become.
 impl From<OrtValue> for JsonValue {
     fn from(ort: OrtValue) -> Self {
         match ort {
@@ -31,6 +195,28 @@ impl From<OrtValue> for JsonValue {
     }
 }
 
+/// Convert to JSON, failing on NaN and infinite numbers, which JSON cannot hold
+///
+/// The `From` conversion silently writes them as null instead. A blanket
+/// implementation rules out `TryFrom<OrtValue>`, so this takes a reference.
+///
+/// # Example
+/// ```
+/// use ort_rs::OrtValue;
+/// use serde_json::{json, Value};
+///
+/// let value = OrtValue::Array(vec![OrtValue::from(1.5), OrtValue::from(f64::INFINITY)]);
+/// assert_eq!(Value::try_from(&value).unwrap_err().message, "Cannot write inf at [1] as JSON");
+/// assert_eq!(Value::from(value), json!([1.5, null]));
+/// ```
+impl TryFrom<&OrtValue> for JsonValue {
+    type Error = OrtError;
+
+    fn try_from(ort: &OrtValue) -> OrtResult<Self> {
+        ort.to_json_with(&NonFinite::Error)
+    }
+}
+
 // Convert serde_json::Value to OrtValue
 impl From<JsonValue> for OrtValue {
     fn from(json: JsonValue) -> Self {
@@ -44,7 +230,7 @@ impl From<JsonValue> for OrtValue {
                 OrtValue::Array(ort_arr)
             }
             JsonValue::Object(obj) => {
-                let ort_obj: HashMap<String, OrtValue> = obj
+                let ort_obj: Map<String, OrtValue> = obj
                     .into_iter()
                     .map(|(k, v)| (k, v.into()))
                     .collect();
@@ -67,7 +253,7 @@ impl From<&JsonValue> for OrtValue {
                 OrtValue::Array(ort_arr)
             }
             JsonValue::Object(obj) => {
-                let ort_obj: HashMap<String, OrtValue> = obj
+                let ort_obj: Map<String, OrtValue> = obj
                     .iter()
                     .map(|(k, v)| (k.clone(), v.into()))
                     .collect();
@@ -76,3 +262,17 @@ impl From<&JsonValue> for OrtValue {
         }
     }
 }
+
+// Convert Diagnostic to serde_json::Value
+impl From<Diagnostic> for JsonValue {
+    fn from(diagnostic: Diagnostic) -> Self {
+        serde_json::json!({
+            "line": diagnostic.line,
+            "column": diagnostic.column,
+            "length": diagnostic.length,
+            "kind": diagnostic.kind,
+            "severity": diagnostic.severity.name(),
+            "message": diagnostic.message,
+        })
+    }
+}
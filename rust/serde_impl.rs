@@ -0,0 +1,139 @@
+use crate::error::{Diagnostic, Severity};
+use crate::ort_value::{Map, OrtValue};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::fmt;
+
+// Serialize OrtValue as its natural data model counterpart
+impl Serialize for OrtValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            OrtValue::Null => serializer.serialize_unit(),
+            OrtValue::Bool(b) => serializer.serialize_bool(*b),
+            OrtValue::Number(n) => {
+                // Whole numbers are written as integers so compact formats can use them
+                if is_integral(*n) {
+                    serializer.serialize_i64(*n as i64)
+                } else {
+                    serializer.serialize_f64(*n)
+                }
+            }
+            OrtValue::String(s) => serializer.serialize_str(s),
+            OrtValue::Array(arr) => serializer.collect_seq(arr),
+            OrtValue::Object(obj) => serializer.collect_map(obj),
+        }
+    }
+}
+
+fn is_integral(n: f64) -> bool {
+    n.fract() == 0.0
+        && n >= i64::MIN as f64
+        && n < i64::MAX as f64
+        && !(n == 0.0 && n.is_sign_negative())
+}
+
+// Deserialize OrtValue from any self-describing format
+impl<'de> Deserialize<'de> for OrtValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(OrtValueVisitor)
+    }
+}
+
+struct OrtValueVisitor;
+
+impl<'de> Visitor<'de> for OrtValueVisitor {
+    type Value = OrtValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an ORT value")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<OrtValue, E> {
+        Ok(OrtValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<OrtValue, E> {
+        Ok(OrtValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<OrtValue, D::Error> {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<OrtValue, E> {
+        Ok(OrtValue::Bool(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<OrtValue, E> {
+        Ok(OrtValue::Number(n as f64))
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<OrtValue, E> {
+        Ok(OrtValue::Number(n as f64))
+    }
+
+    fn visit_f64<E: de::Error>(self, n: f64) -> Result<OrtValue, E> {
+        Ok(OrtValue::Number(n))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<OrtValue, E> {
+        Ok(OrtValue::String(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<OrtValue, E> {
+        Ok(OrtValue::String(s))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<OrtValue, A::Error> {
+        let mut arr = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(item) = seq.next_element()? {
+            arr.push(item);
+        }
+        Ok(OrtValue::Array(arr))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OrtValue, A::Error> {
+        let mut obj = Map::with_capacity(map.size_hint().unwrap_or(0));
+        while let Some((key, value)) = map.next_entry::<String, OrtValue>()? {
+            obj.insert(key, value);
+        }
+        Ok(OrtValue::Object(obj))
+    }
+}
+
+/// Serialize a diagnostic as a flat record
+///
+/// # Example
+/// ```
+/// let error = ort_rs::from_str("users:id,name:\n1,John,extra").unwrap_err();
+/// let json = serde_json::to_value(error.to_diagnostic()).unwrap();
+/// assert_eq!(json["kind"], "value_count_mismatch");
+/// assert_eq!(json["severity"], "error");
+/// assert_eq!(json["column"], 8);
+///
+/// let error = ort_rs::from_str("users:id),name:\n1,John").unwrap_err();
+/// let text = serde_json::to_string(&error.to_diagnostic()).unwrap();
+/// let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+/// assert_eq!(json["kind"], "unmatched_parenthesis");
+/// for field in ["line", "column", "length", "kind", "severity", "message"] {
+///     assert!(json.get(field).is_some());
+/// }
+/// ```
+impl Serialize for Diagnostic {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Diagnostic", 6)?;
+        state.serialize_field("line", &self.line)?;
+        state.serialize_field("column", &self.column)?;
+        state.serialize_field("length", &self.length)?;
+        state.serialize_field("kind", self.kind)?;
+        state.serialize_field("severity", &self.severity)?;
+        state.serialize_field("message", &self.message)?;
+        state.end()
+    }
+}
+
+impl Serialize for Severity {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
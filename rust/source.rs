@@ -0,0 +1,182 @@
+use crate::error::OrtResult;
+use crate::escape::unescape;
+use crate::ort_value::{Map, OrtValue};
+use crate::parser::{is_blank_or_comment, parse_header, parse_ort, starts_section, trim_row, ParseOptions};
+
+/// Lines a value was parsed from, both inclusive and counted from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceSpan {
+    pub line_start: usize,
+    pub line_end: usize,
+}
+
+/// Source spans laid out like the value they describe
+///
+/// A section spans its header and data lines, and a row and everything in it
+/// span the row's line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceTree {
+    Value(SourceSpan),
+    Array(SourceSpan, Vec<SourceTree>),
+    Object(SourceSpan, Map<String, SourceTree>),
+}
+
+impl SourceTree {
+    /// Get the span of this node
+    pub fn span(&self) -> SourceSpan {
+        match self {
+            SourceTree::Value(span) | SourceTree::Array(span, _) | SourceTree::Object(span, _) => *span,
+        }
+    }
+
+    /// Get the node at a dot-separated path, as `OrtValue::get_path` does
+    pub fn get_path(&self, path: &str) -> Option<&SourceTree> {
+        if path.is_empty() {
+            return Some(self);
+        }
+        path.split('.').try_fold(self, |node, segment| match node {
+            SourceTree::Object(_, obj) => obj.get(segment),
+            SourceTree::Array(_, arr) => arr.get(segment.parse::<usize>().ok()?),
+            SourceTree::Value(_) => None,
+        })
+    }
+
+    /// Build the tree of a value written entirely within `span`
+    fn within(value: &OrtValue, span: SourceSpan) -> Self {
+        match value {
+            OrtValue::Array(items) => SourceTree::Array(span, items.iter().map(|item| Self::within(item, span)).collect()),
+            OrtValue::Object(obj) => {
+                SourceTree::Object(span, obj.iter().map(|(key, value)| (key.clone(), Self::within(value, span))).collect())
+            }
+            _ => SourceTree::Value(span),
+        }
+    }
+}
+
+/// Parsed value along with the lines each part of it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrtValueWithSource {
+    pub value: OrtValue,
+    pub source: SourceTree,
+}
+
+impl OrtValueWithSource {
+    /// Get the value and span at a dot-separated path
+    pub fn get_path(&self, path: &str) -> Option<(&OrtValue, SourceSpan)> {
+        Some((self.value.get_path(path)?, self.source.get_path(path)?.span()))
+    }
+}
+
+/// Parse ORT string, also recording the lines every value came from
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::SourceSpan;
+///
+/// let content = "## accounts\nusers:id,name,tags:\n1,John,[admin]\n\n2,Jane,[]\n\nport:\n80\n";
+/// let parsed = ort_rs::from_str_with_source(content)?;
+/// assert_eq!(parsed.value, ort_rs::from_str(content)?);
+///
+/// let span = |line_start, line_end| SourceSpan { line_start, line_end };
+/// assert_eq!(parsed.source.span(), span(1, 8));
+/// assert_eq!(parsed.get_path("users").unwrap().1, span(2, 5));
+/// assert_eq!(parsed.get_path("users.1").unwrap().1, span(5, 5));
+/// let (name, at) = parsed.get_path("users.0.tags.0").unwrap();
+/// assert_eq!((name.as_str(), at), (Some("admin"), span(3, 3)));
+/// assert_eq!(parsed.get_path("port").unwrap().1, span(7, 8));
+///
+/// // A `:fields:` document with one row is that row
+/// let parsed = ort_rs::from_str_with_source("\n:id,name:\n1,John")?;
+/// assert_eq!(parsed.get_path("name").unwrap().1, span(3, 3));
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_str_with_source(s: &str) -> OrtResult<OrtValueWithSource> {
+    let value = parse_ort(s)?;
+    let options = ParseOptions::default();
+
+    // Each section's header line, key, data lines and whether it has fields,
+    // as `parse_ort` reads them
+    let mut sections: Vec<Section> = vec![];
+    let mut line_count = 0;
+    for (idx, line) in s.lines().enumerate() {
+        line_count = idx + 1;
+        let row = trim_row(line, options.delimiter);
+        if is_blank_or_comment(row, &options) {
+            continue;
+        }
+        if !starts_section(row, !sections.is_empty()) {
+            if let Some(section) = sections.last_mut() {
+                section.rows.push(idx + 1);
+            }
+            continue;
+        }
+        if sections.last().is_some_and(|section| section.key.is_none()) {
+            break;
+        }
+        let (key, fields) = parse_header(line.trim(), idx + 1)?;
+        sections.push(Section {
+            header: idx + 1,
+            key: key.map(|key| unescape(key, options.delimiter).into_owned()),
+            rows: vec![],
+            table: !fields.is_empty(),
+        });
+    }
+    let root = SourceSpan { line_start: 1, line_end: line_count.max(1) };
+
+    // A `:fields:` section is the whole document
+    if let Some(section) = sections.iter().find(|section| section.key.is_none()) {
+        let source = match (&value, section.rows.as_slice()) {
+            // A single row is returned as an object
+            (OrtValue::Object(_), &[row]) if section.table => SourceTree::within(&value, SourceSpan { line_start: row, line_end: row }),
+            _ => section.tree(&value).unwrap_or_else(|| SourceTree::within(&value, root)),
+        };
+        return Ok(OrtValueWithSource { value, source });
+    }
+
+    let mut tree = Map::new();
+    if let OrtValue::Object(obj) = &value {
+        for (key, section) in obj {
+            let node = sections
+                .iter()
+                .find(|found| found.key.as_deref() == Some(key.as_str()))
+                .and_then(|found| found.tree(section))
+                .unwrap_or_else(|| SourceTree::within(section, root));
+            tree.insert(key.clone(), node);
+        }
+    }
+    Ok(OrtValueWithSource { value, source: SourceTree::Object(root, tree) })
+}
+
+/// Lines of a section found while scanning the document
+struct Section {
+    header: usize,
+    key: Option<String>,
+    rows: Vec<usize>,
+    table: bool,
+}
+
+impl Section {
+    /// Build the tree of the section's value
+    ///
+    /// Rows of a table each get their line, and the value of a section without
+    /// fields gets its first data line. `None` if the value doesn't match the
+    /// lines.
+    fn tree(&self, value: &OrtValue) -> Option<SourceTree> {
+        let span = SourceSpan { line_start: self.header, line_end: self.rows.last().copied().unwrap_or(self.header) };
+        let line = |row: usize| SourceSpan { line_start: row, line_end: row };
+        match value {
+            OrtValue::Array(items) if self.table && items.len() == self.rows.len() => Some(SourceTree::Array(
+                span,
+                items.iter().zip(&self.rows).map(|(item, &row)| SourceTree::within(item, line(row))).collect(),
+            )),
+            _ if self.table => None,
+            _ => Some(match SourceTree::within(value, line(*self.rows.first()?)) {
+                SourceTree::Value(_) => SourceTree::Value(span),
+                SourceTree::Array(_, items) => SourceTree::Array(span, items),
+                SourceTree::Object(_, obj) => SourceTree::Object(span, obj),
+            }),
+        }
+    }
+}
@@ -1,13 +1,29 @@
+use crate::error::{OrtError, OrtErrorKind};
+use crate::escape::{escape, unescape};
+use crate::fields::{format_fields, FieldSpec};
+use crate::generator::{check_options, generate_field, generate_ort_with, table_fields, write_ort, GenerateOptions};
+use crate::ort_value::Map;
+use crate::parser::{find_section, is_blank_or_comment, is_header, parse_fields, parse_header, parse_ort_with, parse_row, to_field_specs, Context, ParseOptions};
 use crate::{parse_ort, generate_ort, OrtResult, OrtValue};
-use std::fs;
-use std::path::Path;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Parse ORT string into an OrtValue
 ///
 /// # Example
 /// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
 /// let ort_str = "users:id,name:\n1,John\n2,Jane";
-/// let value = ort::from_str(ort_str)?;
+/// let value = ort_rs::from_str(ort_str)?;
+/// # Ok(())
+/// # }
 /// ```
 pub fn from_str(s: &str) -> OrtResult<OrtValue> {
     parse_ort(s)
@@ -15,55 +31,980 @@ pub fn from_str(s: &str) -> OrtResult<OrtValue> {
 
 /// Parse ORT file into an OrtValue
 ///
+/// With the `compression` feature, paths ending in `.gz` are decompressed.
+/// Errors carry the path in `source_file`.
+///
 /// # Example
-/// ```
-/// let value = ort::from_file("data.ort")?;
+/// ```no_run
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_file("data.ort")?;
 /// let name = value["users"][0]["name"].as_str().unwrap();
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ```
+/// let path = std::env::temp_dir().join(format!("ort-source-{}.ort", std::process::id()));
+/// std::fs::write(&path, "users:id,name:\n1,John,extra").unwrap();
+///
+/// let error = ort_rs::from_file(&path).unwrap_err();
+/// assert_eq!(error.source_file.as_deref(), Some(path.as_path()));
+/// assert!(format!("{:#}", error).starts_with(&format!("{}:2:8: Expected 2 values", path.display())));
+/// assert!(error.to_string().starts_with(&format!("{}:2:8:\n  2:8 | 1,John,extra", path.display())));
+///
+/// std::fs::remove_file(&path).unwrap();
+///
+/// // The io::Error of a failed read is kept as the source
+/// use std::error::Error;
+/// let error = ort_rs::from_file(&path).unwrap_err();
+/// let io_error = error.source().unwrap().downcast_ref::<std::io::Error>().unwrap();
+/// assert_eq!(io_error.kind(), std::io::ErrorKind::NotFound);
 /// ```
 pub fn from_file<P: AsRef<Path>>(path: P) -> OrtResult<OrtValue> {
-    let content = fs::read_to_string(path.as_ref())
-        .map_err(|e| crate::error::OrtError {
-            line: 0,
-            code: String::new(),
-            message: format!("Failed to read file: {}", e),
-        })?;
-    parse_ort(&content)
+    from_file_with(path, &ParseOptions::default())
+}
+
+/// Parse ORT file with the given options
+///
+/// With `allow_includes`, lines of the form `#include <path>` or
+/// `@include path` are replaced by the sections of the named file, resolved
+/// against this file's directory. Included sections are merged by
+/// `duplicate_sections`. A file that ends up including itself, or an include
+/// outside the including file's directory, is an error. Errors inside an
+/// included file carry that file in `source_file`.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{OrtErrorKind, ParseOptions};
+///
+/// let dir = std::env::temp_dir().join(format!("ort-include-{}", std::process::id()));
+/// std::fs::create_dir_all(dir.join("shared")).unwrap();
+/// std::fs::write(dir.join("main.ort"), "#include <shared/users.ort>\n\nport:\n80").unwrap();
+/// std::fs::write(dir.join("shared/users.ort"), "@include roles.ort\nusers:id,name:\n1,John").unwrap();
+/// std::fs::write(dir.join("shared/roles.ort"), "roles:\n[admin,guest]").unwrap();
+///
+/// let options = ParseOptions::new().allow_includes(true);
+/// let value = ort_rs::from_file_with(dir.join("main.ort"), &options)?;
+/// assert_eq!(value["users"][0]["name"].as_str(), Some("John"));
+/// assert_eq!(value["roles"][1].as_str(), Some("guest"));
+/// assert_eq!(value["port"].as_i64(), Some(80));
+///
+/// // Without allow_includes, include lines are comments
+/// assert!(ort_rs::from_file(dir.join("main.ort"))?.get("users").is_none());
+///
+/// // A file including itself is reported where the loop closes
+/// std::fs::write(dir.join("shared/roles.ort"), "#include roles.ort").unwrap();
+/// let error = ort_rs::from_file_with(dir.join("main.ort"), &options).unwrap_err();
+/// assert_eq!(error.kind, OrtErrorKind::CircularInclude);
+/// assert_eq!(error.message, "Circular include of 'roles.ort'");
+/// assert_eq!(error.source_file.as_deref(), Some(dir.join("shared/roles.ort").as_path()));
+///
+/// // Includes may not leave the including file's directory
+/// std::fs::write(dir.join("shared/roles.ort"), "#include ../main.ort").unwrap();
+/// let error = ort_rs::from_file_with(dir.join("main.ort"), &options).unwrap_err();
+/// assert_eq!(error.kind, OrtErrorKind::IncludeOutsideDirectory);
+///
+/// // Parsing a string has no directory to include from, so the line is a comment
+/// let content = "#include <shared/users.ort>\nport:\n80";
+/// assert_eq!(ort_rs::parse_ort_with(content, &options)?["port"].as_i64(), Some(80));
+///
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_file_with<P: AsRef<Path>>(path: P, options: &ParseOptions) -> OrtResult<OrtValue> {
+    read_file(path.as_ref(), options).map_err(|e| match e.source_file {
+        Some(_) => e,
+        None => e.with_source_file(path),
+    })
+}
+
+fn read_file(path: &Path, options: &ParseOptions) -> OrtResult<OrtValue> {
+    let file = File::open(path)
+        .map_err(|e| io_error("Failed to read file", e))?;
+
+    #[cfg(feature = "compression")]
+    let bytes = if crate::gzip_compat::is_gzip_path(path) {
+        read_bytes(flate2::read::GzDecoder::new(file))?
+    } else {
+        read_bytes(file)?
+    };
+    #[cfg(not(feature = "compression"))]
+    let bytes = read_bytes(file)?;
+
+    // Includes resolve against this file, and must not lead back to it
+    let mut options = options.clone();
+    options.include_guard.insert(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    options.base_dir = Some(path.parent().map(Path::to_path_buf).unwrap_or_default());
+    parse_ort_with(&decode(&bytes)?, &options)
+}
+
+/// Parse only the section named `key` of an ORT file, or `None` if it has none
+///
+/// The file is read line by line as UTF-8, and only the requested section's
+/// rows are parsed and kept, so memory grows with that section rather than
+/// with the file. Headers and includes are read as `parse_section` reads them.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let path = std::env::temp_dir().join(format!("ort-section-{}.ort", std::process::id()));
+/// let rows: String = (0..1000).map(|i| format!("{},user{}\n", i, i)).collect();
+/// std::fs::write(&path, format!("users:id,name:\n{}\nmeta:version:\n3\n", rows)).unwrap();
+///
+/// let meta = ort_rs::section_from_file(&path, "meta")?.unwrap();
+/// assert_eq!(meta[0]["version"].as_i64(), Some(3));
+/// assert_eq!(ort_rs::section_from_file(&path, "users")?.unwrap()[999]["name"].as_str(), Some("user999"));
+/// assert_eq!(ort_rs::section_from_file(&path, "items")?, None);
+///
+/// std::fs::remove_file(&path).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn section_from_file<P: AsRef<Path>>(path: P, key: &str) -> OrtResult<Option<OrtValue>> {
+    section_from_file_with(path, key, &ParseOptions::default())
+}
+
+/// Parse only the section named `key` of an ORT file with the given options
+pub fn section_from_file_with<P: AsRef<Path>>(path: P, key: &str, options: &ParseOptions) -> OrtResult<Option<OrtValue>> {
+    read_file_section(path.as_ref(), key, options).map_err(|e| match e.source_file {
+        Some(_) => e,
+        None => e.with_source_file(path),
+    })
+}
+
+fn read_file_section(path: &Path, key: &str, options: &ParseOptions) -> OrtResult<Option<OrtValue>> {
+    let read_error = |e: io::Error| io_error("Failed to read file", e);
+    let file = File::open(path).map_err(read_error)?;
+
+    #[cfg(feature = "compression")]
+    let reader: Box<dyn BufRead> = if crate::gzip_compat::is_gzip_path(path) {
+        Box::new(BufReader::new(flate2::read::GzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    #[cfg(not(feature = "compression"))]
+    let reader = BufReader::new(file);
+
+    // Includes resolve against this file, and must not lead back to it
+    let mut options = options.clone();
+    options.include_guard.insert(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    options.base_dir = Some(path.parent().map(Path::to_path_buf).unwrap_or_default());
+
+    let lines = reader.lines().enumerate().map(|(i, line)| match line {
+        Ok(line) if i == 0 => Ok(line.strip_prefix('\u{feff}').map(str::to_string).unwrap_or(line)),
+        Ok(line) => Ok(line),
+        Err(e) => Err(read_error(e)),
+    });
+    find_section(lines, key, &options)
+}
+
+/// Parse ORT from any reader into an OrtValue
+///
+/// The input is decoded like `from_slice` decodes it.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use std::io::Cursor;
+///
+/// let value = ort_rs::from_reader(Cursor::new("users:id,name:\n1,John"))?;
+/// assert_eq!(value["users"][0]["name"].as_str(), Some("John"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_reader<R: Read>(reader: R) -> OrtResult<OrtValue> {
+    from_slice(&read_bytes(reader)?)
+}
+
+fn read_bytes<R: Read>(mut reader: R) -> OrtResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)
+        .map_err(|e| io_error("Failed to read input", e))?;
+    Ok(bytes)
+}
+
+/// Parse ORT bytes into an OrtValue
+///
+/// A UTF-8 byte order mark is skipped, and UTF-16 input is detected by its
+/// byte order mark and transcoded. Anything else must be valid UTF-8.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_slice(b"\xEF\xBB\xBFusers:id,name:\n1,John")?;
+/// assert_eq!(value["users"][0]["name"].as_str(), Some("John"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_slice(bytes: &[u8]) -> OrtResult<OrtValue> {
+    parse_ort(&decode(bytes)?)
+}
+
+/// Get the UTF-8 or UTF-16 byte order mark `bytes` start with, if any
+fn byte_order_mark(bytes: &[u8]) -> &[u8] {
+    [&b"\xEF\xBB\xBF"[..], b"\xFF\xFE", b"\xFE\xFF"]
+        .into_iter()
+        .find(|bom| bytes.starts_with(bom))
+        .unwrap_or_default()
+}
+
+/// Decode ORT bytes into text, as described for `from_slice`
+fn decode(bytes: &[u8]) -> OrtResult<Cow<'_, str>> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return decode_utf8(rest, 3).map(Cow::Borrowed);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return decode_utf16(rest, u16::from_le_bytes).map(Cow::Owned);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return decode_utf16(rest, u16::from_be_bytes).map(Cow::Owned);
+    }
+    decode_utf8(bytes, 0).map(Cow::Borrowed)
+}
+
+fn decode_utf8(bytes: &[u8], bom_len: usize) -> OrtResult<&str> {
+    std::str::from_utf8(bytes)
+        .map_err(|e| {
+            let valid = e.valid_up_to();
+            let line = bytes[..valid].iter().filter(|&&b| b == b'\n').count() + 1;
+            OrtError::new(line, String::new(), format!("Invalid UTF-8 at byte offset {}", valid + bom_len))
+                .with_kind(OrtErrorKind::Encoding)
+                .with_source(e)
+        })
+}
+
+fn decode_utf16(bytes: &[u8], to_unit: fn([u8; 2]) -> u16) -> OrtResult<String> {
+    if !bytes.len().is_multiple_of(2) {
+        let message = format!("Invalid UTF-16: odd length of {} bytes", bytes.len() + 2);
+        return Err(OrtError::new(0, String::new(), message).with_kind(OrtErrorKind::Encoding));
+    }
+
+    let units = bytes.chunks_exact(2).map(|pair| to_unit([pair[0], pair[1]]));
+    let mut result = String::with_capacity(bytes.len() / 2);
+    let mut offset = 0;
+    for ch in char::decode_utf16(units) {
+        match ch {
+            Ok(ch) => {
+                offset += ch.len_utf16() * 2;
+                result.push(ch);
+            }
+            Err(_) => {
+                let line = result.matches('\n').count() + 1;
+                let message = format!("Invalid UTF-16 at byte offset {}", offset + 2);
+                return Err(OrtError::new(line, String::new(), message).with_kind(OrtErrorKind::Encoding));
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// How a key defined more than once is resolved
+///
+/// Used by `from_dir` for top-level keys defined by more than one file, and
+/// by `ParseOptions` for repeated sections and inline object keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    /// Fail with an error naming both definitions
+    #[default]
+    Error,
+    /// Keep the first value
+    FirstWins,
+    /// Keep the last value
+    LastWins,
+    /// Merge objects recursively; other values are taken from the last definition
+    DeepMerge,
+    /// Append the rows of the last array to the first; other values are an error
+    Concatenate,
+}
+
+/// Options for `from_dir_with`
+#[derive(Debug, Clone, Default)]
+pub struct DirOptions {
+    /// How to resolve keys defined by more than one file
+    pub conflict: ConflictPolicy,
+    /// Also read `.ort` files in subdirectories
+    pub recursive: bool,
+}
+
+/// Parse every `.ort` file in a directory and merge them into one object
+///
+/// Files are read in path order and each must hold an object at the top
+/// level. A key defined by two files is an error; see `from_dir_with` to
+/// change that.
+///
+/// # Example
+/// ```no_run
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let config = ort_rs::from_dir("conf.d")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_dir<P: AsRef<Path>>(path: P) -> OrtResult<OrtValue> {
+    from_dir_with(path, &DirOptions::default())
+}
+
+/// Parse every `.ort` file in a directory and merge them with the given options
+///
+/// Errors from a file carry it in `source_file`.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{ConflictPolicy, DirOptions};
+///
+/// let dir = std::env::temp_dir().join(format!("ort-dir-{}", std::process::id()));
+/// std::fs::create_dir_all(&dir).unwrap();
+///
+/// // An empty directory is an empty object
+/// assert_eq!(ort_rs::from_dir(&dir)?, ort_rs::OrtValue::Object(Default::default()));
+///
+/// std::fs::write(dir.join("10-base.ort"), "server:\n(host:localhost,port:80)\n").unwrap();
+/// std::fs::write(dir.join("20-local.ort"), "server:\n(port:8080)\n").unwrap();
+///
+/// let error = ort_rs::from_dir(&dir).unwrap_err();
+/// assert!(error.message.contains("10-base.ort") && error.message.contains("20-local.ort"));
+///
+/// let options = DirOptions { conflict: ConflictPolicy::DeepMerge, ..Default::default() };
+/// let config = ort_rs::from_dir_with(&dir, &options)?;
+/// assert_eq!(config["server"]["host"].as_str(), Some("localhost"));
+/// assert_eq!(config["server"]["port"].as_i64(), Some(8080));
+///
+/// std::fs::write(dir.join("30-broken.ort"), "server:port:\n1,2\n").unwrap();
+/// let error = ort_rs::from_dir_with(&dir, &options).unwrap_err();
+/// assert!(error.source_file.unwrap().ends_with("30-broken.ort"));
+///
+/// std::fs::remove_dir_all(&dir).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn from_dir_with<P: AsRef<Path>>(path: P, options: &DirOptions) -> OrtResult<OrtValue> {
+    let mut files = Vec::new();
+    collect_ort_files(path.as_ref(), options.recursive, &mut files)
+        .map_err(|e| io_error(&format!("Failed to read directory '{}'", path.as_ref().display()), e))?;
+    files.sort();
+
+    let mut result = Map::new();
+    let mut origins: HashMap<String, &Path> = HashMap::new();
+
+    for file in &files {
+        let obj = match from_file(file)? {
+            OrtValue::Object(obj) => obj,
+            other => {
+                let message = format!("Expected an object at the top level but found {}", other.type_name());
+                return Err(OrtError::new(0, String::new(), message).with_source_file(file));
+            }
+        };
+
+        for (key, value) in obj {
+            let Some(existing) = result.get_mut(&key) else {
+                origins.insert(key.clone(), file);
+                result.insert(key, value);
+                continue;
+            };
+
+            if !resolve_conflict(existing, value, options.conflict) {
+                let message = format!(
+                    "Key '{}' is defined in both '{}' and '{}'",
+                    key,
+                    origins[&key].display(),
+                    file.display()
+                );
+                return Err(OrtError::new(0, String::new(), message).with_kind(OrtErrorKind::DuplicateKey));
+            }
+        }
+    }
+
+    Ok(OrtValue::Object(result))
+}
+
+fn collect_ort_files(dir: &Path, recursive: bool, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                collect_ort_files(&path, recursive, files)?;
+            }
+        } else if path.extension().is_some_and(|ext| ext == "ort") {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Combine the new value of a repeated key into the existing one, returning
+/// false if the policy rejects the repetition
+pub(crate) fn resolve_conflict(existing: &mut OrtValue, value: OrtValue, policy: ConflictPolicy) -> bool {
+    match policy {
+        ConflictPolicy::Error => return false,
+        ConflictPolicy::FirstWins => {}
+        ConflictPolicy::LastWins => *existing = value,
+        ConflictPolicy::DeepMerge => existing.merge(value),
+        ConflictPolicy::Concatenate => match (existing, value) {
+            (OrtValue::Array(arr), OrtValue::Array(rows)) => arr.extend(rows),
+            _ => return false,
+        },
+    }
+    true
 }
 
 /// Convert an OrtValue to ORT string
 ///
 /// # Example
 /// ```
-/// use std::collections::HashMap;
-/// use ort::OrtValue;
+/// use ort_rs::Map;
+/// use ort_rs::OrtValue;
 ///
-/// let mut obj = HashMap::new();
+/// let mut obj = Map::new();
 /// obj.insert("id".to_string(), OrtValue::from(1));
 /// obj.insert("name".to_string(), OrtValue::from("John"));
-/// let ort_str = ort::to_string(&OrtValue::Object(obj));
+/// let ort_str = ort_rs::to_string(&OrtValue::Object(obj));
+/// ```
+///
+/// Colons in strings, keys and field names are escaped as `\:` so they read
+/// back unchanged:
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_str("links:url,at\\:time:\nhttps\\://example.com\\:,12\\:30")?;
+/// assert_eq!(value["links"][0]["url"].as_str(), Some("https://example.com:"));
+/// assert_eq!(value["links"][0]["at:time"].as_str(), Some("12:30"));
+/// assert_eq!(ort_rs::from_str(&ort_rs::to_string(&value))?, value);
+/// # Ok(())
+/// # }
 /// ```
 pub fn to_string(value: &OrtValue) -> String {
     generate_ort(value)
 }
 
-/// Convert an OrtValue to ORT string and write to file
+/// Convert an OrtValue to ORT string with the given options
 ///
 /// # Example
 /// ```
-/// use std::collections::HashMap;
-/// use ort::OrtValue;
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{GenerateOptions, TrailingNewline};
+///
+/// let value = ort_rs::from_str("users:id,name:\n1,John")?;
+/// let options = GenerateOptions { trailing_newline: TrailingNewline::Always, ..Default::default() };
+/// assert_eq!(ort_rs::to_string_with(&value, &options)?, "users:id,name:\n1,John\n");
+///
+/// let path = std::env::temp_dir().join(format!("ort-to-file-with-{}.ort", std::process::id()));
+/// ort_rs::to_file_with(&value, &path, &options)?;
+/// assert_eq!(std::fs::read_to_string(&path).unwrap(), "users:id,name:\n1,John\n");
+/// std::fs::remove_file(&path).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_string_with(value: &OrtValue, options: &GenerateOptions) -> OrtResult<String> {
+    generate_ort_with(value, options)
+}
+
+/// Convert an OrtValue to ORT string and write to file
+///
+/// With the `compression` feature, paths ending in `.gz` are compressed.
+///
+/// # Example
+/// ```no_run
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::Map;
+/// use ort_rs::OrtValue;
 ///
-/// let mut obj = HashMap::new();
+/// let mut obj = Map::new();
 /// obj.insert("id".to_string(), OrtValue::from(1));
 /// let value = OrtValue::Object(obj);
-/// ort::to_file(&value, "output.ort")?;
+/// ort_rs::to_file(&value, "output.ort")?;
+/// # Ok(())
+/// # }
 /// ```
 pub fn to_file<P: AsRef<Path>>(value: &OrtValue, path: P) -> OrtResult<()> {
-    let ort_string = generate_ort(value);
-    fs::write(path.as_ref(), ort_string)
-        .map_err(|e| crate::error::OrtError {
-            line: 0,
-            code: String::new(),
-            message: format!("Failed to write file: {}", e),
+    to_file_with(value, path, &GenerateOptions::default())
+}
+
+/// Convert an OrtValue to ORT string with the given options and write to file
+pub fn to_file_with<P: AsRef<Path>>(value: &OrtValue, path: P, options: &GenerateOptions) -> OrtResult<()> {
+    // Check before creating the file, so a failure leaves no empty file behind
+    check_options(value, options)?;
+    let file = File::create(path.as_ref())
+        .map_err(|e| io_error("Failed to write file", e))?;
+
+    #[cfg(feature = "compression")]
+    if crate::gzip_compat::is_gzip_path(path.as_ref()) {
+        return crate::gzip_compat::to_gzip_writer_with(value, file, crate::gzip_compat::DEFAULT_LEVEL, options);
+    }
+
+    to_writer_with(value, file, options)
+}
+
+/// Append rows to the end of a table section in an ORT file
+///
+/// Only headers are parsed to find the section. Each row must be an object
+/// with exactly the section's fields, which are written in header order, and
+/// cells under typed fields must hold their type. The file is decoded as
+/// `from_file` decodes it, and keeps its line endings, encoding and byte
+/// order mark.
+///
+/// Appending to the last section just adds lines at the end of the file. A
+/// section further up is the slow path: everything after it is rewritten. A
+/// missing file or section is created, with a header made of the first row's
+/// keys. Rows are checked before anything is written, and a rewritten file
+/// is replaced atomically, as with `to_file_atomic`. A lock on
+/// `.<name>.lock` next to the file is held while it is read and written, so
+/// concurrent appends don't interleave.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{Map, OrtValue};
+///
+/// let path = std::env::temp_dir().join(format!("ort-append-{}.ort", std::process::id()));
+/// std::fs::write(&path, "events:id int,level,message:\n1,info,started\n\ntotals:\n(count:1)").unwrap();
+///
+/// let row = |id: i64, level: &str, message: &str| OrtValue::Object(Map::from([
+///     ("id".to_string(), OrtValue::from(id)),
+///     ("level".to_string(), OrtValue::from(level)),
+///     ("message".to_string(), OrtValue::from(message)),
+/// ]));
+/// // A section in the middle of the file
+/// ort_rs::append_rows(&path, "events", &[row(2, "warn", "disk 90%"), row(3, "info", "a, b")])?;
+/// assert_eq!(
+///     std::fs::read_to_string(&path).unwrap(),
+///     "events:id int,level,message:\n1,info,started\n2,warn,disk 90%\n3,info,a\\, b\n\ntotals:\n(count:1)",
+/// );
+///
+/// // A section that doesn't exist yet is added at the end, and then appended to
+/// ort_rs::append_rows(&path, "audit", &[row(1, "info", "login")])?;
+/// ort_rs::append_rows(&path, "audit", &[row(2, "warn", "logout")])?;
+/// assert!(std::fs::read_to_string(&path).unwrap().ends_with(
+///     "(count:1)\n\naudit:id,level,message:\n1,info,login\n2,warn,logout\n",
+/// ));
+///
+/// // Rows with other fields, or a value that doesn't fit a typed field, are rejected
+/// let extra = ort_rs::from_str(":id,level,message,user:\n4,info,x,root")?;
+/// let error = ort_rs::append_rows(&path, "events", &[extra]).unwrap_err();
+/// assert_eq!(error.message, "Row 1 has fields id,level,message,user but section 'events' has id,level,message");
+/// let mut bad = row(4, "info", "x");
+/// bad.as_object_mut().unwrap().insert("id".to_string(), OrtValue::from("four"));
+/// let error = ort_rs::append_rows(&path, "events", &[bad]).unwrap_err();
+/// assert_eq!(error.message, "Column 'id' expects int but found 'four'");
+///
+/// let value = ort_rs::from_file(&path)?;
+/// assert_eq!(value["events"].as_array().unwrap().len(), 3);
+/// assert_eq!(value["audit"][1]["message"].as_str(), Some("logout"));
+/// std::fs::remove_file(&path).unwrap();
+///
+/// // Rejected rows leave a missing file missing
+/// assert!(ort_rs::append_rows(&path, "events", &[OrtValue::from(1)]).is_err());
+/// assert!(!path.exists());
+/// std::fs::remove_file(path.with_file_name(format!(".ort-append-{}.ort.lock", std::process::id()))).unwrap();
+/// # Ok(())
+/// # }
+/// ```
+pub fn append_rows<P: AsRef<Path>>(path: P, section_key: &str, rows: &[OrtValue]) -> OrtResult<()> {
+    append_to_file(path.as_ref(), section_key, rows).map_err(|e| e.with_source_file(path))
+}
+
+fn append_to_file(path: &Path, section_key: &str, rows: &[OrtValue]) -> OrtResult<()> {
+    let append_error = |e: io::Error| io_error("Failed to append to file", e);
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let (file_name, dir) = file_parts(path, "Failed to append to file")?;
+    let lock = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(dir.join(format!(".{}.lock", file_name.to_string_lossy())))
+        .map_err(append_error)?;
+    lock.lock().map_err(append_error)?;
+
+    let bytes = match fs::read(path) {
+        Ok(bytes) => Some(bytes),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(append_error(e)),
+    };
+    // Decode as `from_file` does, and write back in the file's own encoding
+    let bom = bytes.as_deref().map(byte_order_mark).unwrap_or_default();
+    let content = bytes.as_deref().map(decode).transpose()?;
+    let (appended, end) = appended_rows(content.as_deref().unwrap_or_default(), section_key, rows)?;
+
+    match content {
+        // Rows for the last section go at the end of the file
+        Some(content) if end == content.len() => OpenOptions::new()
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(&encode(&appended, bom)))
+            .map_err(append_error),
+        content => {
+            let content = content.unwrap_or_default();
+            write_atomic(path, &AtomicWriteOptions::default(), |mut file| {
+                file.write_all(bom)
+                    .and_then(|_| file.write_all(&encode(&content[..end], bom)))
+                    .and_then(|_| file.write_all(&encode(&appended, bom)))
+                    .and_then(|_| file.write_all(&encode(&content[end..], bom)))
+                    .map_err(append_error)
+            })
+        }
+    }
+}
+
+/// Encode text like a file starting with the byte order mark `bom`
+fn encode<'a>(text: &'a str, bom: &[u8]) -> Cow<'a, [u8]> {
+    let to_bytes: fn(u16) -> [u8; 2] = match bom {
+        b"\xFF\xFE" => u16::to_le_bytes,
+        b"\xFE\xFF" => u16::to_be_bytes,
+        _ => return Cow::Borrowed(text.as_bytes()),
+    };
+    Cow::Owned(text.encode_utf16().flat_map(to_bytes).collect())
+}
+
+/// Generate the lines for `rows` and the byte offset to insert them at
+fn appended_rows(content: &str, section_key: &str, rows: &[OrtValue]) -> OrtResult<(String, usize)> {
+    let options = ParseOptions::default();
+
+    // Find the section header, and the end of its last data line
+    let mut section = None;
+    let mut offset = 0;
+    for (i, raw) in content.split_inclusive('\n').enumerate() {
+        let line = raw.trim();
+        let start = offset;
+        offset += raw.len();
+        if is_blank_or_comment(line, &options) {
+            continue;
+        }
+        if is_header(line) {
+            if section.is_some() {
+                break;
+            }
+            let (key, fields_str) = parse_header(line, i + 1)?;
+            if key.is_some_and(|key| unescape(key, options.delimiter) == section_key) {
+                let fields = to_field_specs(&parse_fields(fields_str, line, i + 1, &options)?, options.delimiter);
+                section = Some((fields, i + 1, start + raw.len()));
+            }
+        } else if let Some((_, last_line, end)) = &mut section {
+            *last_line = i + 1;
+            *end = start + raw.len();
+        }
+    }
+
+    let newline = if content.contains("\r\n") { "\r\n" } else { "\n" };
+    let mut appended = String::new();
+    let (fields, last_line, end) = match section {
+        Some(section) => section,
+        None => {
+            // Start a new section at the end of the file
+            let fields = match rows[0].as_object() {
+                Some(first) => table_fields(std::iter::once(first), &GenerateOptions::default()),
+                None => vec![],
+            };
+            if !content.is_empty() {
+                appended.push_str(newline);
+            }
+            appended.push_str(&format!("{}:{}:", escape(section_key, options.delimiter), format_fields(&fields, options.delimiter)));
+            appended.push_str(newline);
+            (fields, content.lines().count() + 2, content.len())
+        }
+    };
+    if fields.is_empty() {
+        return Err(OrtError::new(0, String::new(), format!("Section '{}' has no fields to append rows to", section_key)));
+    }
+
+    if !content[..end].is_empty() && !content[..end].ends_with('\n') {
+        appended.insert_str(0, newline);
+    }
+    for (i, row) in rows.iter().enumerate() {
+        let obj = row.as_object().filter(|obj| obj.len() == fields.len() && fields.iter().all(|f| obj.contains_key(f.name())));
+        let Some(obj) = obj else {
+            let mut keys: Vec<&str> = row.as_object().map(|obj| obj.keys().map(String::as_str).collect()).unwrap_or_default();
+            keys.sort_unstable();
+            let names: Vec<&str> = fields.iter().map(FieldSpec::name).collect();
+            return Err(OrtError::new(
+                0,
+                String::new(),
+                format!("Row {} has fields {} but section '{}' has {}", i + 1, keys.join(","), section_key, names.join(",")),
+            ).with_kind(OrtErrorKind::ValueCountMismatch));
+        };
+
+        let line = fields
+            .iter()
+            .map(|field| generate_field(field, &obj[field.name()], &GenerateOptions::default()))
+            .collect::<Vec<_>>()
+            .join(",");
+        // Check the row reads back, which catches values that don't fit a typed field
+        parse_row(&line, last_line + i + 1, &fields, &mut Context::default())?;
+        appended.push_str(&line);
+        appended.push_str(newline);
+    }
+
+    Ok((appended, end))
+}
+
+/// Asynchronously parse ORT file into an OrtValue
+///
+/// Only the file IO is asynchronous; parsing happens on the buffered content.
+/// Files are decoded and decompressed as `from_file` does, so errors match.
+///
+/// # Example
+/// ```no_run
+/// # async fn run() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_file_async("data.ort").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn from_file_async<P: AsRef<Path>>(path: P) -> OrtResult<OrtValue> {
+    let bytes = tokio::fs::read(path.as_ref())
+        .await
+        .map_err(|e| io_error("Failed to read file", e));
+
+    #[cfg(feature = "compression")]
+    let bytes = match bytes {
+        Ok(bytes) if crate::gzip_compat::is_gzip_path(path.as_ref()) => read_bytes(flate2::read::GzDecoder::new(&bytes[..])),
+        bytes => bytes,
+    };
+
+    bytes.and_then(|bytes| from_slice(&bytes)).map_err(|e| e.with_source_file(path))
+}
+
+/// Asynchronously parse ORT from any async reader into an OrtValue
+///
+/// The input is read to its end, then decoded and parsed like `from_reader`
+/// does, so errors match.
+///
+/// # Example
+/// ```
+/// # async fn run() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_reader_async("users:id,name:\n1,John".as_bytes()).await?;
+/// assert_eq!(value["users"][0]["name"].as_str(), Some("John"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn from_reader_async<R: tokio::io::AsyncRead + Unpin>(mut reader: R) -> OrtResult<OrtValue> {
+    use tokio::io::AsyncReadExt;
+
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)
+        .await
+        .map_err(|e| io_error("Failed to read input", e))?;
+    from_slice(&bytes)
+}
+
+/// Asynchronously convert an OrtValue to ORT string and write to file
+///
+/// The output is generated in memory first, compressed for `.gz` paths as
+/// `to_file` does, and then written.
+///
+/// # Example
+/// ```no_run
+/// # async fn run() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_str("users:id,name:\n1,John")?;
+/// ort_rs::to_file_async(&value, "output.ort").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "tokio")]
+pub async fn to_file_async<P: AsRef<Path>>(value: &OrtValue, path: P) -> OrtResult<()> {
+    let options = GenerateOptions::default();
+    let mut bytes = Vec::new();
+
+    #[cfg(feature = "compression")]
+    if crate::gzip_compat::is_gzip_path(path.as_ref()) {
+        crate::gzip_compat::to_gzip_writer_with(value, &mut bytes, crate::gzip_compat::DEFAULT_LEVEL, &options)?;
+    } else {
+        to_writer_with(value, &mut bytes, &options)?;
+    }
+    #[cfg(not(feature = "compression"))]
+    to_writer_with(value, &mut bytes, &options)?;
+
+    tokio::fs::write(path.as_ref(), bytes)
+        .await
+        .map_err(|e| io_error("Failed to write file", e))
+}
+
+/// Options for `to_file_atomic`
+#[derive(Debug, Clone)]
+pub struct AtomicWriteOptions {
+    /// Flush the written file to disk before it replaces the target
+    pub sync: bool,
+    /// Flush the parent directory after the rename (Unix only)
+    pub sync_dir: bool,
+    /// Copy the permissions of an existing target file
+    pub preserve_permissions: bool,
+}
+
+impl Default for AtomicWriteOptions {
+    fn default() -> Self {
+        Self {
+            sync: true,
+            sync_dir: true,
+            preserve_permissions: true,
+        }
+    }
+}
+
+/// Convert an OrtValue to ORT and atomically replace a file with it
+///
+/// The output is written to a temporary file in the same directory which is
+/// then renamed over `path`, so readers see either the old or the new complete
+/// document. The temporary file is removed if anything fails.
+///
+/// # Example
+/// ```no_run
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::AtomicWriteOptions;
+///
+/// let value = ort_rs::from_str("state:id,status:\n1,running")?;
+/// ort_rs::to_file_atomic(&value, "state.ort", &AtomicWriteOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_file_atomic<P: AsRef<Path>>(value: &OrtValue, path: P, options: &AtomicWriteOptions) -> OrtResult<()> {
+    write_atomic(path.as_ref(), options, |file| to_writer(value, file))
+}
+
+/// Write a temporary file in the same directory as `path`, then rename it over `path`
+fn write_atomic<F>(path: &Path, options: &AtomicWriteOptions, write: F) -> OrtResult<()>
+where
+    F: FnOnce(&File) -> OrtResult<()>,
+{
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let (file_name, dir) = file_parts(path, "Failed to write file")?;
+    let tmp_path = dir.join(format!(
+        ".{}.{}.{}.tmp",
+        file_name.to_string_lossy(),
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&tmp_path)
+        .map_err(|e| io_error("Failed to write file", e))?;
+
+    let result = write(&file).and_then(|_| {
+        replace_file(&file, &tmp_path, path, dir, options).map_err(|e| io_error("Failed to write file", e))
+    });
+
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Split a file path into its name and directory, `.` for a bare name
+fn file_parts<'a>(path: &'a Path, action: &str) -> OrtResult<(&'a OsStr, &'a Path)> {
+    let file_name = path.file_name().ok_or_else(|| {
+        OrtError::new(0, String::new(), format!("{}: '{}' is not a file path", action, path.display()))
+            .with_kind(OrtErrorKind::Io)
+    })?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    Ok((file_name, dir))
+}
+
+fn replace_file(file: &File, tmp_path: &Path, path: &Path, dir: &Path, options: &AtomicWriteOptions) -> io::Result<()> {
+    if options.preserve_permissions {
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(tmp_path, metadata.permissions())?;
+        }
+    }
+
+    if options.sync {
+        file.sync_all()?;
+    }
+
+    rename(tmp_path, path)?;
+
+    #[cfg(unix)]
+    if options.sync_dir {
+        File::open(dir)?.sync_all()?;
+    }
+    #[cfg(not(unix))]
+    let _ = dir;
+
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn rename(from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)
+}
+
+// Replacing a file on Windows fails while another process (often a virus
+// scanner or indexer) briefly holds it open, so retry for a short while
+#[cfg(windows)]
+fn rename(from: &Path, to: &Path) -> io::Result<()> {
+    let mut attempts = 0;
+    loop {
+        match fs::rename(from, to) {
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied && attempts < 10 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(10 * attempts));
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Convert an OrtValue to ORT and write it to any writer
+///
+/// Output is written section by section and row by row instead of being
+/// built as a single string first.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// let value = ort_rs::from_str("users:id,name:\n1,John")?;
+///
+/// let mut buffer = Vec::new();
+/// ort_rs::to_writer(&value, &mut buffer)?;
+/// assert_eq!(String::from_utf8(buffer).unwrap(), ort_rs::to_string(&value));
+/// # Ok(())
+/// # }
+/// ```
+pub fn to_writer<W: Write>(value: &OrtValue, writer: W) -> OrtResult<()> {
+    to_writer_with(value, writer, &GenerateOptions::default())
+}
+
+/// Convert an OrtValue to ORT with the given options and write it to any writer
+pub fn to_writer_with<W: Write>(value: &OrtValue, writer: W, options: &GenerateOptions) -> OrtResult<()> {
+    check_options(value, options)?;
+    let mut adapter = IoAdapter {
+        inner: io::BufWriter::new(writer),
+        error: None,
+    };
+
+    let result = write_ort(&mut adapter, value, options)
+        .map_err(|_| adapter.error.take().unwrap_or_else(|| io::Error::other("formatter error")))
+        .and_then(|_| adapter.inner.flush());
+
+    result.map_err(|e| io_error("Failed to write output", e))
+}
+
+// Io error whose message ends with the cause
+fn io_error(action: &str, e: io::Error) -> OrtError {
+    OrtError::new(0, String::new(), format!("{}: {}", action, e))
+        .with_kind(OrtErrorKind::Io)
+        .with_source(e)
+}
+
+// Bridge fmt::Write output to an io::Write, keeping the io::Error
+struct IoAdapter<W: Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
         })
+    }
 }
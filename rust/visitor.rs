@@ -0,0 +1,80 @@
+use crate::ort_value::{Map, OrtValue};
+
+/// Tree rewriter used by `OrtValue::transform`
+///
+/// Each method receives a node and returns its replacement. The defaults
+/// rebuild the node unchanged, so implementations only override what they
+/// rewrite. Arrays and objects are visited after their children, and receive
+/// the already transformed children.
+pub trait Visitor {
+    fn visit_null(&mut self) -> OrtValue {
+        OrtValue::Null
+    }
+
+    fn visit_bool(&mut self, b: bool) -> OrtValue {
+        OrtValue::Bool(b)
+    }
+
+    fn visit_number(&mut self, n: f64) -> OrtValue {
+        OrtValue::Number(n)
+    }
+
+    fn visit_string(&mut self, s: &str) -> OrtValue {
+        OrtValue::String(s.to_string())
+    }
+
+    fn visit_array(&mut self, items: Vec<OrtValue>) -> OrtValue {
+        OrtValue::Array(items)
+    }
+
+    fn visit_object(&mut self, obj: Map<String, OrtValue>) -> OrtValue {
+        OrtValue::Object(obj)
+    }
+}
+
+impl OrtValue {
+    /// Rebuild the value by calling the visitor at every node
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> ort_rs::OrtResult<()> {
+    /// use ort_rs::{OrtValue, Visitor};
+    ///
+    /// struct Redact;
+    ///
+    /// impl Visitor for Redact {
+    ///     fn visit_string(&mut self, s: &str) -> OrtValue {
+    ///         if s.contains('@') {
+    ///             OrtValue::from("***")
+    ///         } else {
+    ///             OrtValue::from(s)
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let value = ort_rs::from_str("users:id,email:\n1,john@example.com")?;
+    /// let redacted = value.transform(&mut Redact);
+    /// assert_eq!(redacted["users"][0]["email"].as_str(), Some("***"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn transform(&self, visitor: &mut dyn Visitor) -> OrtValue {
+        match self {
+            OrtValue::Null => visitor.visit_null(),
+            OrtValue::Bool(b) => visitor.visit_bool(*b),
+            OrtValue::Number(n) => visitor.visit_number(*n),
+            OrtValue::String(s) => visitor.visit_string(s),
+            OrtValue::Array(arr) => {
+                let items = arr.iter().map(|item| item.transform(visitor)).collect();
+                visitor.visit_array(items)
+            }
+            OrtValue::Object(obj) => {
+                let obj = obj
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.transform(visitor)))
+                    .collect();
+                visitor.visit_object(obj)
+            }
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::error::{OrtError, OrtErrorKind, OrtResult};
+use crate::escape::escape;
+use crate::fields::{format_fields, FieldSpec};
+use crate::generator::{generate_field, generate_value, GenerateOptions};
+use crate::ort_value::OrtValue;
+use std::io::Write;
+
+/// Streaming ORT writer that appends sections and rows to an `io::Write`
+///
+/// Values are escaped exactly like `generate_ort` escapes them. A section
+/// declared without fields holds a single value per row.
+///
+/// # Example
+/// ```
+/// # fn main() -> ort_rs::OrtResult<()> {
+/// use ort_rs::{FieldSpec, OrtValue, OrtWriter};
+///
+/// let fields = vec![
+///     FieldSpec::Simple("id".to_string()),
+///     FieldSpec::Nested("pos".to_string(), vec![
+///         FieldSpec::Simple("lat".to_string()),
+///         FieldSpec::Simple("lng".to_string()),
+///     ]),
+/// ];
+///
+/// let mut writer = OrtWriter::new(Vec::new());
+/// writer.begin_section(Some("places"), &fields)?;
+/// let pos = ort_rs::from_str(":lat,lng:\n1.5,2.5")?;
+/// writer.write_row(&[OrtValue::from(1), pos])?;
+/// let output = writer.finish()?;
+///
+/// assert_eq!(String::from_utf8(output).unwrap(), "places:id,pos(lat,lng):\n1,(1.5,2.5)\n");
+/// # Ok(())
+/// # }
+/// ```
+pub struct OrtWriter<W: Write> {
+    writer: W,
+    fields: Option<Vec<FieldSpec>>,
+    line: usize,
+}
+
+impl<W: Write> OrtWriter<W> {
+    /// Create a writer over an output sink
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            fields: None,
+            line: 0,
+        }
+    }
+
+    /// Write a section header (`key:fields:`, or `:fields:` without a key)
+    pub fn begin_section(&mut self, key: Option<&str>, fields: &[FieldSpec]) -> OrtResult<()> {
+        // Separate sections with a blank line
+        if self.line > 0 {
+            self.write_line("")?;
+        }
+
+        let header = format!("{}:{}:", escape(key.unwrap_or(""), ','), format_fields(fields, ','));
+        self.write_line(&header)?;
+        self.fields = Some(fields.to_vec());
+        Ok(())
+    }
+
+    /// Write a data row, one value per declared field
+    pub fn write_row(&mut self, values: &[OrtValue]) -> OrtResult<()> {
+        let fields = match &self.fields {
+            Some(fields) => fields,
+            None => {
+                return Err(OrtError::new(
+                    self.line + 1,
+                    String::new(),
+                    "Cannot write a row before a section is started".to_string(),
+                ));
+            }
+        };
+
+        let line = if fields.is_empty() {
+            if values.len() != 1 {
+                return Err(self.arity_error(1, values.len()));
+            }
+            generate_value(&values[0], &GenerateOptions::default())
+        } else {
+            if values.len() != fields.len() {
+                return Err(self.arity_error(fields.len(), values.len()));
+            }
+            fields
+                .iter()
+                .zip(values)
+                .map(|(field, value)| generate_field(field, value, &GenerateOptions::default()))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+
+        self.write_line(&line)
+    }
+
+    /// Flush the output and return the underlying writer
+    pub fn finish(mut self) -> OrtResult<W> {
+        self.writer.flush().map_err(|e| self.io_error(e))?;
+        Ok(self.writer)
+    }
+
+    fn write_line(&mut self, line: &str) -> OrtResult<()> {
+        self.line += 1;
+        writeln!(self.writer, "{}", line).map_err(|e| self.io_error(e))
+    }
+
+    fn arity_error(&self, expected: usize, got: usize) -> OrtError {
+        OrtError::new(
+            self.line + 1,
+            String::new(),
+            format!("Expected {} values but got {}", expected, got),
+        )
+        .with_kind(OrtErrorKind::ValueCountMismatch)
+    }
+
+    fn io_error(&self, e: std::io::Error) -> OrtError {
+        OrtError::new(self.line, String::new(), format!("Failed to write output: {}", e))
+            .with_source(e)
+            .with_kind(OrtErrorKind::Io)
+    }
+}
@@ -0,0 +1,108 @@
+//! Reads and writes ORT through the tokio file and reader APIs
+#![cfg(feature = "tokio")]
+
+use ort_rs::{from_file_async, from_reader_async, to_file_async, OrtErrorKind};
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+const USERS: &str = "users:id,name:\n1,José\n2,Jane\n";
+
+/// Reader giving a few bytes at a time, pending between reads, and then
+/// failing if `error` is set
+struct Trickle {
+    bytes: Vec<u8>,
+    pos: usize,
+    pending: bool,
+    error: Option<io::ErrorKind>,
+}
+
+impl Trickle {
+    fn new(bytes: &[u8], error: Option<io::ErrorKind>) -> Self {
+        Trickle { bytes: bytes.to_vec(), pos: 0, pending: false, error }
+    }
+}
+
+impl AsyncRead for Trickle {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        self.pending = !self.pending;
+        if self.pending {
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        if self.pos == self.bytes.len() {
+            return Poll::Ready(self.error.map_or(Ok(()), |kind| Err(io::Error::new(kind, "connection reset"))));
+        }
+        // Three bytes at a time splits the two bytes of 'é'
+        let end = (self.pos + 3).min(self.bytes.len()).min(self.pos + buf.remaining());
+        buf.put_slice(&self.bytes[self.pos..end]);
+        self.pos = end;
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn partial_reads_parse_like_one_read() {
+    let value = from_reader_async(Trickle::new(USERS.as_bytes(), None)).await.unwrap();
+    assert_eq!(value, ort_rs::from_str(USERS).unwrap());
+    assert_eq!(value["users"][0]["name"].as_str(), Some("José"));
+}
+
+#[tokio::test]
+async fn reader_errors_match_from_reader() {
+    let error = from_reader_async(Trickle::new(USERS.as_bytes(), Some(io::ErrorKind::ConnectionReset))).await.unwrap_err();
+    assert_eq!(error.kind, OrtErrorKind::Io);
+    assert_eq!(error.message, "Failed to read input: connection reset");
+    assert!(error.source.is_some());
+
+    let error = from_reader_async(Trickle::new(b"users:id:\n1,2\n", None)).await.unwrap_err();
+    let sync_error = ort_rs::from_reader(&b"users:id:\n1,2\n"[..]).unwrap_err();
+    assert_eq!((error.line, error.kind, error.message), (sync_error.line, sync_error.kind, sync_error.message));
+}
+
+#[tokio::test]
+async fn files_round_trip_through_a_temp_directory() {
+    let dir = std::env::temp_dir().join(format!("ort-async-{}", std::process::id()));
+    tokio::fs::create_dir_all(&dir).await.unwrap();
+    let path = dir.join("users.ort");
+
+    let value = ort_rs::from_str(USERS).unwrap();
+    to_file_async(&value, &path).await.unwrap();
+    assert_eq!(from_file_async(&path).await.unwrap(), value);
+
+    let missing = dir.join("missing.ort");
+    let error = from_file_async(&missing).await.unwrap_err();
+    let sync_error = ort_rs::from_file(&missing).unwrap_err();
+    assert_eq!(error.kind, OrtErrorKind::Io);
+    assert_eq!(error.message, sync_error.message);
+    assert_eq!(error.source_file.as_deref(), Some(missing.as_path()));
+
+    tokio::fs::write(&path, b"users:id:\n\xFF\n").await.unwrap();
+    let error = from_file_async(&path).await.unwrap_err();
+    assert_eq!((error.kind, error.line), (OrtErrorKind::Encoding, 2));
+    tokio::fs::remove_dir_all(&dir).await.unwrap();
+}
+
+#[cfg(feature = "compression")]
+#[tokio::test]
+async fn gzip_files_match_the_sync_functions() {
+    let dir = std::env::temp_dir().join(format!("ort-async-gzip-{}", std::process::id()));
+    tokio::fs::create_dir_all(&dir).await.unwrap();
+    let value = ort_rs::from_str(USERS).unwrap();
+
+    let sync_path = dir.join("sync.ort.gz");
+    ort_rs::to_file(&value, &sync_path).unwrap();
+    assert_eq!(from_file_async(&sync_path).await.unwrap(), value);
+
+    let async_path = dir.join("async.ort.gz");
+    to_file_async(&value, &async_path).await.unwrap();
+    assert_eq!(&tokio::fs::read(&async_path).await.unwrap()[..2], b"\x1f\x8b");
+    assert_eq!(ort_rs::from_file(&async_path).unwrap(), value);
+
+    tokio::fs::write(&async_path, USERS).await.unwrap();
+    let error = from_file_async(&async_path).await.unwrap_err();
+    let sync_error = ort_rs::from_file(&async_path).unwrap_err();
+    assert_eq!((error.kind, error.message), (sync_error.kind, sync_error.message));
+    tokio::fs::remove_dir_all(&dir).await.unwrap();
+}
@@ -0,0 +1,345 @@
+//! Runs the `ort` command line through standard input and output
+#![cfg(all(feature = "cli", feature = "serde_json"))]
+
+use assert_cmd::cargo::cargo_bin_cmd;
+use std::fs;
+
+const USERS: &str = "users:id,name:\n1,John\n2,Jane\n";
+
+#[test]
+fn to_json_pipes_stdin_to_stdout() {
+    cargo_bin_cmd!("ort")
+        .args(["to-json", "--compact", "-"])
+        .write_stdin(USERS)
+        .assert()
+        .success()
+        .stdout("{\"users\":[{\"id\":1.0,\"name\":\"John\"},{\"id\":2.0,\"name\":\"Jane\"}]}\n");
+}
+
+#[test]
+fn from_json_round_trips_through_to_json() {
+    let json = cargo_bin_cmd!("ort").args(["to-json", "-", "-o", "-"]).write_stdin(USERS).output().unwrap();
+    assert!(json.status.success());
+
+    cargo_bin_cmd!("ort")
+        .args(["from-json", "-", "--output", "-"])
+        .write_stdin(json.stdout)
+        .assert()
+        .success()
+        .stdout(USERS);
+}
+
+#[test]
+fn aliases_pipe_a_document_through_json() {
+    let original = include_str!("../example/04_nested_objects.ort");
+    let json = cargo_bin_cmd!("ort2json").arg("-").write_stdin(original).output().unwrap();
+    assert!(json.status.success());
+    assert!(json.stderr.is_empty());
+
+    let ort = cargo_bin_cmd!("json2ort").write_stdin(json.stdout).output().unwrap();
+    assert!(ort.status.success());
+    assert!(ort.stderr.is_empty());
+    let round_trip = ort_rs::from_str(&String::from_utf8(ort.stdout).unwrap()).unwrap();
+    assert_eq!(round_trip, ort_rs::from_str(original).unwrap());
+}
+
+#[test]
+fn output_names_a_file() {
+    let dir = std::env::temp_dir().join(format!("ort-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("users.json");
+
+    cargo_bin_cmd!("ort")
+        .args(["to-json", "--compact", "-", "-o"])
+        .arg(&output)
+        .write_stdin("port:\n80")
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(fs::read_to_string(&output).unwrap(), "{\"port\":80.0}");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn aliases_run_the_subcommands() {
+    cargo_bin_cmd!("ort2json").args(["--compact", "-"]).write_stdin("port:\n80").assert().success().stdout("{\"port\":80.0}\n");
+    cargo_bin_cmd!("json2ort").arg("-").write_stdin("{\"port\":80}").assert().success().stdout("port:\n80\n");
+}
+
+#[test]
+fn help_and_version_exit_with_0() {
+    cargo_bin_cmd!("ort").arg("--version").assert().success().stdout(format!("ort {}\n", env!("CARGO_PKG_VERSION")));
+    cargo_bin_cmd!("ort").args(["to-json", "--help"]).assert().success();
+}
+
+#[test]
+fn usage_errors_exit_with_1() {
+    cargo_bin_cmd!("ort").assert().code(1);
+    cargo_bin_cmd!("ort").arg("convert").assert().code(1);
+    cargo_bin_cmd!("ort").args(["to-json", "--indent", "wide", "-"]).assert().code(1);
+    cargo_bin_cmd!("ort2json").args(["--delimiter", "::", "-"]).assert().code(1);
+}
+
+#[test]
+fn parse_errors_exit_with_2() {
+    cargo_bin_cmd!("ort").args(["to-json", "-"]).write_stdin("users:id,name:\n1,John,extra").assert().code(2).stdout("");
+    cargo_bin_cmd!("ort").args(["from-json", "-"]).write_stdin("{\"port\":").assert().code(2);
+    cargo_bin_cmd!("ort").args(["validate", "-"]).write_stdin("users:id),name:\n1,John").assert().code(2);
+    cargo_bin_cmd!("ort").args(["validate", "-"]).write_stdin(USERS).assert().success();
+}
+
+#[test]
+fn recursive_conversion_mirrors_the_tree() {
+    let dir = std::env::temp_dir().join(format!("ort-tree-{}", std::process::id()));
+    let tree = dir.join("exports");
+    fs::create_dir_all(tree.join("a/b")).unwrap();
+    fs::write(tree.join("top.ort"), "port:\n80").unwrap();
+    fs::write(tree.join("a/users.ort"), USERS).unwrap();
+    fs::write(tree.join("a/b/deep.ort"), "deep:\ntrue").unwrap();
+    fs::write(tree.join("a/bad.ort"), "users:id:\n1,2").unwrap();
+    fs::write(tree.join("a/notes.txt"), "not ort").unwrap();
+
+    let json = dir.join("json");
+    let output = cargo_bin_cmd!("ort").args(["to-json", "--recursive", "--compact", "--jobs", "3"]).arg(&tree).arg("-o").arg(&json).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("bad.ort") && stderr.ends_with("3 converted, 1 failed\n"), "{}", stderr);
+    assert_eq!(fs::read_to_string(json.join("top.json")).unwrap(), "{\"port\":80.0}");
+    assert_eq!(fs::read_to_string(json.join("a/b/deep.json")).unwrap(), "{\"deep\":true}");
+    assert!(json.join("a/users.json").exists() && !json.join("a/bad.json").exists() && !json.join("a/notes.json").exists());
+
+    let ort = dir.join("ort");
+    cargo_bin_cmd!("json2ort").arg("-r").arg(&json).arg("-o").arg(&ort).assert().success();
+    assert_eq!(fs::read_to_string(ort.join("a/users.ort")).unwrap(), USERS.trim_end());
+    assert_eq!(fs::read_to_string(ort.join("a/b/deep.ort")).unwrap(), "deep:\ntrue");
+
+    // Files are converted in path order, so a/b/deep.ort comes before a/bad.ort
+    let stopped = dir.join("stopped");
+    cargo_bin_cmd!("ort2json").args(["-r", "--fail-fast"]).arg(&tree).arg("-o").arg(&stopped).assert().code(2);
+    assert!(stopped.join("a/b/deep.json").exists() && !stopped.join("a/users.json").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn inputs_converting_to_one_output_are_rejected() {
+    let dir = std::env::temp_dir().join(format!("ort-clash-{}", std::process::id()));
+    fs::create_dir_all(dir.join("a")).unwrap();
+    fs::create_dir_all(dir.join("b")).unwrap();
+    fs::write(dir.join("a/users.ort"), USERS).unwrap();
+    fs::write(dir.join("b/users.ort"), "port:\n80").unwrap();
+
+    let out = dir.join("out");
+    let output = cargo_bin_cmd!("ort").arg("to-json").arg(dir.join("a/users.ort")).arg(dir.join("b/users.ort")).arg("-o").arg(&out).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("both convert to") && stderr.contains("users.json"), "{}", stderr);
+    assert!(!out.join("users.json").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn validate_reports_every_file_and_the_worst_result() {
+    let dir = std::env::temp_dir().join(format!("ort-validate-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("good.ort"), USERS).unwrap();
+    fs::write(dir.join("bad.ort"), "users:id,name:\n1,John\n2\n3,Bob,extra\n").unwrap();
+    fs::write(dir.join("schema.ort"), "type:\nobject\n\nfields:\n(users:(type:array,items:(fields:(id:(type:int,max:1)))))").unwrap();
+    let pattern = dir.join("*d.ort");
+
+    cargo_bin_cmd!("ort").args(["validate", "example/*.ort"]).assert().success().stderr("");
+
+    let output = cargo_bin_cmd!("ort").arg("validate").arg(&pattern).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let bad = dir.join("bad.ort").display().to_string();
+    assert_eq!(stderr.matches(&bad).count(), 2, "{}", stderr);
+    assert!(!stderr.contains("good.ort"));
+
+    cargo_bin_cmd!("ort").args(["validate", "--quiet"]).arg(&pattern).assert().code(2).stderr("");
+    cargo_bin_cmd!("ort").arg("validate").arg(&pattern).arg(dir.join("missing.ort")).assert().code(3);
+    cargo_bin_cmd!("ort").args(["validate", "--max-rows", "1"]).arg(dir.join("good.ort")).assert().code(2);
+
+    let output = cargo_bin_cmd!("ort").arg("validate").arg("--schema").arg(dir.join("schema.ort")).arg(dir.join("good.ort")).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr).unwrap().ends_with("good.ort: users.1.id: expected int at most 1 but found 2\n"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fmt_rewrites_files_and_checks_them() {
+    let dir = std::env::temp_dir().join(format!("ort-fmt-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("users.ort");
+    fs::write(&path, "# Users\nusers:id,name:\n1 , John\n\n\n2,Jane").unwrap();
+
+    let output = cargo_bin_cmd!("ort").args(["fmt", "--check"]).arg(&path).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert!(diff.contains("\n-1 , John\n") && diff.contains("\n+1,John\n"), "{}", diff);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "# Users\nusers:id,name:\n1 , John\n\n\n2,Jane");
+
+    cargo_bin_cmd!("ort").arg("fmt").arg(&path).assert().success().stdout("");
+    let formatted = fs::read_to_string(&path).unwrap();
+    assert_eq!(formatted, "# Users\nusers:id,name:\n1,John\n\n2,Jane\n");
+    cargo_bin_cmd!("ort").args(["fmt", "--check"]).arg(&path).assert().success().stdout("");
+    cargo_bin_cmd!("ort").args(["fmt", "-"]).write_stdin(formatted.clone()).assert().success().stdout(formatted);
+
+    fs::write(&path, "users:id,name:\n1,John,extra").unwrap();
+    cargo_bin_cmd!("ort").arg("fmt").arg(&path).assert().code(2);
+    assert_eq!(fs::read_to_string(&path).unwrap(), "users:id,name:\n1,John,extra");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fmt_compares_against_the_file_as_it_is() {
+    let dir = std::env::temp_dir().join(format!("ort-fmt-raw-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["crlf.ort", "trailing-whitespace.ort"] {
+        let path = dir.join(name);
+        let original = fs::read_to_string(format!("tests/fixtures/format/{}", name)).unwrap();
+        fs::write(&path, &original).unwrap();
+
+        cargo_bin_cmd!("ort").args(["fmt", "--check"]).arg(&path).assert().code(1);
+        cargo_bin_cmd!("ort").arg("fmt").arg(&path).assert().success();
+        let formatted = fs::read_to_string(&path).unwrap();
+        assert_ne!(formatted, original);
+        assert!(!formatted.contains('\r') && !formatted.contains(" \n") && !formatted.contains("\t\n"), "{:?}", formatted);
+        cargo_bin_cmd!("ort").args(["fmt", "--check"]).arg(&path).assert().success().stdout("");
+    }
+    assert_eq!(fs::read_to_string(dir.join("crlf.ort")).unwrap(), "a:\n1\n\nb:\n2\n");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn fmt_check_diffs_large_files_without_comparing_every_line_pair() {
+    let rows: String = (0..5000).map(|i| format!("{} , n{}\n", i, i)).collect();
+    let input = format!("# Users\nusers:id,name:\n{}\nport:\n80\n", rows);
+
+    let output = cargo_bin_cmd!("ort").args(["fmt", "--check", "-"]).write_stdin(input).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let diff = String::from_utf8(output.stdout).unwrap();
+    assert!(diff.contains("\n@@ -1,5005 +1,5005 @@\n # Users\n users:id,name:\n-0 , n0\n"), "{}", &diff[..200]);
+    assert!(diff.contains("\n+4999,n4999\n \n port:\n 80\n"));
+}
+
+#[test]
+fn io_errors_exit_with_3() {
+    let missing = std::env::temp_dir().join("ort-cli-missing.ort");
+    cargo_bin_cmd!("ort").arg("to-json").arg(&missing).assert().code(3);
+    cargo_bin_cmd!("ort").arg("validate").arg(&missing).assert().code(3);
+    cargo_bin_cmd!("ort").args(["to-json", "-", "-o", "/nonexistent/dir/out.json"]).write_stdin(USERS).assert().code(3);
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn csv_round_trips_a_flat_table() {
+    let original = "users:active,id,name,score:\ntrue,1,John\\, Jr.,9.5\nfalse,2,Jane,\n";
+    let csv = cargo_bin_cmd!("ort").args(["to-csv", "-", "--section", "users"]).write_stdin(original).output().unwrap();
+    assert!(csv.status.success());
+    assert_eq!(String::from_utf8_lossy(&csv.stdout), "active,id,name,score\ntrue,1,\"John, Jr.\",9.5\nfalse,2,Jane,\n");
+
+    let ort = cargo_bin_cmd!("csv2ort").args(["--key", "users"]).write_stdin(csv.stdout).output().unwrap();
+    assert!(ort.status.success());
+    let round_tripped = ort_rs::parse_ort(&String::from_utf8_lossy(&ort.stdout)).unwrap();
+    assert_eq!(round_tripped, ort_rs::parse_ort(original).unwrap());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn to_csv_needs_a_section_or_all() {
+    let dir = std::env::temp_dir().join(format!("ort-csv-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("data.ort");
+    fs::write(&input, format!("{}\nteams:id,lead:\n1,John\n", USERS)).unwrap();
+
+    cargo_bin_cmd!("ort").arg("to-csv").arg(&input).assert().code(1);
+    cargo_bin_cmd!("ort").arg("to-csv").arg(&input).args(["--section", "missing"]).assert().code(1);
+    cargo_bin_cmd!("ort").arg("to-csv").arg(&input).args(["--section", "teams", "-o", "-"]).assert().success().stdout("id,lead\n1,John\n");
+
+    cargo_bin_cmd!("ort").arg("to-csv").arg(&input).args(["--all", "--csv-delimiter", "tab", "-o"]).arg(dir.join("csv")).assert().success();
+    assert_eq!(fs::read_to_string(dir.join("csv/users.csv")).unwrap(), "id\tname\n1\tJohn\n2\tJane\n");
+    assert_eq!(fs::read_to_string(dir.join("csv/teams.csv")).unwrap(), "id\tlead\n1\tJohn\n");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn csv_flattens_nested_fields_into_dotted_columns() {
+    let nested = "users:id,address(city,country):\n1,(Seoul,KR)\n";
+    cargo_bin_cmd!("ort").args(["to-csv", "-"]).write_stdin(nested).assert().code(2);
+
+    let csv = cargo_bin_cmd!("ort").args(["to-csv", "-", "--nested", "dotted"]).write_stdin(nested).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&csv.stdout), "address.city,address.country,id\nSeoul,KR,1\n");
+
+    cargo_bin_cmd!("ort")
+        .args(["from-csv", "-", "--key", "users", "--nested", "dotted", "--no-infer"])
+        .write_stdin(csv.stdout)
+        .assert()
+        .success()
+        .stdout("users:address(city,country),id:\n(Seoul,KR),\"1\"\n");
+}
+
+#[test]
+fn from_json_reads_json_lines() {
+    let expected = "users:active,id,name:\ntrue,1,John\nfalse,2,Jane\\, Jr.\n,3,Bob\n";
+    cargo_bin_cmd!("ort")
+        .args(["from-json", "--ndjson", "--key", "users", "tests/fixtures/users.jsonl", "-o", "-"])
+        .assert()
+        .success()
+        .stdout(expected);
+
+    // Detected without --ndjson, as the file isn't one JSON document
+    let fixture = fs::read_to_string("tests/fixtures/users.jsonl").unwrap();
+    let ort = cargo_bin_cmd!("json2ort").args(["-k", "users"]).write_stdin(fixture.clone()).output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&ort.stdout), expected);
+
+    let output = cargo_bin_cmd!("ort").args(["from-json", "--ndjson"]).write_stdin(format!("{}{{\"id\":\n", fixture)).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 5"));
+
+    // And back to JSON Lines
+    cargo_bin_cmd!("ort")
+        .args(["to-json", "--ndjson", "-"])
+        .write_stdin(expected)
+        .assert()
+        .success()
+        .stdout("{\"active\":true,\"id\":1.0,\"name\":\"John\"}\n{\"active\":false,\"id\":2.0,\"name\":\"Jane, Jr.\"}\n{\"active\":null,\"id\":3.0,\"name\":\"Bob\"}\n");
+    cargo_bin_cmd!("ort").args(["to-json", "--ndjson", "-"]).write_stdin(format!("{}\nteams:id:\n1\n", USERS)).assert().code(1);
+}
+
+#[test]
+fn diff_matches_rows_and_exits_with_the_result() {
+    let dir = std::env::temp_dir().join(format!("ort-diff-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (old, new) = (dir.join("old.ort"), dir.join("new.ort"));
+    fs::write(&old, "users:id,name:\n1,John\n2,Jane\n\nport:\n80\n").unwrap();
+    fs::write(&new, "users:name,id:\nJanet,2\nBob,3\nJohn,1\n\nport:\n80\n").unwrap();
+
+    cargo_bin_cmd!("ort")
+        .args(["diff", "--key", "id"])
+        .args([&old, &new])
+        .assert()
+        .code(1)
+        .stdout("users\n  ~ [id=2] name: Jane -> Janet\n  + [id=3]: (id:3,name:Bob)\n");
+    cargo_bin_cmd!("ort")
+        .arg("diff")
+        .args([&old, &new])
+        .assert()
+        .code(1)
+        .stdout("users\n  - [1]: (id:2,name:Jane)\n  + [0]: (id:2,name:Janet)\n  + [1]: (id:3,name:Bob)\n");
+
+    let output = cargo_bin_cmd!("ort").args(["diff", "-k", "id", "--format", "json"]).args([&old, &new]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(records[0], serde_json::json!({"change": "changed", "path": "users.0.name", "key": 2.0, "old": "Jane", "new": "Janet"}));
+
+    // Reordered columns and rows are no change
+    fs::write(&new, "port:\n80\n\nusers:name,id:\nJane,2\nJohn,1\n").unwrap();
+    cargo_bin_cmd!("ort").arg("diff").args([&old, &new]).assert().code(0).stdout("");
+    cargo_bin_cmd!("ort").arg("diff").arg(&old).arg(dir.join("missing.ort")).assert().code(2);
+    fs::write(&new, "users:id:\n1,2\n").unwrap();
+    cargo_bin_cmd!("ort").arg("diff").args([&old, &new]).assert().code(2);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,53 @@
+//! Reads files in each encoding `from_file` detects
+
+use ort_rs::{append_rows, from_file, Map, OrtErrorKind, OrtValue};
+
+fn fixture(name: &str) -> String {
+    format!("tests/fixtures/encoding/{}", name)
+}
+
+#[test]
+fn byte_order_marks_are_decoded_and_dropped() {
+    for name in ["utf8-bom.ort", "utf16le.ort", "utf16be.ort"] {
+        let value = from_file(fixture(name)).unwrap();
+        let users = value["users"].as_array().unwrap();
+        assert_eq!(users.len(), 2, "{}", name);
+        assert_eq!(users[0]["name"].as_str(), Some("José"), "{}", name);
+        assert_eq!(value.as_object().unwrap().keys().collect::<Vec<_>>(), ["users"], "{}", name);
+    }
+}
+
+#[test]
+fn invalid_utf8_names_its_offset() {
+    let error = from_file(fixture("invalid-utf8.ort")).unwrap_err();
+    assert_eq!(error.kind, OrtErrorKind::Encoding);
+    assert_eq!(error.message, "Invalid UTF-8 at byte offset 20");
+    assert_eq!(error.line, 2);
+    assert!(error.source_file.as_deref().is_some_and(|path| path.ends_with("invalid-utf8.ort")));
+}
+
+#[test]
+fn appending_keeps_the_byte_order_mark() {
+    let dir = std::env::temp_dir().join(format!("ort-encoding-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let row = |id: i64, name: &str| OrtValue::Object(Map::from([("id".to_string(), OrtValue::from(id)), ("name".to_string(), OrtValue::from(name))]));
+
+    for name in ["utf8-bom.ort", "utf16le.ort", "utf16be.ort"] {
+        let path = dir.join(name);
+        let original = std::fs::read(fixture(name)).unwrap();
+        std::fs::write(&path, &original).unwrap();
+
+        // The last section takes the fast path, a new one after it the slow one
+        append_rows(&path, "users", &[row(3, "Zoë")]).unwrap();
+        append_rows(&path, "admins", &[row(1, "Root")]).unwrap();
+        append_rows(&path, "users", &[row(4, "Ann")]).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes[..3], original[..3], "{}", name);
+        let value = from_file(&path).unwrap();
+        let names = value["users"].as_array().unwrap().iter().map(|user| user["name"].as_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(names, ["José", "Jane", "Zoë", "Ann"], "{}", name);
+        assert_eq!(value["admins"][0]["name"].as_str(), Some("Root"), "{}", name);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
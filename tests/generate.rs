@@ -0,0 +1,108 @@
+//! Generates ORT text and checks what it reads back as
+
+use ort_rs::{generate_ort, generate_ort_with, parse_ort, GenerateOptions, Map, OrtErrorKind, OrtValue};
+
+fn object(pairs: Vec<(&str, OrtValue)>) -> OrtValue {
+    OrtValue::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect::<Map<_, _>>())
+}
+
+fn empty() -> OrtValue {
+    OrtValue::String(String::new())
+}
+
+#[test]
+fn null_and_empty_strings_stay_apart() {
+    let row = || {
+        object(vec![
+            ("a", OrtValue::Null),
+            ("b", empty()),
+            ("g", object(vec![("x", OrtValue::Null), ("y", empty())])),
+            ("l", OrtValue::Array(vec![OrtValue::Null, empty()])),
+        ])
+    };
+    let value = object(vec![("rows", OrtValue::Array(vec![row(), row()]))]);
+
+    let ort = generate_ort(&value);
+    assert_eq!(ort, "rows:a,b,g(x,y),l:\n,\"\",(,\"\"),[,\"\"]\n,\"\",(,\"\"),[,\"\"]");
+    assert_eq!(parse_ort(&ort).unwrap(), value);
+
+    let value = object(vec![("one", OrtValue::Array(vec![object(vec![("x", empty())]), object(vec![("x", OrtValue::Null)])]))]);
+    assert_eq!(parse_ort(&generate_ort(&value)).unwrap(), value);
+
+    let parsed = parse_ort("rows:a,b:\n,\"\"").unwrap();
+    assert!(parsed["rows"][0]["a"].is_null());
+    assert_eq!(parsed["rows"][0]["b"], empty());
+}
+
+#[test]
+fn file_errors_name_the_file() {
+    let dir = std::env::temp_dir().join(format!("ort-generate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("bad.ort");
+    std::fs::write(&path, "users:id:\n1,2\n").unwrap();
+
+    let error = ort_rs::from_file(&path).unwrap_err();
+    assert_eq!((error.kind, error.line), (OrtErrorKind::ValueCountMismatch, 2));
+    assert_eq!(error.source_file.as_deref(), Some(path.as_path()));
+    assert!(format!("{:#}", error).starts_with(&format!("{}:2", path.display())));
+
+    let error = ort_rs::from_str("users:id:\n1,2\n").unwrap_err();
+    assert!(error.source_file.is_none());
+    assert_eq!(format!("{:#}", error), "line 2: Expected 1 values but got 2 — \"1,2\"");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Byte offsets of the delimiters between a row's cells, skipping escaped
+/// ones and those inside quotes, arrays and groups
+fn cell_ends(line: &str) -> Vec<usize> {
+    let (mut depth, mut quoted, mut escaped) = (0, false, false);
+    let mut ends = Vec::new();
+    for (i, ch) in line.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => quoted = !quoted,
+            _ if quoted => {}
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ',' if depth == 0 => ends.push(i),
+            _ => {}
+        }
+    }
+    ends
+}
+
+#[test]
+fn aligned_columns_line_up_and_read_back() {
+    let row = |id: i64, name: String, tags: Vec<&str>, note: &str| {
+        object(vec![
+            ("id", OrtValue::from(id)),
+            ("name", OrtValue::from(name)),
+            ("tags", OrtValue::Array(tags.into_iter().map(OrtValue::from).collect())),
+            ("meta", object(vec![("note", OrtValue::from(note)), ("score", OrtValue::from(id as f64 / 3.0))])),
+        ])
+    };
+    let rows = vec![
+        row(1, "x".to_string(), vec![], ""),
+        row(-9_876_543_210, "a".repeat(120), vec!["one", "two, three", "(four)"], "  spaced  "),
+        row(42, "back\\slash, comma: colon".to_string(), vec!["x"], "#hash"),
+        row(0, String::new(), vec!["[nested]"; 5], "\"quoted\""),
+    ];
+    let value = object(vec![("rows", OrtValue::Array(rows))]);
+    let options = GenerateOptions { align_columns: true, ..Default::default() };
+
+    let ort = generate_ort_with(&value, &options).unwrap();
+    let lines = ort.lines().skip(1).collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    let ends = cell_ends(lines[0]);
+    assert_eq!(ends.len(), 3);
+    for line in &lines {
+        assert_eq!(cell_ends(line), ends, "{}", line);
+    }
+    assert!(lines.iter().any(|line| line.contains(" ,")));
+
+    assert_eq!(parse_ort(&ort).unwrap(), value);
+    assert_eq!(parse_ort(&ort).unwrap(), parse_ort(&generate_ort(&value)).unwrap());
+    let indented = generate_ort_with(&value, &GenerateOptions { indent_nested: true, ..options }).unwrap();
+    assert_eq!(parse_ort(&indented).unwrap(), value);
+}
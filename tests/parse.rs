@@ -0,0 +1,132 @@
+//! Parses documents with the options `ParseOptions` offers
+
+use ort_rs::{generate_ort, parse_ort, parse_ort_with, ConflictPolicy, Map, OrtErrorKind, OrtReader, OrtValue, ParseOptions, RowArity};
+
+fn object(pairs: Vec<(&str, OrtValue)>) -> OrtValue {
+    OrtValue::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect::<Map<_, _>>())
+}
+
+fn ids(value: &OrtValue) -> Vec<&str> {
+    value.as_array().unwrap().iter().map(|row| row["id"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn escaped_hashes_are_data_and_bare_ones_comments() {
+    let content = "tags:id:\n\\#1\n# a comment\n\\#2\n\nhash:\n\\#\n\nafter:\n1";
+    let value = parse_ort(content).unwrap();
+    assert_eq!(ids(&value["tags"]), ["#1", "#2"]);
+    assert_eq!(value["hash"].as_str(), Some("#"));
+    assert_eq!(value["after"].as_i64(), Some(1));
+
+    let rows = OrtReader::from_reader(content.as_bytes()).next_section().unwrap().unwrap().map(Result::unwrap).collect::<Vec<_>>();
+    assert_eq!(rows, value["tags"].as_array().unwrap().as_slice());
+
+    let row = |id: &str| object(vec![("id", OrtValue::from(id))]);
+    let value = object(vec![("tags", OrtValue::Array(vec![row("#1"), row("#"), row("a#")])), ("hash", OrtValue::from("#"))]);
+    let ort = generate_ort(&value);
+    assert_eq!(ort, "hash:\n\\#\n\ntags:id:\n\\#1\n\\#\na#\n");
+    assert_eq!(parse_ort(&ort).unwrap(), value);
+}
+
+#[test]
+fn escaped_hash_rows_are_checked_like_other_rows() {
+    let error = parse_ort("tags:id:\n\\#1\n# 1,2\n\\#2,3\n\nafter:\n1").unwrap_err();
+    assert_eq!((error.kind, error.line), (OrtErrorKind::ValueCountMismatch, 4));
+    assert_eq!(error.message, "Expected 1 values but got 2");
+}
+
+fn users(rows: Vec<(i64, &str, Option<i64>)>) -> OrtValue {
+    let rows = rows.into_iter().map(|(id, name, age)| object(vec![("id", OrtValue::from(id)), ("name", OrtValue::from(name)), ("age", age.map_or(OrtValue::Null, OrtValue::from))]));
+    object(vec![("users", OrtValue::Array(rows.collect()))])
+}
+
+#[test]
+fn row_arity_modes_read_the_same_ragged_fixture() {
+    let content = std::fs::read_to_string("tests/fixtures/ragged.ort").unwrap();
+    let parse = |row_arity| parse_ort_with(&content, &ParseOptions::new().row_arity(row_arity));
+
+    let error = parse(RowArity::Strict).unwrap_err();
+    assert_eq!((error.kind, error.line, error.message.as_str()), (OrtErrorKind::ValueCountMismatch, 2, "Expected 3 values but got 4"));
+    assert_eq!(parse(RowArity::Strict).unwrap_err().line, parse_ort(&content).unwrap_err().line);
+
+    let error = parse(RowArity::PadMissing).unwrap_err();
+    assert_eq!((error.line, error.message.as_str()), (4, "Expected 3 values but got 4"));
+
+    let error = parse(RowArity::IgnoreExtra).unwrap_err();
+    assert_eq!((error.line, error.message.as_str()), (3, "Expected 3 values but got 2"));
+
+    let ragged = users(vec![(1, "John", Some(30)), (2, "Jane", None), (3, "Bob", Some(25)), (4, "Ann", Some(41))]);
+    assert_eq!(parse(RowArity::Ragged).unwrap(), ragged);
+
+    // Without the row each mode rejects, the others read the rest alike
+    let lines = content.lines().collect::<Vec<_>>();
+    let without = |skip: usize| lines.iter().enumerate().filter(|(i, _)| *i != skip).map(|(_, l)| *l).collect::<Vec<_>>().join("\n");
+    let options = ParseOptions::new().row_arity(RowArity::PadMissing);
+    assert_eq!(parse_ort_with(&without(3), &options).unwrap(), users(vec![(1, "John", Some(30)), (2, "Jane", None), (4, "Ann", Some(41))]));
+    let options = ParseOptions::new().row_arity(RowArity::IgnoreExtra);
+    assert_eq!(parse_ort_with(&without(2), &options).unwrap(), users(vec![(1, "John", Some(30)), (3, "Bob", Some(25)), (4, "Ann", Some(41))]));
+}
+
+#[test]
+fn a_trailing_comma_is_only_an_error_when_strict() {
+    let content = "users:id,name:\n1,John,\n2,Jane";
+    assert_eq!(parse_ort(content).unwrap_err().line, 2);
+    for row_arity in [RowArity::PadMissing, RowArity::IgnoreExtra, RowArity::Ragged] {
+        let value = parse_ort_with(content, &ParseOptions::new().row_arity(row_arity)).unwrap();
+        assert_eq!(value["users"][0], object(vec![("id", OrtValue::from(1)), ("name", OrtValue::from("John"))]), "{:?}", row_arity);
+    }
+
+    // Two trailing commas are a real extra value
+    let error = parse_ort_with("users:id,name:\n1,John,,", &ParseOptions::new().row_arity(RowArity::PadMissing)).unwrap_err();
+    assert_eq!(error.message, "Expected 2 values but got 4");
+}
+
+#[test]
+fn duplicate_sections_follow_their_policy() {
+    let content = "# shard 1\nusers:id:\n1\n2\n\nitems:id:\n9\n\n# shard 2\nusers:id:\n3\n";
+    let error = parse_ort(content).unwrap_err();
+    assert_eq!((error.kind, error.line), (OrtErrorKind::DuplicateKey, 10));
+    assert_eq!(error.message, "Duplicate section 'users', first defined at line 2");
+    assert_eq!(format!("{:#}", error), "line 10: Duplicate section 'users', first defined at line 2 — \"users:id:\"");
+    assert_eq!(ort_rs::parse_ort_borrowed(content).unwrap_err().message, error.message);
+
+    let (value, errors) = ort_rs::parse_ort_all(content);
+    assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), [10]);
+    assert_eq!(value["users"], parse_ort("users:id:\n1\n2").unwrap()["users"]);
+
+    let parse = |policy| parse_ort_with(content, &ParseOptions::new().duplicate_sections(policy)).unwrap();
+    let numbers = |value: &OrtValue| value["users"].as_array().unwrap().iter().map(|row| row["id"].as_i64().unwrap()).collect::<Vec<_>>();
+    assert_eq!(numbers(&parse(ConflictPolicy::FirstWins)), [1, 2]);
+    assert_eq!(numbers(&parse(ConflictPolicy::LastWins)), [3]);
+    assert_eq!(numbers(&parse(ConflictPolicy::Concatenate)), [1, 2, 3]);
+    assert_eq!(parse(ConflictPolicy::Concatenate)["items"], parse_ort(content.split("# shard 2").next().unwrap()).unwrap()["items"]);
+}
+
+#[test]
+fn only_tables_concatenate() {
+    let options = ParseOptions::new().duplicate_sections(ConflictPolicy::Concatenate);
+    let error = parse_ort_with("name:\nJohn\n\nusers:id:\n1\n\nname:\nJane", &options).unwrap_err();
+    assert_eq!((error.kind, error.line), (OrtErrorKind::DuplicateKey, 7));
+    assert_eq!(error.message, "Cannot concatenate section 'name' with the one at line 1, as only tables can be concatenated");
+
+    let error = parse_ort_with("users:id:\n1\n\nusers:\n(id:2)", &options).unwrap_err();
+    assert_eq!(error.line, 4);
+}
+
+#[test]
+fn repeated_inline_keys_follow_their_policy() {
+    let content = "meta:\n(a:1,b:2,a:3)";
+    assert_eq!(parse_ort(content).unwrap()["meta"]["a"].as_i64(), Some(3));
+
+    let options = ParseOptions::new().duplicate_keys(ConflictPolicy::FirstWins);
+    assert_eq!(parse_ort_with(content, &options).unwrap()["meta"]["a"].as_i64(), Some(1));
+
+    let options = ParseOptions::new().duplicate_keys(ConflictPolicy::Error);
+    let error = parse_ort_with(content, &options).unwrap_err();
+    assert_eq!((error.kind, error.line, error.column), (OrtErrorKind::DuplicateKey, 2, Some(10)));
+    assert_eq!(error.message, "Key 'a' is repeated in the inline object");
+
+    let (_, warnings) = ort_rs::parse_ort_with_warnings(content).unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].kind, ort_rs::WarningKind::DuplicateKey);
+}
@@ -0,0 +1,284 @@
+//! Property tests that generated and formatted ORT parses back to the same value
+
+use ort_rs::escape::{escape, unescape};
+use ort_rs::{generate_ort, generate_ort_with, parse_ort, parse_ort_with, GenerateOptions, Map, OrtDocument, OrtValue, ParseOptions};
+use proptest::prelude::*;
+
+/// Strings mixing plain text with characters that are special in ORT
+fn text() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            prop::sample::select(vec![',', '(', ')', '[', ']', '\\', ':', '"', '#', ' ', '\t', '\n', '\r', '-', '.']),
+            any::<char>(),
+            prop::char::range('a', 'z'),
+        ],
+        0..12,
+    )
+    .prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Strings mostly of backslashes, structural characters and letters that
+/// follow a backslash in escapes
+fn backslashes() -> impl Strategy<Value = String> {
+    let chars = vec!['\\', '\\', '\\', ',', '(', ')', '[', ']', ':', '#', '"', 'n', 't', 'x', ' ', '|', '\t', '\n', 'é'];
+    prop::collection::vec(prop::sample::select(chars), 0..12).prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Keys of sections, fields and objects
+///
+/// Keys are trimmed when parsed and an empty one is no key at all, so those
+/// can't be written.
+fn key() -> impl Strategy<Value = String> {
+    text().prop_map(|key| key.trim().to_string()).prop_filter("empty key", |key| !key.is_empty())
+}
+
+fn scalar() -> impl Strategy<Value = OrtValue> {
+    prop_oneof![
+        Just(OrtValue::Null),
+        any::<bool>().prop_map(OrtValue::Bool),
+        any::<i64>().prop_map(|n| OrtValue::Number(n as f64)),
+        any::<f64>().prop_map(OrtValue::Number),
+        text().prop_map(OrtValue::String),
+    ]
+}
+
+fn value() -> impl Strategy<Value = OrtValue> {
+    scalar().prop_recursive(4, 32, 4, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(OrtValue::Array),
+            prop::collection::vec((key(), inner), 0..4).prop_map(|entries| OrtValue::Object(entries.into_iter().collect())),
+        ]
+    })
+}
+
+/// Tables of rows sharing their keys, which become headers with fields
+fn table() -> impl Strategy<Value = OrtValue> {
+    prop::collection::vec(key(), 1..4).prop_flat_map(|keys| {
+        let width = keys.len();
+        prop::collection::vec(prop::collection::vec(value(), width), 0..4).prop_map(move |rows| {
+            OrtValue::Array(
+                rows.into_iter()
+                    .map(|cells| OrtValue::Object(keys.iter().cloned().zip(cells).collect()))
+                    .collect(),
+            )
+        })
+    })
+}
+
+/// Documents that are a single table, list or scalar rather than sections
+///
+/// Rows of a table share their keys, as a missing key reads back as null.
+fn top_level() -> impl Strategy<Value = OrtValue> {
+    let list = prop::collection::vec(value(), 0..4).prop_filter("rows", |items| !items.iter().any(OrtValue::is_object));
+    prop_oneof![
+        scalar().prop_filter("null", |value| !value.is_null()),
+        list.prop_map(OrtValue::Array),
+        table(),
+    ]
+}
+
+/// Documents of named sections
+///
+/// A section without data lines is an empty array, so a section can't hold
+/// a bare null.
+fn document() -> impl Strategy<Value = OrtValue> {
+    let section = value().prop_filter("null section", |value| !value.is_null());
+    prop::collection::vec((key(), prop_oneof![table(), section]), 0..4)
+        .prop_map(|sections| OrtValue::Object(sections.into_iter().collect::<Map<_, _>>()))
+}
+
+/// Strings that read as another type when written bare: literals, numbers
+/// in any notation and numbers with leading zeros
+fn ambiguous() -> impl Strategy<Value = String> {
+    let literal = prop::sample::select(vec!["true", "false", "null", "-0", "1e5", "nan", "inf", "-inf", "0x1F", "0b101", "[]", "()", ""]);
+    prop_oneof![
+        literal.prop_map(str::to_string),
+        any::<i64>().prop_map(|n| n.to_string()),
+        any::<f64>().prop_map(|n| n.to_string()),
+        any::<f64>().prop_map(|n| format!("{:e}", n)),
+        "[0-9]{1,6}".prop_map(|digits| format!("0{}", digits)),
+        "-?[0-9]{1,4}\\.[0-9]{0,4}(e[+-]?[0-9]{1,3})?",
+    ]
+}
+
+/// Format a document, failing with the error and the document
+fn format(content: &str) -> Result<String, String> {
+    let formatted = match OrtDocument::parse(content) {
+        Ok(doc) => doc.formatted(),
+        Err(e) => Err(e),
+    };
+    formatted.map_err(|e| format!("{:#}\n{}", e, content))
+}
+
+proptest! {
+    #[test]
+    fn generate_parse_generate(value in document()) {
+        let text = generate_ort(&value);
+        let parsed = parse_ort(&text).map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, text)))?;
+        prop_assert_eq!(generate_ort(&parsed), text);
+    }
+
+    #[test]
+    fn parse_generate_parse(value in document()) {
+        let parsed = parse_ort(&generate_ort(&value)).map_err(|e| TestCaseError::fail(format!("{:#}", e)))?;
+        let text = generate_ort(&parsed);
+        let reparsed = parse_ort(&text).map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, text)))?;
+        prop_assert_eq!(reparsed, parsed);
+    }
+
+    #[test]
+    fn format_format(value in document()) {
+        let text = generate_ort(&value);
+        let formatted = format(&text).map_err(TestCaseError::fail)?;
+        prop_assert_eq!(parse_ort(&formatted).map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, formatted)))?, parse_ort(&text).unwrap());
+        prop_assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn ambiguous_strings_stay_strings(strings in prop::collection::vec(ambiguous(), 1..4)) {
+        let string = |s: &String| OrtValue::String(s.clone());
+        let rows = strings.iter().map(|s| OrtValue::Object(Map::from([("v".to_string(), string(s))]))).collect();
+        let fields = strings.iter().enumerate().map(|(i, s)| (format!("k{}", i), string(s))).collect();
+        let value = OrtValue::Object(Map::from([
+            ("rows".to_string(), OrtValue::Array(rows)),
+            ("list".to_string(), OrtValue::Array(strings.iter().map(string).collect())),
+            ("object".to_string(), OrtValue::Object(fields)),
+            ("single".to_string(), string(&strings[0])),
+        ]));
+        let text = generate_ort(&value);
+        prop_assert_eq!(parse_ort(&text).map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, text)))?, value, "{}", text);
+    }
+
+    #[test]
+    fn top_level_arrays_and_scalars_round_trip(value in top_level()) {
+        let text = generate_ort(&value);
+        prop_assert_eq!(parse_ort(&text).map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, text)))?, value, "{}", text);
+    }
+
+    #[test]
+    fn unescape_inverts_escape(s in backslashes(), delimiter in prop::sample::select(vec![',', '|', '\t'])) {
+        let escaped = escape(&s, delimiter);
+        prop_assert_eq!(unescape(&escaped, delimiter), s);
+    }
+
+    #[test]
+    fn backslashes_round_trip_as_keys_and_values(s in backslashes()) {
+        let name = format!("k{}", s.trim());
+        let row = OrtValue::Object(Map::from([(name.clone(), OrtValue::String(s.clone()))]));
+        let value = OrtValue::Object(Map::from([
+            (name.clone(), OrtValue::Array(vec![row.clone(), row])),
+            (format!("{}.", name), OrtValue::String(s)),
+        ]));
+        let text = generate_ort(&value);
+        prop_assert_eq!(parse_ort(&text).map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, text)))?, value, "{}", text);
+    }
+
+    #[test]
+    fn leading_comment_char_round_trips(comment_char in prop::sample::select(vec![';', '%', '!', '#']), cells in prop::collection::vec(text(), 1..4)) {
+        let row = |cell: String| OrtValue::Object(Map::from([("v".to_string(), OrtValue::String(format!("{}{}", comment_char, cell)))]));
+        let value = OrtValue::Object(Map::from([("rows".to_string(), OrtValue::Array(cells.into_iter().map(row).collect()))]));
+        let text = generate_ort_with(&value, &GenerateOptions { comment_char, ..Default::default() }).unwrap();
+        let parsed = parse_ort_with(&text, &ParseOptions::new().comment_char(comment_char));
+        prop_assert_eq!(parsed.map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, text)))?, value);
+    }
+}
+
+/// Formatting every fixture keeps its value and changes nothing the second
+/// time, with `\n` line endings and no trailing whitespace
+#[test]
+fn fixtures_format_idempotently() {
+    let mut paths: Vec<_> = ["example", "tests/fixtures/format"]
+        .iter()
+        .flat_map(|dir| std::fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()))
+        .collect();
+    paths.sort();
+    for path in paths.iter().filter(|path| path.extension().is_some_and(|ext| ext == "ort")) {
+        let content = std::fs::read_to_string(path).unwrap();
+        let formatted = format(&content).unwrap();
+        assert_eq!(parse_ort(&formatted).unwrap(), parse_ort(&content).unwrap(), "{}", path.display());
+        assert_eq!(format(&formatted).unwrap(), formatted, "{}", path.display());
+        assert!(!formatted.contains('\r'), "{}", path.display());
+        assert!(formatted.lines().all(|line| line.trim_end() == line || line.trim_end().ends_with('\\')), "{}", path.display());
+    }
+}
+
+/// Formatting and editing a document parsed with another comment character
+/// quote values starting with it, so they don't turn into comments
+#[test]
+fn documents_keep_values_starting_with_their_comment_character() {
+    let options = ParseOptions::new().comment_char(';');
+    let content = "; Notes\nnotes:\n\";a\"\n\nusers:id,name:\n1,\";John\"\n# 2,Jane\n";
+    let doc = OrtDocument::parse_with(content, &options).unwrap();
+    let value = parse_ort_with(content, &options).unwrap();
+    assert_eq!(value["notes"].as_str(), Some(";a"));
+    assert_eq!(value["users"].as_array().unwrap().len(), 2);
+
+    let formatted = doc.formatted().unwrap();
+    assert_eq!(formatted, "; Notes\nnotes:\n\";a\"\n\nusers:id,name:\n1,\";John\"\n\\# 2,Jane\n");
+    assert_eq!(parse_ort_with(&formatted, &options).unwrap(), value);
+
+    let mut doc = OrtDocument::parse_with(content, &options).unwrap();
+    doc.set_cell("users", 1, "id", &OrtValue::from(";2")).unwrap();
+    let row = OrtValue::Object(Map::from([("id".to_string(), OrtValue::from(";3")), ("name".to_string(), OrtValue::from("Ann"))]));
+    doc.set_row("users", 0, &row).unwrap();
+    let value = parse_ort_with(&doc.to_string(), &options).unwrap();
+    assert_eq!(value["users"][0], row);
+    assert_eq!(value["users"][1]["id"].as_str(), Some(";2"));
+}
+
+/// Values ORT has no text for, which the strategies above leave out, read
+/// back as the nearest value it has
+#[test]
+fn values_without_ort_text_read_back_changed() {
+    let section = |key: &str, value: OrtValue| OrtValue::Object(Map::from([(key.to_string(), value)]));
+    let cases = [
+        // A section without data lines is an empty array
+        (section("k", OrtValue::Null), section("k", OrtValue::Array(vec![]))),
+        (OrtValue::Null, OrtValue::Array(vec![])),
+        // A section with an empty key is the header of a top-level value
+        (section("", OrtValue::from(1)), OrtValue::from(1)),
+        // Table rows missing a key read back with it null
+        (
+            OrtValue::Array(vec![section("a", OrtValue::from(1)), section("b", OrtValue::from(2))]),
+            OrtValue::Array(vec![
+                OrtValue::Object(Map::from([("a".to_string(), OrtValue::from(1)), ("b".to_string(), OrtValue::Null)])),
+                OrtValue::Object(Map::from([("a".to_string(), OrtValue::Null), ("b".to_string(), OrtValue::from(2))])),
+            ]),
+        ),
+    ];
+    for (value, read_back) in cases {
+        let text = generate_ort(&value);
+        assert_eq!(parse_ort(&text).unwrap(), read_back, "{}", text);
+    }
+}
+
+/// Field names ending in a type name read back as names, not typed fields
+#[test]
+fn field_names_like_typed_fields_round_trip() {
+    for name in ["size int", "a [str]", "n  float", "x\u{2003}bool", "int", "size \\ int"] {
+        let row = |n: i64| OrtValue::Object(Map::from([(name.to_string(), OrtValue::from(n)), ("id".to_string(), OrtValue::from("x"))]));
+        let value = OrtValue::Object(Map::from([("rows".to_string(), OrtValue::Array(vec![row(1), row(2)]))]));
+        let text = generate_ort(&value);
+        assert_eq!(parse_ort(&text).unwrap(), value, "{}", text);
+        let typed = generate_ort_with(&value, &GenerateOptions { typed_headers: true, ..Default::default() }).unwrap();
+        assert_eq!(parse_ort(&typed).unwrap(), value, "{}", typed);
+    }
+}
+
+/// JSON Lines read into a table and written back give the same table
+#[cfg(feature = "serde_json")]
+#[test]
+fn ndjson_fixture_round_trips() {
+    let file = std::fs::File::open("tests/fixtures/users.jsonl").unwrap();
+    let value = ort_rs::ndjson_to_ort(std::io::BufReader::new(file), Some("users")).unwrap();
+    let users = value["users"].as_array().unwrap();
+    assert_eq!(users.len(), 3);
+    assert_eq!(users[1]["name"].as_str(), Some("Jane, Jr."));
+    assert!(users[2]["active"].is_null());
+
+    let mut lines = vec![];
+    ort_rs::ort_to_ndjson(&value["users"], &mut lines, &ort_rs::NonFinite::Error).unwrap();
+    assert_eq!(lines.iter().filter(|&&byte| byte == b'\n').count(), 3);
+    assert_eq!(ort_rs::ndjson_to_ort(lines.as_slice(), Some("users")).unwrap(), value);
+    assert_eq!(parse_ort(&generate_ort(&value)).unwrap(), value);
+}
@@ -0,0 +1,125 @@
+//! Streams large tables through `OrtReader` and `OrtWriter`
+
+use ort_rs::{FieldSpec, Map, OrtReader, OrtValue, OrtWriter};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{self, BufReader, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Allocator that tracks the bytes in use and their peak
+struct Counting;
+
+static IN_USE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let in_use = IN_USE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(in_use, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        IN_USE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Reader producing a table of `rows` rows a line at a time, so the document
+/// is never held in memory as a whole
+struct Table {
+    rows: usize,
+    next: usize,
+    line: Vec<u8>,
+    pos: usize,
+}
+
+impl Table {
+    fn new(rows: usize) -> Self {
+        Table { rows, next: 0, line: b"events:id,name,tags,pos(lat,lng):\n".to_vec(), pos: 0 }
+    }
+}
+
+impl Read for Table {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos == self.line.len() {
+            if self.next == self.rows {
+                return Ok(0);
+            }
+            self.line.clear();
+            let row = self.next;
+            self.line.extend_from_slice(format!("{},event {},[a,b],({}.5,-{}.25)\n", row, row, row, row).as_bytes());
+            self.next += 1;
+            self.pos = 0;
+        }
+        let n = buf.len().min(self.line.len() - self.pos);
+        buf[..n].copy_from_slice(&self.line[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A million rows stream through in the memory of a few rows
+///
+/// Slow in debug builds; run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn million_rows_stream_in_bounded_memory() {
+    const ROWS: usize = 1_000_000;
+    let mut reader = OrtReader::from_reader(BufReader::new(Table::new(ROWS)));
+    let mut section = reader.next_section().unwrap().unwrap();
+    assert_eq!(section.key(), Some("events"));
+
+    let baseline = IN_USE.load(Ordering::Relaxed);
+    PEAK.store(baseline, Ordering::Relaxed);
+    let mut count = 0;
+    for (i, row) in section.by_ref().enumerate() {
+        let row = row.unwrap();
+        assert_eq!(row["id"], OrtValue::from(i as i64));
+        assert_eq!(row["pos"]["lng"], OrtValue::from(-(i as f64) - 0.25));
+        count += 1;
+    }
+    assert_eq!(count, ROWS);
+
+    // The document is about 40 MB; streaming it holds only a few rows
+    let growth = PEAK.load(Ordering::Relaxed) - baseline;
+    assert!(growth < 64 * 1024, "peak grew by {} bytes", growth);
+}
+
+/// A hundred thousand rows written one at a time read back unchanged,
+/// including cells that need escaping
+#[test]
+fn writer_rows_parse_back_identically() {
+    const ROWS: usize = 100_000;
+    let simple = |name: &str| FieldSpec::Simple(name.to_string());
+    let fields = [simple("id"), simple("name"), simple("tags"), FieldSpec::Nested("pos".to_string(), vec![simple("lat"), simple("lng")])];
+    let row = |i: usize| {
+        let pos = Map::from([("lat".to_string(), OrtValue::from(i as f64 + 0.5)), ("lng".to_string(), OrtValue::from(-(i as f64)))]);
+        vec![
+            OrtValue::from(i as i64),
+            OrtValue::from(format!("user {}, #{} (a:b) [c]\\", i, i)),
+            OrtValue::Array(vec![OrtValue::from("x"), OrtValue::from(i.is_multiple_of(2)), OrtValue::Null]),
+            OrtValue::Object(pos),
+        ]
+    };
+
+    let mut writer = OrtWriter::new(Vec::new());
+    writer.begin_section(Some("users"), &fields).unwrap();
+    for i in 0..ROWS {
+        writer.write_row(&row(i)).unwrap();
+    }
+    let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+    let value = ort_rs::parse_ort(&output).unwrap();
+    let users = value["users"].as_array().unwrap();
+    assert_eq!(users.len(), ROWS);
+    for (i, user) in users.iter().enumerate() {
+        let expected: Map<String, OrtValue> = fields.iter().map(|field| field.name().to_string()).zip(row(i)).collect();
+        assert_eq!(user, &OrtValue::Object(expected), "row {}", i);
+    }
+}
@@ -0,0 +1,171 @@
+//! Feeds hostile input to the parser, which must return an error rather than
+//! crash, run out of memory or read files it was not given
+
+use ort_rs::{from_file_with, parse_ort, parse_ort_borrowed, parse_ort_with, OrtErrorKind, OrtReader, ParseOptions};
+use proptest::prelude::*;
+use std::path::PathBuf;
+
+/// Lines mostly of the characters that open, close and separate nested values
+fn soup() -> impl Strategy<Value = String> {
+    let chars = vec!['(', '(', '[', '[', ')', ']', ',', ':', '\\', '"', '#', 'a', '1', ' ', '\n'];
+    prop::collection::vec(prop::sample::select(chars), 0..400).prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Openers for one level of nesting in a value, as an array or an inline
+/// object, or for two as an object in an array
+fn opener() -> impl Strategy<Value = &'static str> {
+    prop::sample::select(vec!["[", "(a:", "[(a:"])
+}
+
+/// Every entry point parses the same input to the same outcome, without panicking
+fn parse_everywhere(content: &str, options: &ParseOptions) -> Result<(), OrtErrorKind> {
+    let owned = parse_ort_with(content, options).map(|_| ()).map_err(|e| e.kind);
+    if options == &ParseOptions::default() {
+        assert_eq!(parse_ort_borrowed(content).map(|_| ()).map_err(|e| e.kind), owned);
+        let mut reader = OrtReader::from_reader(content.as_bytes());
+        while let Some(Ok(section)) = reader.next_section() {
+            section.for_each(drop);
+        }
+        let _ = ort_rs::parse_ort_all(content);
+    }
+    owned
+}
+
+proptest! {
+    #[test]
+    fn arbitrary_soup_never_panics(header in soup(), body in soup()) {
+        let _ = parse_everywhere(&format!("k:{}:\n{}", header, body), &ParseOptions::default());
+        let _ = parse_everywhere(&format!("k:\n{}", body), &ParseOptions::default());
+        let _ = parse_everywhere(&body, &ParseOptions::new().max_depth(4).max_line_length(64).max_rows(8));
+    }
+
+    #[test]
+    fn nesting_past_the_limit_is_an_error(openers in prop::collection::vec(opener(), 1..48), max_depth in 1usize..16) {
+        let depth = openers.iter().map(|o| o.matches(['(', '[']).count()).sum::<usize>();
+        let openers = openers.concat();
+        let closers = openers.chars().rev().filter_map(|c| match c {
+            '(' => Some(')'),
+            '[' => Some(']'),
+            _ => None,
+        });
+        let content = format!("k:\n{}1{}", openers, closers.collect::<String>());
+
+        let result = parse_everywhere(&content, &ParseOptions::new().max_depth(max_depth));
+        if depth > max_depth {
+            prop_assert_eq!(result, Err(OrtErrorKind::DepthLimit));
+        }
+        prop_assert_eq!(parse_everywhere(&content, &ParseOptions::default()), parse_ort(&content).map(|_| ()).map_err(|e| e.kind));
+    }
+}
+
+#[test]
+fn ten_thousand_open_brackets_hit_the_depth_limit() {
+    let n = 10_000;
+    let contents = [
+        format!("k:\n{}1{}", "[".repeat(n), "]".repeat(n)),
+        format!("k:\n{}1{}", "(a:".repeat(n), ")".repeat(n)),
+        format!("k:\n{}", "[(a:".repeat(n)),
+        format!("k:a{}:\n1", "(a".repeat(n)),
+        format!("k:a{}{}:\n1", "(a".repeat(n), ")".repeat(n)),
+        format!("k:id,tags:\n1,{}1{}", "[".repeat(n), "]".repeat(n)),
+    ];
+    for content in &contents {
+        assert_eq!(parse_everywhere(content, &ParseOptions::default()), Err(OrtErrorKind::DepthLimit), "{}", &content[..24]);
+    }
+
+    let error = parse_ort(&contents[0]).unwrap_err();
+    assert_eq!((error.line, error.message.as_str()), (2, "Nesting is deeper than the limit of 128 levels"));
+    assert!(parse_ort(&format!("k:\n{}1{}", "[".repeat(128), "]".repeat(128))).is_ok());
+}
+
+#[test]
+fn long_lines_and_many_rows_hit_their_limits() {
+    let rows = format!("k:id:\n{}", (0..1000).map(|i| i.to_string()).collect::<Vec<_>>().join("\n"));
+    let error = parse_ort_with(&rows, &ParseOptions::new().max_rows(100)).unwrap_err();
+    assert_eq!((error.kind, error.line, error.message.as_str()), (OrtErrorKind::InputLimit, 102, "More than 100 rows"));
+    assert!(parse_ort_with(&rows, &ParseOptions::new().max_rows(1000)).is_ok());
+
+    let line = format!("k:\n{}", "a".repeat(1 << 20));
+    let error = parse_ort_with(&line, &ParseOptions::new().max_line_length(4096)).unwrap_err();
+    assert_eq!((error.kind, error.line), (OrtErrorKind::InputLimit, 2));
+    assert_eq!(error.message, "Line is 1048576 bytes long, over the limit of 4096");
+}
+
+/// Directory of an uploaded file, beside a secret the upload must not read
+struct Upload {
+    root: PathBuf,
+}
+
+impl Upload {
+    fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("ort-untrusted-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(root.join("uploads/nested")).unwrap();
+        std::fs::write(root.join("secret.ort"), "secret:\nhunter2").unwrap();
+        std::fs::write(root.join("uploads/nested/rows.ort"), "rows:id:\n1").unwrap();
+        Upload { root }
+    }
+
+    fn parse(&self, content: &str) -> Result<ort_rs::OrtValue, ort_rs::OrtError> {
+        let path = self.root.join("uploads/main.ort");
+        std::fs::write(&path, content).unwrap();
+        from_file_with(&path, &ParseOptions::new().allow_includes(true))
+    }
+}
+
+impl Drop for Upload {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.root);
+    }
+}
+
+#[test]
+fn includes_stay_inside_the_uploaded_directory() {
+    let upload = Upload::new("paths");
+    let secret = upload.root.join("secret.ort");
+
+    let value = upload.parse("#include nested/rows.ort\nport:\n80").unwrap();
+    assert_eq!(value["rows"][0]["id"].as_i64(), Some(1));
+
+    let escapes = [
+        "../secret.ort".to_string(),
+        "nested/../../secret.ort".to_string(),
+        format!("<{}>", secret.display()),
+        format!("\"{}\"", secret.display()),
+    ];
+    for target in &escapes {
+        for directive in ["#include", "@include"] {
+            let error = upload.parse(&format!("{} {}\nport:\n80", directive, target)).unwrap_err();
+            assert_eq!((error.kind, error.line), (OrtErrorKind::IncludeOutsideDirectory, 1), "{}", target);
+            assert!(!error.to_string().contains("hunter2"));
+        }
+    }
+
+    // Without allow_includes the same lines are comments
+    let path = upload.root.join("uploads/main.ort");
+    assert!(from_file_with(&path, &ParseOptions::default()).unwrap().get("secret").is_none());
+
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&secret, upload.root.join("uploads/link.ort")).unwrap();
+        let error = upload.parse("#include link.ort").unwrap_err();
+        assert_eq!(error.kind, OrtErrorKind::IncludeOutsideDirectory);
+    }
+}
+
+#[test]
+fn circular_includes_are_reported_not_followed() {
+    let upload = Upload::new("cycles");
+    let uploads = upload.root.join("uploads");
+
+    assert_eq!(upload.parse("#include main.ort").unwrap_err().kind, OrtErrorKind::CircularInclude);
+
+    std::fs::write(uploads.join("nested/a.ort"), "#include b.ort\na:\n1").unwrap();
+    std::fs::write(uploads.join("nested/b.ort"), "#include a.ort\nb:\n2").unwrap();
+    let error = upload.parse("#include nested/a.ort").unwrap_err();
+    assert_eq!(error.kind, OrtErrorKind::CircularInclude);
+    assert_eq!(error.source_file.as_deref(), Some(uploads.join("nested/b.ort").as_path()));
+
+    // A file included twice side by side is no cycle, only a duplicate section
+    let error = upload.parse("#include nested/rows.ort\n#include nested/rows.ort").unwrap_err();
+    assert_eq!(error.kind, OrtErrorKind::DuplicateKey);
+}